@@ -0,0 +1,264 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::{
+    cell::{OnceCell, RefCell},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    rc::Rc,
+};
+use tempfile::TempDir;
+
+use tsuyu_ast as ast;
+use tsuyu_error::{error, ComposedResult, Error, ErrorKind, Result};
+use tsuyu_source::Source;
+
+use crate::{analyze, lower};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// C compiler driver. Used to assemble and link unless `assembler` or `linker` is set.
+    pub cc: PathBuf,
+    /// Assembler invoked as `<assembler> -o <obj> <asm>`.
+    pub assembler: Option<PathBuf>,
+    /// Linker invoked as `<linker> -o <exe> <obj> <link_args>`.
+    pub linker: Option<PathBuf>,
+    pub link_args: Vec<String>,
+    /// Keep intermediate files in this directory instead of removing them.
+    pub keep_temps: Option<PathBuf>,
+    /// Print the values of top-level expressions like OCaml's toplevel.
+    pub dump: bool,
+    /// Omit bounds checks of array accesses.
+    pub no_bounds_check: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            cc: PathBuf::from("cc"),
+            assembler: None,
+            linker: None,
+            link_args: Vec::new(),
+            keep_temps: None,
+            dump: false,
+            no_bounds_check: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Driver {
+    pub config: Config,
+    // Compiled on the first build and shared by the later ones.
+    runtime: OnceCell<Rc<Runtime>>,
+    // Warnings of the analyzed sources until they are taken.
+    warnings: RefCell<Vec<Error>>,
+}
+
+#[derive(Debug)]
+enum WorkDir {
+    Temp(TempDir),
+    Kept(PathBuf),
+}
+
+impl WorkDir {
+    fn path(&self) -> &Path {
+        match self {
+            WorkDir::Temp(dir) => dir.path(),
+            WorkDir::Kept(path) => path,
+        }
+    }
+}
+
+// The runtime object lives in its own work dir, which is removed with the driver.
+#[derive(Debug)]
+struct Runtime {
+    _dir: WorkDir,
+    obj_path: PathBuf,
+}
+
+fn io_error(what: &str, path: &Path, err: std::io::Error) -> Error {
+    error!(format!(
+        "Failed to {} {}: {}",
+        what,
+        path.to_string_lossy(),
+        err
+    ))
+}
+
+fn toolchain_error(command: String, message: String) -> Error {
+    Error::new(ErrorKind::Toolchain { command, message })
+}
+
+impl Driver {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            runtime: OnceCell::new(),
+            warnings: RefCell::default(),
+        }
+    }
+
+    /// Returns the warnings of the sources compiled since the last call.
+    pub fn take_warnings(&self) -> Vec<Error> {
+        self.warnings.take()
+    }
+
+    fn analyze(&self, source: Source) -> ComposedResult<ast::File> {
+        let (file, warnings) = analyze(source)?;
+        self.warnings.borrow_mut().extend(warnings);
+        Ok(file)
+    }
+
+    fn work_dir(&self) -> Result<WorkDir> {
+        match &self.config.keep_temps {
+            Some(path) => fs::create_dir_all(path)
+                .map(|_| WorkDir::Kept(path.clone()))
+                .map_err(|err| io_error("create", path, err)),
+            None => TempDir::new()
+                .map(WorkDir::Temp)
+                .map_err(|err| error!(format!("Failed to create tempdir: {}", err))),
+        }
+    }
+
+    fn exec(&self, program: &Path, args: &[&OsStr]) -> Result<()> {
+        let command = std::iter::once(program.as_os_str())
+            .chain(args.iter().copied())
+            .map(OsStr::to_string_lossy)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|err| toolchain_error(command.clone(), err.to_string()))?;
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = match stderr.trim_end() {
+            "" => output.status.to_string(),
+            stderr => format!("{}\n{}", output.status, stderr),
+        };
+        Err(toolchain_error(command, message))
+    }
+
+    fn assemble(&self, source: Source, dir: &WorkDir, output: &Path) -> ComposedResult<()> {
+        let asm_path = Self::temp_path(&source, dir, "s");
+        let asm = self.compile(source)?;
+        fs::write(&asm_path, asm).map_err(|err| vec![io_error("write", &asm_path, err)])?;
+
+        match &self.config.assembler {
+            Some(assembler) => self.exec(
+                assembler,
+                &["-o".as_ref(), output.as_os_str(), asm_path.as_os_str()],
+            ),
+            None => self.exec(
+                &self.config.cc,
+                &[
+                    "-c".as_ref(),
+                    "-o".as_ref(),
+                    output.as_os_str(),
+                    asm_path.as_os_str(),
+                ],
+            ),
+        }
+        .map_err(|err| vec![err])
+    }
+
+    // The runtime does not depend on the sources, so it is compiled only once per driver.
+    fn compile_runtime(&self) -> Result<&Path> {
+        if let Some(runtime) = self.runtime.get() {
+            return Ok(&runtime.obj_path);
+        }
+        let dir = self.work_dir()?;
+        let c_path = dir.path().join(tsuyu_runtime::FILE_NAME);
+        fs::write(&c_path, tsuyu_runtime::SOURCE).map_err(|err| io_error("write", &c_path, err))?;
+        let obj_path = c_path.with_extension("o");
+        self.exec(
+            &self.config.cc,
+            &[
+                "-c".as_ref(),
+                "-O2".as_ref(),
+                "-o".as_ref(),
+                obj_path.as_os_str(),
+                c_path.as_os_str(),
+            ],
+        )?;
+        let runtime = self.runtime.get_or_init(|| {
+            Rc::new(Runtime {
+                _dir: dir,
+                obj_path,
+            })
+        });
+        Ok(&runtime.obj_path)
+    }
+
+    fn link(&self, obj_paths: &[&Path], output: &Path) -> Result<()> {
+        let linker = self.config.linker.as_ref().unwrap_or(&self.config.cc);
+        let mut args = vec!["-o".as_ref(), output.as_os_str()];
+        args.extend(obj_paths.iter().map(|path| path.as_os_str()));
+        args.extend(self.config.link_args.iter().map(OsStr::new));
+        self.exec(linker, &args)
+    }
+
+    // Sources not read from files are named like `<stdin>`. Their files are named `a` like the
+    // default output of the CLI.
+    fn temp_path(source: &Source, dir: &WorkDir, ext: &str) -> PathBuf {
+        let stem = source
+            .path
+            .file_stem()
+            .filter(|stem| !stem.to_string_lossy().starts_with('<'))
+            .unwrap_or_else(|| "a".as_ref());
+        dir.path().join(stem).with_extension(ext)
+    }
+
+    pub fn emit_ir(&self, source: Source) -> ComposedResult<String> {
+        self.analyze(source)
+            .map(|file| lower(file, self.config.dump).to_string())
+    }
+
+    pub fn compile(&self, source: Source) -> ComposedResult<String> {
+        let file = self.analyze(source)?;
+        let options = tsuyuir_codegen::x86_64::Options {
+            no_bounds_check: self.config.no_bounds_check,
+        };
+        Ok(tsuyuir_codegen::x86_64::compile(
+            &lower(file, self.config.dump),
+            &options,
+        ))
+    }
+
+    pub fn build_object(&self, source: Source, output: &Path) -> ComposedResult<()> {
+        let dir = self.work_dir().map_err(|err| vec![err])?;
+        self.assemble(source, &dir, output)
+    }
+
+    fn build_in(&self, source: Source, dir: &WorkDir, output: &Path) -> ComposedResult<()> {
+        let obj_path = Self::temp_path(&source, dir, "o");
+        self.assemble(source, dir, &obj_path)?;
+        self.compile_runtime()
+            .and_then(|runtime_path| self.link(&[&obj_path, runtime_path], output))
+            .map_err(|err| vec![err])
+    }
+
+    pub fn build(&self, source: Source, output: &Path) -> ComposedResult<()> {
+        let dir = self.work_dir().map_err(|err| vec![err])?;
+        self.build_in(source, &dir, output)
+    }
+
+    pub fn run(&self, source: Source) -> ComposedResult<Output> {
+        let dir = self.work_dir().map_err(|err| vec![err])?;
+        let bin_path = Self::temp_path(&source, &dir, "out");
+        self.build_in(source, &dir, &bin_path)?;
+
+        Command::new(&bin_path)
+            .output()
+            .map_err(|err| vec![io_error("execute", &bin_path, err)])
+    }
+}
@@ -4,58 +4,63 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::{fs::File, io::Write, process::Command, rc::Rc};
-use tempfile::TempDir;
+use std::{path::Path, rc::Rc};
 
 use tsuyu_ast::{self as ast};
-use tsuyu_error::ComposedResult;
+use tsuyu_error::{ComposedResult, Error};
 use tsuyu_lexer::tokenize;
+use tsuyu_matching::compile_matches;
 use tsuyu_parser::parse;
+use tsuyu_resolver::resolve;
 use tsuyu_source::Source;
-use tsuyuir as ir;
-use tsuyuir::IR;
+use tsuyu_typing::infer;
 
-pub fn compile(source: Source) -> ComposedResult<String> {
+mod driver;
+mod lower;
+pub use driver::*;
+use lower::lower;
+
+// Warnings are returned with the file since they do not stop the compilation.
+fn analyze(source: Source) -> ComposedResult<(ast::File, Vec<Error>)> {
     let s = Rc::new(source);
-    let file = parse(tokenize(&s))?;
+    let mut file = parse(tokenize(&s))?;
+    resolve(&file)?;
+    infer(&mut file)?;
+    let warnings = compile_matches(&mut file);
+    Ok((file, warnings))
+}
+
+/// Returns the warnings if the source has no errors.
+pub fn check(source: Source) -> ComposedResult<Vec<Error>> {
+    analyze(source).map(|(_, warnings)| warnings)
+}
 
-    let mut ir = IR::new();
-    let main = ir.create_function("main").unwrap();
-    for stmt in file.stmts {
-        match stmt {
-            ast::Stmt::Expr(expr) => main.body.push(ir::Stmt::Dump(match expr {
-                ast::Expr::IntLiteral(n) => ir::Expr::Immediate(tsuyuir::Value::Int(n)),
-            })),
-        }
-    }
-    main.body
-        .push(ir::Stmt::Return(ir::Expr::Immediate(tsuyuir::Value::Int(
-            0,
-        ))));
+pub fn emit_tokens(source: Source) -> String {
+    let s = Rc::new(source);
+    tokenize(&s).map(|tok| format!("{}\n", tok)).collect()
+}
+
+pub fn emit_ast(source: Source) -> ComposedResult<String> {
+    let s = Rc::new(source);
+    parse(tokenize(&s)).map(|file| file.to_string())
+}
+
+pub fn emit_ir(source: Source) -> ComposedResult<String> {
+    Driver::default().emit_ir(source)
+}
+
+pub fn compile(source: Source) -> ComposedResult<String> {
+    Driver::default().compile(source)
+}
+
+pub fn build_object(source: Source, output: &Path) -> ComposedResult<()> {
+    Driver::default().build_object(source, output)
+}
 
-    Ok(tsuyuir_codegen::x86_64::compile(&ir))
+pub fn build(source: Source, output: &Path) -> ComposedResult<()> {
+    Driver::default().build(source, output)
 }
 
 pub fn run(source: Source) -> ComposedResult<std::process::Output> {
-    let asm = compile(source)?;
-
-    let tempdir = TempDir::new().expect("failed to create tempdir");
-    let asm_path = tempdir.path().join("a.S");
-    let bin_path = tempdir.path().join("a.out");
-
-    let mut asm_file = File::create(&asm_path).expect("failed to create asm file");
-    asm_file
-        .write_all(asm.as_bytes())
-        .expect("failed to write asm");
-
-    Command::new("cc")
-        .args(["-o", bin_path.to_str().unwrap(), asm_path.to_str().unwrap()])
-        .status()
-        .expect("failed to wait on child")
-        .success()
-        .then(|| ())
-        .expect("assemble error");
-    Ok(Command::new(bin_path)
-        .output()
-        .expect("failed to execute compiled binary"))
+    Driver::default().run(source)
 }
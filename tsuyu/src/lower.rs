@@ -0,0 +1,1136 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tsuyu_ast as ast;
+use tsuyu_source::Loc;
+use tsuyuir::{self as ir, IR};
+
+fn location(loc: &Loc) -> ir::Location {
+    ir::Location {
+        path: loc
+            .source()
+            .map(|s| s.path.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        line: loc.line,
+        column: loc.column,
+    }
+}
+
+fn ir_type(typ: &ast::Type) -> ir::Type {
+    match typ {
+        ast::Type::Unit => ir::Type::Unit,
+        ast::Type::Int => ir::Type::Int,
+        ast::Type::Bool => ir::Type::Bool,
+        ast::Type::String => ir::Type::String,
+        ast::Type::Char => ir::Type::Char,
+        ast::Type::Var(_) => ir::Type::Poly,
+        ast::Type::Fun(..) => ir::Type::Fun,
+        ast::Type::Tuple(types) => ir::Type::Tuple(types.iter().map(ir_type).collect()),
+        ast::Type::List(elem) => ir::Type::List(Box::new(ir_type(elem))),
+        ast::Type::Ref(elem) => ir::Type::Ref(Box::new(ir_type(elem))),
+        ast::Type::Array(elem) => ir::Type::Array(Box::new(ir_type(elem))),
+        // Declarations are numbered in the same order in the IR.
+        ast::Type::Named(_, id) => ir::Type::Defined(*id),
+    }
+}
+
+fn expr_type(expr: &ast::Expr) -> ir::Type {
+    ir_type(expr.typ.as_ref().expect("untyped expression"))
+}
+
+// Whether values of the type are blocks compared by the runtime. Functions are passed to the
+// runtime to raise `Invalid_argument` like OCaml. Values of polymorphic types are compared as words
+// since the typing rejects comparisons which would look at them.
+fn is_compared_structurally(typ: &ir::Type) -> bool {
+    matches!(
+        typ,
+        ir::Type::Tuple(_)
+            | ir::Type::List(_)
+            | ir::Type::Ref(_)
+            | ir::Type::Array(_)
+            | ir::Type::Defined(_)
+            | ir::Type::Fun
+    )
+}
+
+// Types of the parameters and the result of the function taking `arity` arguments.
+fn signature(typ: &ast::Type, arity: usize) -> (Vec<ir::Type>, ir::Type) {
+    let mut params = Vec::new();
+    let mut typ = typ;
+    for _ in 0..arity {
+        let ast::Type::Fun(param, ret) = typ else {
+            unreachable!("applied non-function type");
+        };
+        params.push(ir_type(param));
+        typ = ret;
+    }
+    (params, ir_type(typ))
+}
+
+// Parameters and body of a function binding. `let f = fun x -> e` is same as `let f x = e`.
+fn function_parts(binding: &ast::Binding) -> Option<(&[ast::Pattern], &ast::Expr)> {
+    match &binding.value.kind {
+        _ if !binding.params.is_empty() => Some((&binding.params, &binding.value)),
+        ast::ExprKind::Fun(params, body) => Some((params, body)),
+        _ => None,
+    }
+}
+
+// Names which are free in `fun params -> body` in order of appearance.
+fn free_vars<'a>(params: &'a [ast::Pattern], body: &'a ast::Expr) -> Vec<&'a str> {
+    let mut bound: Vec<_> = params.iter().flat_map(ast::Pattern::names).collect();
+    let mut free = Vec::new();
+    collect_free_vars(body, &mut bound, &mut free);
+    free
+}
+
+fn collect_free_vars<'a>(expr: &'a ast::Expr, bound: &mut Vec<&'a str>, free: &mut Vec<&'a str>) {
+    match &expr.kind {
+        ast::ExprKind::UnitLiteral
+        | ast::ExprKind::IntLiteral(_)
+        | ast::ExprKind::BoolLiteral(_)
+        | ast::ExprKind::StringLiteral(_)
+        | ast::ExprKind::CharLiteral(_)
+        | ast::ExprKind::Constr(_, None) => {}
+        ast::ExprKind::Var(name) => {
+            if !bound.contains(&name.as_str()) && !free.contains(&name.as_str()) {
+                free.push(name);
+            }
+        }
+        ast::ExprKind::Let(group, body) => {
+            let len = bound.len();
+            let names = group.bindings.iter().flat_map(|b| b.pattern.names());
+            if group.rec {
+                bound.extend(names.clone());
+            }
+            for binding in &group.bindings {
+                let len = bound.len();
+                bound.extend(binding.params.iter().flat_map(ast::Pattern::names));
+                collect_free_vars(&binding.value, bound, free);
+                bound.truncate(len);
+            }
+            if !group.rec {
+                bound.extend(names);
+            }
+            collect_free_vars(body, bound, free);
+            bound.truncate(len);
+        }
+        ast::ExprKind::Apply(func, args) => {
+            collect_free_vars(func, bound, free);
+            for arg in args {
+                collect_free_vars(arg, bound, free);
+            }
+        }
+        ast::ExprKind::Fun(params, body) => {
+            let len = bound.len();
+            bound.extend(params.iter().flat_map(ast::Pattern::names));
+            collect_free_vars(body, bound, free);
+            bound.truncate(len);
+        }
+        ast::ExprKind::Tuple(exprs) | ast::ExprKind::List(exprs) | ast::ExprKind::Array(exprs) => {
+            for expr in exprs {
+                collect_free_vars(expr, bound, free);
+            }
+        }
+        ast::ExprKind::If(cond, then, els) => {
+            collect_free_vars(cond, bound, free);
+            collect_free_vars(then, bound, free);
+            collect_free_vars(els, bound, free);
+        }
+        ast::ExprKind::Record(fields) => {
+            for (_, expr) in fields {
+                collect_free_vars(expr, bound, free);
+            }
+        }
+        ast::ExprKind::With(base, fields) => {
+            collect_free_vars(base, bound, free);
+            for (_, expr) in fields {
+                collect_free_vars(expr, bound, free);
+            }
+        }
+        ast::ExprKind::UnOp(_, operand)
+        | ast::ExprKind::Field(operand, _)
+        | ast::ExprKind::Constr(_, Some(operand)) => collect_free_vars(operand, bound, free),
+        ast::ExprKind::BinOp(_, lhs, rhs)
+        | ast::ExprKind::Seq(lhs, rhs)
+        | ast::ExprKind::SetField(lhs, _, rhs)
+        | ast::ExprKind::Index(lhs, rhs) => {
+            collect_free_vars(lhs, bound, free);
+            collect_free_vars(rhs, bound, free);
+        }
+        ast::ExprKind::SetIndex(array, index, value) => {
+            collect_free_vars(array, bound, free);
+            collect_free_vars(index, bound, free);
+            collect_free_vars(value, bound, free);
+        }
+        ast::ExprKind::Match(scrutinee, cases, _) => {
+            collect_free_vars(scrutinee, bound, free);
+            for case in cases {
+                let len = bound.len();
+                bound.extend(case.pattern.names());
+                if let Some(guard) = &case.guard {
+                    collect_free_vars(guard, bound, free);
+                }
+                collect_free_vars(&case.body, bound, free);
+                bound.truncate(len);
+            }
+        }
+    }
+}
+
+// Names of the pattern with the expressions reading their parts of the value.
+fn pattern_parts<'a>(
+    pattern: &'a ast::Pattern,
+    value: ir::Expr,
+    parts: &mut Vec<(&'a str, ir::Expr)>,
+) {
+    match &pattern.kind {
+        ast::PatternKind::Wildcard | ast::PatternKind::Unit => {}
+        ast::PatternKind::Var(name) => parts.push((name, value)),
+        ast::PatternKind::Tuple(pats) => {
+            for (i, pat) in pats.iter().enumerate() {
+                pattern_parts(pat, ir::Expr::field(value.clone(), i), parts);
+            }
+        }
+        // The alias follows the names of the pattern like `Pattern::names`.
+        ast::PatternKind::Alias(pat, name) => {
+            pattern_parts(pat, value.clone(), parts);
+            parts.push((name, value));
+        }
+        // Matched only by decision trees.
+        ast::PatternKind::IntLiteral(_)
+        | ast::PatternKind::BoolLiteral(_)
+        | ast::PatternKind::StringLiteral(_)
+        | ast::PatternKind::CharLiteral(_)
+        | ast::PatternKind::CharRange(..)
+        | ast::PatternKind::List(_)
+        | ast::PatternKind::Cons(..)
+        | ast::PatternKind::Constr(..)
+        | ast::PatternKind::Or(..) => unreachable!("refutable pattern outside match"),
+    }
+}
+
+// Reads the part of the value in the slot at the occurrence.
+fn occurrence(slot: usize, occurrence: &[usize]) -> ir::Expr {
+    occurrence
+        .iter()
+        .fold(ir::Expr::Local(slot), |value, i| ir::Expr::field(value, *i))
+}
+
+// Wraps the body in the lets in order.
+fn let_all(lets: Vec<(usize, ir::Expr)>, body: ir::Expr) -> ir::Expr {
+    lets.into_iter().rev().fold(body, |body, (slot, value)| {
+        ir::Expr::let_(slot, value, body)
+    })
+}
+
+#[derive(Clone)]
+enum Binding {
+    Local(usize),
+    Global(String),
+    /// Top-level function called by the symbol with the number of parameters.
+    Function(String, usize),
+    Builtin(ast::Builtin),
+}
+
+// Index of the first captured value in a closure block.
+const CLOSURE_CAPTURED: usize = 2;
+
+fn closure_block(symbol: String, arity: usize, captured: Vec<ir::Expr>) -> ir::Expr {
+    let mut values = vec![
+        ir::Expr::FuncAddr(symbol),
+        ir::Expr::Immediate(ir::Value::Int(arity as i64)),
+    ];
+    values.extend(captured);
+    ir::Expr::Alloc(values)
+}
+
+// Calls the runtime function taking one argument.
+fn runtime_call(name: &str, arg: ir::Expr, typ: ir::Type) -> ir::Expr {
+    ir::Expr::Call(name.to_string(), vec![arg], typ)
+}
+
+// Adds `delta` to the contents of the ref. The ref is read twice.
+fn ref_add(r: ir::Expr, delta: i64) -> ir::Expr {
+    let contents = ir::Expr::field(r.clone(), 0);
+    let value = ir::Expr::binop(
+        ir::BinOp::Add,
+        contents,
+        ir::Expr::Immediate(ir::Value::Int(delta)),
+    );
+    ir::Expr::set_field(r, 0, value)
+}
+
+// Index of the length in an array block. The elements follow it.
+const ARRAY_LENGTH: usize = 0;
+
+// Applies the builtin to the arguments. Builtins other than `Array.make` take one argument.
+// The argument must be a local if `builtin_reads_twice` holds.
+fn builtin_apply(builtin: ast::Builtin, mut args: Vec<ir::Expr>) -> ir::Expr {
+    if builtin == ast::Builtin::ArrayMake {
+        // The runtime allocates the block of the length.
+        let typ = ir::Type::Array(Box::new(ir::Type::Poly));
+        return ir::Expr::Call("tsuyu_array_make".to_string(), args, typ);
+    }
+    let arg = args.pop().unwrap();
+    match builtin {
+        ast::Builtin::Not => ir::Expr::unop(ir::UnOp::Not, arg),
+        // Chars are the codes in words.
+        ast::Builtin::CharCode => arg,
+        ast::Builtin::CharChr => runtime_call("tsuyu_char_chr", arg, ir::Type::Char),
+        ast::Builtin::StringOfInt => runtime_call("tsuyu_string_of_int", arg, ir::Type::String),
+        ast::Builtin::StringOfBool => ir::Expr::if_(
+            arg,
+            ir::Expr::Immediate(ir::Value::String(b"true".to_vec())),
+            ir::Expr::Immediate(ir::Value::String(b"false".to_vec())),
+        ),
+        ast::Builtin::PrintInt => runtime_call("tsuyu_print_int", arg, ir::Type::Unit),
+        ast::Builtin::PrintChar => runtime_call("tsuyu_print_char", arg, ir::Type::Unit),
+        ast::Builtin::PrintString => runtime_call("tsuyu_print_string", arg, ir::Type::Unit),
+        ast::Builtin::PrintEndline => runtime_call("tsuyu_print_endline", arg, ir::Type::Unit),
+        // The argument is the unit value.
+        ast::Builtin::PrintNewline => runtime_call("tsuyu_print_newline", arg, ir::Type::Unit),
+        ast::Builtin::Fst => ir::Expr::field(arg, 0),
+        ast::Builtin::Snd => ir::Expr::field(arg, 1),
+        ast::Builtin::ListHd => runtime_call("tsuyu_list_hd", arg, ir::Type::Poly),
+        ast::Builtin::ListTl => runtime_call(
+            "tsuyu_list_tl",
+            arg,
+            ir::Type::List(Box::new(ir::Type::Poly)),
+        ),
+        // Refs are blocks of the contents.
+        ast::Builtin::Ref => ir::Expr::Alloc(vec![arg]),
+        ast::Builtin::Incr => ref_add(arg, 1),
+        ast::Builtin::Decr => ref_add(arg, -1),
+        ast::Builtin::ArrayLength => ir::Expr::field(arg, ARRAY_LENGTH),
+        ast::Builtin::ArrayMake => unreachable!(),
+    }
+}
+
+fn builtin_reads_twice(builtin: ast::Builtin) -> bool {
+    matches!(builtin, ast::Builtin::Incr | ast::Builtin::Decr)
+}
+
+/// Functions of the whole program.
+#[derive(Default)]
+struct Program {
+    ir: IR,
+    symbol_count: usize,
+}
+
+impl Program {
+    // Names may be shadowed. So symbols are numbered to be unique.
+    fn symbol(&mut self, name: &str) -> String {
+        self.symbol_count += 1;
+        format!("tsuyu.{}.{}", name.replace('\'', "_"), self.symbol_count)
+    }
+
+    // Definition of the declared type of the expression.
+    fn type_def(&self, expr: &ast::Expr) -> &ir::TypeDef {
+        let Some(ast::Type::Named(_, id)) = &expr.typ else {
+            unreachable!("expression of non-declared type");
+        };
+        &self.ir.types[*id]
+    }
+
+    // Builtins are defined as functions only when they are used as values.
+    fn builtin_function(&mut self, builtin: ast::Builtin) -> String {
+        let symbol = format!("tsuyu.{}", builtin.name());
+        if !self.ir.functions.contains_key(&symbol) {
+            let (params, ret) = signature(&builtin.typ(), builtin.arity());
+            let args = (0..builtin.arity()).map(ir::Expr::Local).collect();
+            let body = builtin_apply(builtin, args);
+            let func = self.ir.create_function(&symbol, params, ret).unwrap();
+            func.body.push(ir::Stmt::Return(body));
+        }
+        symbol
+    }
+}
+
+/// Lowers a function body. Names must be resolved beforehand.
+///
+/// Functions in the body are lifted to top-level functions which take the closure as the last
+/// argument. Captured variables are copied into the closure when it is created.
+struct Lower<'a, 'p> {
+    program: &'p mut Program,
+    scopes: Vec<(&'a str, Binding)>,
+    // Slots in use. Slots are reused after the binding goes out of scope.
+    slots: usize,
+    locals: usize,
+}
+
+impl<'a, 'p> Lower<'a, 'p> {
+    // Top-level bindings are visible in every function.
+    fn new(program: &'p mut Program, scopes: &[(&'a str, Binding)]) -> Self {
+        Self {
+            program,
+            scopes: scopes
+                .iter()
+                .filter(|(_, b)| !matches!(b, Binding::Local(_)))
+                .cloned()
+                .collect(),
+            slots: 0,
+            locals: 0,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> &Binding {
+        let (_, binding) = self
+            .scopes
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .expect("unresolved name");
+        binding
+    }
+
+    fn alloc_slot(&mut self) -> usize {
+        let slot = self.slots;
+        self.slots += 1;
+        self.locals = self.locals.max(self.slots);
+        slot
+    }
+
+    fn bind_local(&mut self, name: &'a str) -> usize {
+        let slot = self.alloc_slot();
+        self.scopes.push((name, Binding::Local(slot)));
+        slot
+    }
+
+    // Binds the names of the pattern to the value in the slot. Parts of tuples are copied to new
+    // slots by the lets.
+    fn bind_pattern(
+        &mut self,
+        pattern: &'a ast::Pattern,
+        slot: usize,
+        lets: &mut Vec<(usize, ir::Expr)>,
+    ) {
+        if let ast::PatternKind::Var(name) = &pattern.kind {
+            self.scopes.push((name, Binding::Local(slot)));
+            return;
+        }
+        let mut parts = Vec::new();
+        pattern_parts(pattern, ir::Expr::Local(slot), &mut parts);
+        for (name, part) in parts {
+            lets.push((self.bind_local(name), part));
+        }
+    }
+
+    // Lowers the body of a function whose parameters are in the slots.
+    fn function_body(
+        &mut self,
+        params: &'a [ast::Pattern],
+        slots: &[usize],
+        body: &'a ast::Expr,
+    ) -> ir::Expr {
+        let mut lets = Vec::new();
+        for (param, slot) in params.iter().zip(slots) {
+            self.bind_pattern(param, *slot, &mut lets);
+        }
+        let body = self.expr(body);
+        let_all(lets, body)
+    }
+
+    // Returns the expression creating the closure and the names captured in it.
+    // `self_name` refers to the closure itself in the body.
+    fn closure(
+        &mut self,
+        name: &str,
+        params: &'a [ast::Pattern],
+        body: &'a ast::Expr,
+        typ: &ast::Type,
+        self_name: Option<&'a str>,
+    ) -> (ir::Expr, Vec<&'a str>) {
+        let captured: Vec<_> = free_vars(params, body)
+            .into_iter()
+            .filter(|name| Some(*name) != self_name)
+            .filter_map(|name| match self.lookup(name) {
+                Binding::Local(slot) => Some((name, *slot)),
+                _ => None,
+            })
+            .collect();
+        let symbol = self.program.symbol(name);
+
+        let mut lower = Lower::new(self.program, &self.scopes);
+        let param_slots: Vec<_> = params.iter().map(|_| lower.alloc_slot()).collect();
+        let env = lower.bind_local(self_name.unwrap_or_default());
+        let slots: Vec<_> = captured
+            .iter()
+            .map(|(name, _)| lower.bind_local(name))
+            .collect();
+        let value = lower.function_body(params, &param_slots, body);
+        let value = slots
+            .iter()
+            .enumerate()
+            .rev()
+            .fold(value, |value, (i, slot)| {
+                let captured = ir::Expr::field(ir::Expr::Local(env), CLOSURE_CAPTURED + i);
+                ir::Expr::let_(*slot, captured, value)
+            });
+        let locals = lower.locals;
+
+        let (mut param_types, ret) = signature(typ, params.len());
+        param_types.push(ir::Type::Fun);
+        let func = self
+            .program
+            .ir
+            .create_function(&symbol, param_types, ret)
+            .unwrap();
+        func.locals = locals;
+        func.body.push(ir::Stmt::Return(value));
+
+        let values = captured
+            .iter()
+            .map(|(_, slot)| ir::Expr::Local(*slot))
+            .collect();
+        let names = captured.into_iter().map(|(name, _)| name).collect();
+        (closure_block(symbol, params.len(), values), names)
+    }
+
+    fn binding_value(&mut self, binding: &'a ast::Binding) -> ir::Expr {
+        match function_parts(binding) {
+            Some((params, body)) => {
+                let typ = binding.typ.as_ref().expect("untyped binding");
+                let name = binding.name().unwrap_or("fun");
+                self.closure(name, params, body, typ, None).0
+            }
+            None => self.expr(&binding.value),
+        }
+    }
+
+    // Closures in the group are created before captured siblings are filled in.
+    fn let_rec(&mut self, group: &'a ast::BindingGroup, body: &'a ast::Expr) -> ir::Expr {
+        let slots: Vec<_> = group
+            .bindings
+            .iter()
+            .map(|binding| self.bind_local(binding.name().expect("let rec of non-variable")))
+            .collect();
+        let mut values = Vec::new();
+        let mut patches = Vec::new();
+        for (i, binding) in group.bindings.iter().enumerate() {
+            let (params, value) = function_parts(binding).expect("let rec of non-function");
+            let typ = binding.typ.as_ref().expect("untyped binding");
+            let name = binding.name().unwrap();
+            let (mut closure, captured) = self.closure(name, params, value, typ, Some(name));
+            let ir::Expr::Alloc(fields) = &mut closure else {
+                unreachable!();
+            };
+            for (field, name) in captured.into_iter().enumerate() {
+                let later = group.bindings[i + 1..]
+                    .iter()
+                    .position(|b| b.name() == Some(name));
+                if let Some(j) = later {
+                    let field = CLOSURE_CAPTURED + field;
+                    fields[field] = ir::Expr::Immediate(ir::Value::Int(0));
+                    patches.push(ir::Expr::set_field(
+                        ir::Expr::Local(slots[i]),
+                        field,
+                        ir::Expr::Local(slots[i + 1 + j]),
+                    ));
+                }
+            }
+            values.push(closure);
+        }
+        let body = self.expr(body);
+        self.scopes.truncate(self.scopes.len() - slots.len());
+        self.slots -= slots.len();
+
+        let body = patches
+            .into_iter()
+            .rev()
+            .fold(body, |body, patch| ir::Expr::seq(patch, body));
+        slots
+            .into_iter()
+            .zip(values)
+            .rev()
+            .fold(body, |body, (slot, value)| {
+                ir::Expr::let_(slot, value, body)
+            })
+    }
+
+    // Binds the names to the parts of the value in the slot. The whole value is not copied.
+    fn bind_occurrences(
+        &mut self,
+        bindings: &'a [(String, ast::Occurrence)],
+        slot: usize,
+    ) -> Vec<(usize, ir::Expr)> {
+        let mut lets = Vec::new();
+        for (name, occ) in bindings {
+            if occ.is_empty() {
+                self.scopes.push((name, Binding::Local(slot)));
+            } else {
+                lets.push((self.bind_local(name), occurrence(slot, occ)));
+            }
+        }
+        lets
+    }
+
+    // Lowers the decision tree of the match of the value in the slot.
+    fn decision(
+        &mut self,
+        decision: &'a ast::Decision,
+        slot: usize,
+        cases: &'a [ast::Case],
+        loc: &ir::Location,
+    ) -> ir::Expr {
+        let (occ, typ, branches, default) = match decision {
+            ast::Decision::Fail => return ir::Expr::MatchFailure(loc.clone()),
+            ast::Decision::Leaf(case, bindings) => {
+                let (scopes, slots) = (self.scopes.len(), self.slots);
+                let lets = self.bind_occurrences(bindings, slot);
+                let body = self.expr(&cases[*case].body);
+                self.scopes.truncate(scopes);
+                self.slots = slots;
+                return let_all(lets, body);
+            }
+            // The names are bound for both the guard and the body. The fallback cannot see them.
+            ast::Decision::Guard(case, bindings, fallback) => {
+                let (scopes, slots) = (self.scopes.len(), self.slots);
+                let lets = self.bind_occurrences(bindings, slot);
+                let guard = self.expr(cases[*case].guard.as_ref().expect("guard of a case"));
+                let body = self.expr(&cases[*case].body);
+                self.scopes.truncate(scopes);
+                self.slots = slots;
+                let fallback = self.decision(fallback, slot, cases, loc);
+                return let_all(lets, ir::Expr::if_(guard, body, fallback));
+            }
+            ast::Decision::Switch(occ, typ, branches, default) => (occ, typ, branches, default),
+        };
+        let value = occurrence(slot, occ);
+        let mut heads = Vec::new();
+        let mut exprs = Vec::new();
+        for (head, branch) in branches {
+            heads.push(head);
+            exprs.push(self.decision(branch, slot, cases, loc));
+        }
+        let mut default = default
+            .as_ref()
+            .map(|branch| self.decision(branch, slot, cases, loc));
+        if exprs.len() == 1 && default.is_none() {
+            return exprs.pop().unwrap();
+        }
+
+        match typ {
+            // Constant constructors are numbered first and the others follow them by their tags.
+            // The table maps the numbers to the branches with the default at the end.
+            ast::Type::Named(_, id) => {
+                let ir::TypeDefKind::Variant(constrs) = &self.program.ir.types[*id].kind else {
+                    unreachable!("match on a record");
+                };
+                let (constant, block): (Vec<_>, Vec<_>) =
+                    constrs.iter().partition(|(_, args)| args.is_empty());
+                let n_const = constant.len() as i64;
+                let table = constant
+                    .iter()
+                    .chain(&block)
+                    .map(|(name, _)| {
+                        heads
+                            .iter()
+                            .position(|head| **head == ast::Head::Constr(name.clone()))
+                            .unwrap_or(exprs.len())
+                    })
+                    .collect();
+                let tag = ir::Expr::field(value.clone(), 0);
+                let key = if block.is_empty() {
+                    value
+                } else if constant.is_empty() {
+                    tag
+                } else {
+                    // Blocks are pointers which are greater than any constant constructor.
+                    ir::Expr::if_(
+                        ir::Expr::binop(
+                            ir::BinOp::Lt,
+                            value.clone(),
+                            ir::Expr::Immediate(ir::Value::Int(n_const)),
+                        ),
+                        value,
+                        ir::Expr::binop(
+                            ir::BinOp::Add,
+                            tag,
+                            ir::Expr::Immediate(ir::Value::Int(n_const)),
+                        ),
+                    )
+                };
+                exprs.extend(default);
+                ir::Expr::switch(key, table, exprs)
+            }
+            ast::Type::Bool | ast::Type::List(_) => {
+                let (yes, no) = if *typ == ast::Type::Bool {
+                    (ast::Head::Bool(true), ast::Head::Bool(false))
+                } else {
+                    (ast::Head::Nil, ast::Head::Cons)
+                };
+                let cond = if *typ == ast::Type::Bool {
+                    value
+                } else {
+                    let nil = ir::Expr::Immediate(ir::Value::Nil);
+                    ir::Expr::binop(ir::BinOp::Eq, value, nil)
+                };
+                // At most one of the heads is missing and it takes the default.
+                let mut branches: Vec<_> = heads.into_iter().zip(exprs).collect();
+                let mut branch = |head| match branches.iter().position(|(h, _)| **h == head) {
+                    Some(i) => branches.swap_remove(i).1,
+                    None => default.take().expect("incomplete switch"),
+                };
+                let then = branch(yes);
+                ir::Expr::if_(cond, then, branch(no))
+            }
+            // Literals are compared in order and the default is taken if none is equal.
+            _ => heads.into_iter().zip(exprs).rev().fold(
+                default.expect("switch on literals without default"),
+                |els, (head, then)| {
+                    // `lo <= value && value <= hi`.
+                    if let ast::Head::CharRange(lo, hi) = head {
+                        let bound = |op, c| {
+                            let c = ir::Expr::Immediate(ir::Value::Char(c));
+                            ir::Expr::binop(op, value.clone(), c)
+                        };
+                        let cond = ir::Expr::if_(
+                            bound(ir::BinOp::Ge, *lo),
+                            bound(ir::BinOp::Le, *hi),
+                            ir::Expr::Immediate(ir::Value::Bool(false)),
+                        );
+                        return ir::Expr::if_(cond, then, els);
+                    }
+                    let lit = match head {
+                        ast::Head::Int(n) => ir::Value::Int(*n),
+                        ast::Head::Char(c) => ir::Value::Char(*c),
+                        ast::Head::String(s) => ir::Value::String(s.clone()),
+                        _ => unreachable!("constructor head of a literal type"),
+                    };
+                    let (lhs, rhs) = if let ir::Value::String(_) = lit {
+                        let compare = ir::Expr::Call(
+                            "tsuyu_string_compare".to_string(),
+                            vec![value.clone(), ir::Expr::Immediate(lit)],
+                            ir::Type::Int,
+                        );
+                        (compare, ir::Value::Int(0))
+                    } else {
+                        (value.clone(), lit)
+                    };
+                    let cond = ir::Expr::binop(ir::BinOp::Eq, lhs, ir::Expr::Immediate(rhs));
+                    ir::Expr::if_(cond, then, els)
+                },
+            ),
+        }
+    }
+
+    fn expr(&mut self, expr: &'a ast::Expr) -> ir::Expr {
+        match &expr.kind {
+            ast::ExprKind::UnitLiteral => ir::Expr::Immediate(ir::Value::Unit),
+            ast::ExprKind::IntLiteral(n) => ir::Expr::Immediate(ir::Value::Int(*n)),
+            ast::ExprKind::BoolLiteral(b) => ir::Expr::Immediate(ir::Value::Bool(*b)),
+            ast::ExprKind::StringLiteral(s) => ir::Expr::Immediate(ir::Value::String(s.clone())),
+            ast::ExprKind::CharLiteral(c) => ir::Expr::Immediate(ir::Value::Char(*c)),
+            ast::ExprKind::Var(name) => match self.lookup(name).clone() {
+                Binding::Local(slot) => ir::Expr::Local(slot),
+                Binding::Global(symbol) => ir::Expr::Global(symbol),
+                // Top-level functions ignore the closure passed as the last argument.
+                Binding::Function(symbol, arity) => closure_block(symbol, arity, Vec::new()),
+                Binding::Builtin(builtin) => {
+                    let symbol = self.program.builtin_function(builtin);
+                    closure_block(symbol, builtin.arity(), Vec::new())
+                }
+            },
+            ast::ExprKind::Let(group, body) if group.rec => self.let_rec(group, body),
+            ast::ExprKind::Let(group, body) => {
+                let (scopes, slots) = (self.scopes.len(), self.slots);
+                // Slots are taken before the next value so that it does not overwrite them.
+                let mut lets = Vec::new();
+                for binding in &group.bindings {
+                    let value = self.binding_value(binding);
+                    lets.push((self.alloc_slot(), value));
+                }
+                for (i, binding) in group.bindings.iter().enumerate() {
+                    let slot = lets[i].0;
+                    self.bind_pattern(&binding.pattern, slot, &mut lets);
+                }
+                let body = self.expr(body);
+                self.scopes.truncate(scopes);
+                self.slots = slots;
+                let_all(lets, body)
+            }
+            ast::ExprKind::Apply(func, args) => {
+                let binding = match &func.kind {
+                    ast::ExprKind::Var(name) => Some(self.lookup(name).clone()),
+                    _ => None,
+                };
+                let mut args: Vec<_> = args.iter().map(|arg| self.expr(arg)).collect();
+                // Known functions applied to enough arguments are called directly. The result is
+                // applied to the rest of the arguments.
+                match binding {
+                    Some(Binding::Function(symbol, arity)) if args.len() == arity => {
+                        ir::Expr::Call(symbol, args, expr_type(expr))
+                    }
+                    Some(Binding::Function(symbol, arity)) if args.len() > arity => {
+                        let rest = args.split_off(arity);
+                        let result = ir::Expr::Call(symbol, args, ir::Type::Fun);
+                        ir::Expr::Apply(Box::new(result), rest)
+                    }
+                    Some(Binding::Builtin(builtin)) if args.len() == builtin.arity() => {
+                        if builtin_reads_twice(builtin) && !matches!(args[0], ir::Expr::Local(_)) {
+                            let slot = self.alloc_slot();
+                            self.slots -= 1;
+                            let body = builtin_apply(builtin, vec![ir::Expr::Local(slot)]);
+                            ir::Expr::let_(slot, args.pop().unwrap(), body)
+                        } else {
+                            builtin_apply(builtin, args)
+                        }
+                    }
+                    _ => ir::Expr::Apply(Box::new(self.expr(func)), args),
+                }
+            }
+            ast::ExprKind::Fun(params, body) => {
+                let typ = expr.typ.as_ref().expect("untyped expression");
+                self.closure("fun", params, body, typ, None).0
+            }
+            // Tuples are blocks of the elements.
+            ast::ExprKind::Tuple(exprs) => {
+                ir::Expr::Alloc(exprs.iter().map(|expr| self.expr(expr)).collect())
+            }
+            // Lists are built from the last cons cell.
+            ast::ExprKind::List(exprs) => exprs
+                .iter()
+                .rev()
+                .fold(ir::Expr::Immediate(ir::Value::Nil), |tail, expr| {
+                    ir::Expr::Alloc(vec![self.expr(expr), tail])
+                }),
+            ast::ExprKind::Array(exprs) => {
+                let mut values = vec![ir::Expr::Immediate(ir::Value::Int(exprs.len() as i64))];
+                values.extend(exprs.iter().map(|expr| self.expr(expr)));
+                ir::Expr::Alloc(values)
+            }
+            ast::ExprKind::Constr(name, arg) => {
+                let def = self.program.type_def(expr);
+                let tag = ir::Expr::Immediate(ir::Value::Int(def.constr_tag(name).unwrap() as i64));
+                let ir::TypeDefKind::Variant(constrs) = &def.kind else {
+                    unreachable!();
+                };
+                let arity = constrs.iter().find(|(n, _)| n == name).unwrap().1.len();
+                match arg.as_deref() {
+                    None => tag,
+                    // Arguments are stored in the block after the tag without a tuple.
+                    Some(ast::Expr {
+                        kind: ast::ExprKind::Tuple(exprs),
+                        ..
+                    }) if arity > 1 => {
+                        let mut values = vec![tag];
+                        values.extend(exprs.iter().map(|expr| self.expr(expr)));
+                        ir::Expr::Alloc(values)
+                    }
+                    Some(arg) => ir::Expr::Alloc(vec![tag, self.expr(arg)]),
+                }
+            }
+            ast::ExprKind::Index(array, index) => {
+                ir::Expr::load(self.expr(array), self.expr(index), location(&expr.loc))
+            }
+            ast::ExprKind::SetIndex(array, index, value) => ir::Expr::store(
+                self.expr(array),
+                self.expr(index),
+                self.expr(value),
+                location(&expr.loc),
+            ),
+            // Fields are evaluated in the order of the layout. OCaml leaves the order unspecified.
+            ast::ExprKind::Record(fields) => {
+                let def = self.program.type_def(expr);
+                let mut values: Vec<_> = fields
+                    .iter()
+                    .map(|(label, value)| (def.field_index(&label.name).unwrap(), value))
+                    .collect();
+                values.sort_by_key(|(index, _)| *index);
+                ir::Expr::Alloc(values.into_iter().map(|(_, v)| self.expr(v)).collect())
+            }
+            // Copies the record with the fields replaced.
+            ast::ExprKind::With(base, fields) => {
+                let def = self.program.type_def(expr);
+                let ir::TypeDefKind::Record(all) = &def.kind else {
+                    unreachable!();
+                };
+                let len = all.len();
+                let indices: Vec<_> = fields
+                    .iter()
+                    .map(|(label, _)| def.field_index(&label.name).unwrap())
+                    .collect();
+                let base = self.expr(base);
+                let slot = self.alloc_slot();
+                let values = (0..len)
+                    .map(|i| match indices.iter().position(|j| *j == i) {
+                        Some(k) => self.expr(&fields[k].1),
+                        None => ir::Expr::field(ir::Expr::Local(slot), i),
+                    })
+                    .collect();
+                self.slots -= 1;
+                ir::Expr::let_(slot, base, ir::Expr::Alloc(values))
+            }
+            ast::ExprKind::Field(record, label) => {
+                let index = self.program.type_def(record).field_index(&label.name);
+                ir::Expr::field(self.expr(record), index.unwrap())
+            }
+            ast::ExprKind::SetField(record, label, value) => {
+                let index = self.program.type_def(record).field_index(&label.name);
+                ir::Expr::set_field(self.expr(record), index.unwrap(), self.expr(value))
+            }
+            ast::ExprKind::If(cond, then, els) => {
+                ir::Expr::if_(self.expr(cond), self.expr(then), self.expr(els))
+            }
+            ast::ExprKind::Match(scrutinee, cases, decision) => {
+                let value = self.expr(scrutinee);
+                let slot = self.alloc_slot();
+                let decision = decision.as_ref().expect("uncompiled match");
+                let body = self.decision(decision, slot, cases, &location(&expr.loc));
+                self.slots -= 1;
+                ir::Expr::let_(slot, value, body)
+            }
+            ast::ExprKind::UnOp(ast::UnOp::Deref, r) => ir::Expr::field(self.expr(r), 0),
+            ast::ExprKind::UnOp(op, expr) => ir::Expr::unop(
+                match op {
+                    ast::UnOp::Neg => ir::UnOp::Neg,
+                    ast::UnOp::Deref => unreachable!(),
+                },
+                self.expr(expr),
+            ),
+            // Short-circuit operators are conditionals.
+            ast::ExprKind::BinOp(ast::BinOp::And, lhs, rhs) => ir::Expr::if_(
+                self.expr(lhs),
+                self.expr(rhs),
+                ir::Expr::Immediate(ir::Value::Bool(false)),
+            ),
+            ast::ExprKind::BinOp(ast::BinOp::Or, lhs, rhs) => ir::Expr::if_(
+                self.expr(lhs),
+                ir::Expr::Immediate(ir::Value::Bool(true)),
+                self.expr(rhs),
+            ),
+            ast::ExprKind::Seq(first, second) => ir::Expr::seq(self.expr(first), self.expr(second)),
+            ast::ExprKind::BinOp(ast::BinOp::Cons, head, tail) => {
+                ir::Expr::Alloc(vec![self.expr(head), self.expr(tail)])
+            }
+            ast::ExprKind::BinOp(ast::BinOp::Append, lhs, rhs) => ir::Expr::Call(
+                "tsuyu_list_append".to_string(),
+                vec![self.expr(lhs), self.expr(rhs)],
+                expr_type(expr),
+            ),
+            ast::ExprKind::BinOp(ast::BinOp::Assign, lhs, rhs) => {
+                ir::Expr::set_field(self.expr(lhs), 0, self.expr(rhs))
+            }
+            ast::ExprKind::BinOp(ast::BinOp::Concat, lhs, rhs) => ir::Expr::Call(
+                "tsuyu_string_concat".to_string(),
+                vec![self.expr(lhs), self.expr(rhs)],
+                ir::Type::String,
+            ),
+            ast::ExprKind::BinOp(op, lhs, rhs) => {
+                let typ = expr_type(lhs);
+                let (lhs, rhs) = if typ == ir::Type::String {
+                    // Strings are compared by contents. The result of the runtime is compared to 0.
+                    let compare = ir::Expr::Call(
+                        "tsuyu_string_compare".to_string(),
+                        vec![self.expr(lhs), self.expr(rhs)],
+                        ir::Type::Int,
+                    );
+                    (compare, ir::Expr::Immediate(ir::Value::Int(0)))
+                } else if is_compared_structurally(&typ) {
+                    let loc = location(&expr.loc);
+                    let compare = ir::Expr::compare(typ, self.expr(lhs), self.expr(rhs), loc);
+                    (compare, ir::Expr::Immediate(ir::Value::Int(0)))
+                } else {
+                    (self.expr(lhs), self.expr(rhs))
+                };
+                ir::Expr::binop(
+                    match op {
+                        ast::BinOp::Add => ir::BinOp::Add,
+                        ast::BinOp::Sub => ir::BinOp::Sub,
+                        ast::BinOp::Mul => ir::BinOp::Mul,
+                        ast::BinOp::Div => ir::BinOp::Div(location(&expr.loc)),
+                        ast::BinOp::Mod => ir::BinOp::Mod(location(&expr.loc)),
+                        // Immediate values are compared as words.
+                        ast::BinOp::Eq => ir::BinOp::Eq,
+                        ast::BinOp::Ne => ir::BinOp::Ne,
+                        ast::BinOp::Lt => ir::BinOp::Lt,
+                        ast::BinOp::Le => ir::BinOp::Le,
+                        ast::BinOp::Gt => ir::BinOp::Gt,
+                        ast::BinOp::Ge => ir::BinOp::Ge,
+                        ast::BinOp::Concat
+                        | ast::BinOp::Cons
+                        | ast::BinOp::Append
+                        | ast::BinOp::Assign
+                        | ast::BinOp::And
+                        | ast::BinOp::Or => unreachable!(),
+                    },
+                    lhs,
+                    rhs,
+                )
+            }
+        }
+    }
+}
+
+struct TopLevel<'a, 'p> {
+    // Lowers statements into main. Its scopes are the top-level scopes.
+    main: Lower<'a, 'p>,
+    body: Vec<ir::Stmt>,
+    // Prints the values of expressions like OCaml's toplevel.
+    dump: bool,
+}
+
+impl<'a, 'p> TopLevel<'a, 'p> {
+    fn stmt(&mut self, stmt: &'a ast::Stmt) {
+        match stmt {
+            ast::Stmt::Expr(expr) if self.dump => {
+                let typ = expr_type(expr);
+                let expr = self.main.expr(expr);
+                self.body.push(ir::Stmt::Dump(typ, expr));
+            }
+            // Evaluated only for the effects like `let _ = expr`.
+            ast::Stmt::Expr(expr) => {
+                let expr = self.main.expr(expr);
+                self.body.push(ir::Stmt::Eval(expr));
+            }
+            ast::Stmt::Let(group) => self.group(group),
+            ast::Stmt::Type(decls) => {
+                for decl in decls {
+                    let kind = match &decl.kind {
+                        ast::TypeDeclKind::Record(fields) => ir::TypeDefKind::Record(
+                            fields
+                                .iter()
+                                .map(|field| {
+                                    let typ = field.typ.as_ref().expect("untyped field");
+                                    (field.label.name.clone(), ir_type(typ))
+                                })
+                                .collect(),
+                        ),
+                        ast::TypeDeclKind::Variant(constrs) => ir::TypeDefKind::Variant(
+                            constrs
+                                .iter()
+                                .map(|constr| {
+                                    let typs = constr.typs.as_ref().expect("untyped constructor");
+                                    (constr.name.clone(), typs.iter().map(ir_type).collect())
+                                })
+                                .collect(),
+                        ),
+                    };
+                    self.main.program.ir.types.push(ir::TypeDef {
+                        name: decl.name.clone(),
+                        kind,
+                    });
+                }
+            }
+        }
+    }
+
+    fn group(&mut self, group: &'a ast::BindingGroup) {
+        // Bindings of the names of each binding.
+        let bindings: Vec<Vec<_>> = group
+            .bindings
+            .iter()
+            .map(|binding| match binding.name() {
+                Some(name) if !binding.params.is_empty() => {
+                    let symbol = self.main.program.symbol(name);
+                    vec![(name, Binding::Function(symbol, binding.params.len()))]
+                }
+                _ => binding
+                    .pattern
+                    .names()
+                    .into_iter()
+                    .map(|name| (name, Binding::Global(self.main.program.symbol(name))))
+                    .collect(),
+            })
+            .collect();
+
+        // Functions can refer to each other in any order since they are called by symbol.
+        if group.rec {
+            for (name, b) in bindings.iter().flatten() {
+                self.main.scopes.push((name, b.clone()));
+            }
+        }
+        for (binding, names) in group.bindings.iter().zip(&bindings) {
+            match (&binding.pattern.kind, names.as_slice()) {
+                (_, [(_, Binding::Function(symbol, _))]) => self.function(symbol, binding),
+                (ast::PatternKind::Var(_), [(_, Binding::Global(symbol))]) => {
+                    let value = self.main.binding_value(binding);
+                    self.main.program.ir.globals.insert(symbol.clone());
+                    self.body.push(ir::Stmt::SetGlobal(symbol.clone(), value));
+                }
+                (ast::PatternKind::Tuple(_) | ast::PatternKind::Alias(..), _) => {
+                    self.destructure(binding, names)
+                }
+                // Evaluated only for the effects like `let _ = expr`.
+                _ => {
+                    let value = self.main.binding_value(binding);
+                    self.body.push(ir::Stmt::Eval(value));
+                }
+            }
+        }
+        if !group.rec {
+            for (name, b) in bindings.into_iter().flatten() {
+                self.main.scopes.push((name, b));
+            }
+        }
+    }
+
+    // Stores the value to a hidden global and its parts to the globals of the names.
+    fn destructure(&mut self, binding: &'a ast::Binding, names: &[(&'a str, Binding)]) {
+        let value = self.main.binding_value(binding);
+        let symbol = self.main.program.symbol("tuple");
+        self.main.program.ir.globals.insert(symbol.clone());
+        self.body.push(ir::Stmt::SetGlobal(symbol.clone(), value));
+
+        let mut parts = Vec::new();
+        pattern_parts(&binding.pattern, ir::Expr::Global(symbol), &mut parts);
+        for ((_, part), (_, b)) in parts.into_iter().zip(names) {
+            let Binding::Global(symbol) = b else {
+                unreachable!();
+            };
+            self.main.program.ir.globals.insert(symbol.clone());
+            self.body.push(ir::Stmt::SetGlobal(symbol.clone(), part));
+        }
+    }
+
+    fn function(&mut self, symbol: &str, binding: &'a ast::Binding) {
+        let mut lower = Lower::new(self.main.program, &self.main.scopes);
+        let slots: Vec<_> = binding.params.iter().map(|_| lower.alloc_slot()).collect();
+        let value = lower.function_body(&binding.params, &slots, &binding.value);
+        let locals = lower.locals;
+
+        let typ = binding.typ.as_ref().expect("untyped binding");
+        let (params, ret) = signature(typ, binding.params.len());
+        let func = self
+            .main
+            .program
+            .ir
+            .create_function(symbol, params, ret)
+            .unwrap();
+        func.locals = locals;
+        func.body.push(ir::Stmt::Return(value));
+    }
+}
+
+/// Lowers the file to IR. Values of top-level expressions are printed if `dump` is set.
+pub(crate) fn lower(file: ast::File, dump: bool) -> IR {
+    let mut program = Program::default();
+
+    // Top-level statements are run in main.
+    let mut top = TopLevel {
+        main: Lower::new(&mut program, &[]),
+        body: Vec::new(),
+        dump,
+    };
+    for builtin in ast::Builtin::ALL {
+        top.main
+            .scopes
+            .push((builtin.name(), Binding::Builtin(builtin)));
+    }
+    for stmt in &file.stmts {
+        top.stmt(stmt);
+    }
+    let mut body = top.body;
+    body.push(ir::Stmt::Return(ir::Expr::Immediate(ir::Value::Int(0))));
+    let locals = top.main.locals;
+
+    let mut ir = program.ir;
+    let main = ir.create_function("main", vec![], ir::Type::Int).unwrap();
+    main.locals = locals;
+    main.body = body;
+    ir
+}
@@ -1,7 +1,12 @@
 use insta::{assert_toml_snapshot, glob};
 use tsuyu_source::Source;
 use serde::Serialize;
-use std::{path::Path, process};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+use tsuyu::{Config, Driver};
+use tsuyu_error::ErrorKind;
 
 mod tsuyu_testutil {
     use super::*;
@@ -10,7 +15,7 @@ mod tsuyu_testutil {
             snapshot_path => dir,
             prepend_module_to_snapshot => false,
             snapshot_suffix => "",
-            input_file => path.to_path_buf(),
+            input_file => path.to_owned(),
         }, {
             let name = path.file_name().unwrap().to_string_lossy();
             f(&name, path);
@@ -34,6 +39,281 @@ fn correct_sources() {
 #[test]
 fn compile_error() {
     tsuyu::compile(Source::inline("xx")).expect_err("");
+
+    let errors = tsuyu::compile(Source::dummy("let.ml", "let x = 1 in\ny + x")).expect_err("");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        ["let.ml:2:1 Unbound value y"]
+    );
+
+    let errors = tsuyu::compile(Source::dummy(
+        "int.ml",
+        "1 + 4611686018427387904 * 2\n0x1_0000_0000_0000_0000",
+    ))
+    .expect_err("");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        ["int.ml:2:1 Integer literal exceeds the range of representable integers of type int"]
+    );
+
+    let errors = tsuyu::compile(Source::dummy(
+        "if.ml",
+        "if 1 then true else false;;
+1 + not true",
+    ))
+    .expect_err("");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        [
+            "if.ml:1:4 This expression has type int but an expression was expected of type bool",
+            "if.ml:2:5 This expression has type bool but an expression was expected of type int",
+        ]
+    );
+
+    let errors =
+        tsuyu::compile(Source::dummy("eq.ml", "let eq x y = x = y;;\neq [1] [1]")).expect_err("");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        ["eq.ml:1:14 Values of the polymorphic type 'a cannot be compared"]
+    );
+
+    let errors = tsuyu::compile(Source::dummy(
+        "record.ml",
+        "type t = { a : int; b : int };;\n{ a = 1 };;\n{ a = 1; b = 2; a = 3 };;\n\
+         fun r -> r.b <- 1",
+    ))
+    .expect_err("");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        [
+            "record.ml:2:1 Some record fields are undefined: b",
+            "record.ml:3:17 The record field a is defined several times",
+            "record.ml:4:10 The record field b is not mutable",
+        ]
+    );
+
+    let errors = tsuyu::compile(Source::dummy(
+        "variant.ml",
+        "type t = A | B of int * int;;\nB 1;;\nC",
+    ))
+    .expect_err("");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        [
+            "variant.ml:2:1 The constructor B expects 2 argument(s), but is applied here to 1 argument(s)",
+            "variant.ml:3:1 Unbound constructor C",
+        ]
+    );
+}
+
+#[test]
+fn division_by_zero() {
+    for code in ["1 + 10 / (2 - 2)", "1 + 10 mod (2 - 2)"] {
+        let output = Output::from(tsuyu::run(Source::dummy("div.ml", code)).unwrap());
+        assert_eq!(output.stdout_text, "");
+        assert_eq!(
+            output.stderr_text,
+            "div.ml:1:5 Fatal error: exception Division_by_zero\n"
+        );
+        assert_eq!(output.code, Some(2));
+    }
+
+    // Paths are embedded in the executable byte by byte.
+    let path = "dir\\\"割り算\".ml";
+    let output = Output::from(tsuyu::run(Source::dummy(path, "1 / 0")).unwrap());
+    assert_eq!(
+        output.stderr_text,
+        format!("{}:1:1 Fatal error: exception Division_by_zero\n", path)
+    );
+}
+
+#[test]
+fn compare_functional_value() {
+    let code = "let f = fun x -> x + 1;; print_int (f 1);; f = f";
+    let output = Output::from(tsuyu::run(Source::dummy("fun.ml", code)).unwrap());
+    assert_eq!(output.stdout_text, "2");
+    assert_eq!(
+        output.stderr_text,
+        "fun.ml:1:44 Fatal error: exception Invalid_argument(\"compare: functional value\")\n"
+    );
+    assert_eq!(output.code, Some(2));
+}
+
+#[test]
+fn invalid_argument() {
+    for code in ["Char.chr 256", "let f = Char.chr in f (-1)"] {
+        let output = Output::from(tsuyu::run(Source::dummy("chr.ml", code)).unwrap());
+        assert_eq!(output.stdout_text, "");
+        assert_eq!(
+            output.stderr_text,
+            "Fatal error: exception Invalid_argument(\"Char.chr\")\n"
+        );
+        assert_eq!(output.code, Some(2));
+    }
+}
+
+#[test]
+fn index_out_of_bounds() {
+    for (code, stdout) in [
+        ("let a = [| 1; 2 |];;\nprint_int a.(1);; a.(2)", "2"),
+        ("let a = [| 1; 2 |];;\nprint_int a.(1);; a.(-1) <- 0", "2"),
+        ("let a = Array.make 2 0;;\nprint_int a.(1);; [||].(0)", "0"),
+    ] {
+        let output = Output::from(tsuyu::run(Source::dummy("array.ml", code)).unwrap());
+        assert_eq!(output.stdout_text, stdout);
+        assert_eq!(
+            output.stderr_text,
+            "array.ml:2:19 Fatal error: exception Invalid_argument(\"index out of bounds\")\n"
+        );
+        assert_eq!(output.code, Some(2));
+    }
+
+    let output = Output::from(tsuyu::run(Source::dummy("array.ml", "Array.make (-1) 0")).unwrap());
+    assert_eq!(
+        output.stderr_text,
+        "Fatal error: exception Invalid_argument(\"Array.make\")\n"
+    );
+
+    // Accesses in bounds work without the checks.
+    let driver = Driver::new(Config {
+        no_bounds_check: true,
+        ..Config::default()
+    });
+    let asm = driver
+        .compile(Source::dummy(
+            "array.ml",
+            "let a = [| 1 |];; a.(0) <- a.(0) + 1",
+        ))
+        .unwrap();
+    assert!(!asm.contains("tsuyu_raise_index_out_of_bounds"));
+    let output = Output::from(
+        driver
+            .run(Source::dummy(
+                "array.ml",
+                "let a = [| 1 |];; a.(0) <- a.(0) + 1;; print_int a.(0)",
+            ))
+            .unwrap(),
+    );
+    assert_eq!(output.stdout_text, "2");
+}
+
+#[test]
+fn match_failure() {
+    let code = "type t = A | B of int | C;;\nlet f x = match x with A -> 1 | B 0 -> 2;;\n\
+                print_int (f A + f (B 0));; f C";
+    let output = Output::from(tsuyu::run(Source::dummy("match.ml", code)).unwrap());
+    assert_eq!(output.stdout_text, "3");
+    assert_eq!(
+        output.stderr_text,
+        "match.ml:2:11 Fatal error: exception Match_failure\n"
+    );
+    assert_eq!(output.code, Some(2));
+
+    // Values for which every guard fails are not matched either.
+    let code = "let g n = match n with n when n > 0 -> 1 | 0 -> 0;;\n\
+                print_int (g 3 + g 0); print_newline ();;\nprint_int (g (-1))";
+    let output = Output::from(tsuyu::run(Source::dummy("guard.ml", code)).unwrap());
+    assert_eq!(output.stdout_text, "1\n");
+    assert_eq!(
+        output.stderr_text,
+        "guard.ml:1:11 Fatal error: exception Match_failure\n"
+    );
+    assert_eq!(output.code, Some(2));
+
+    // Constructors are selected by a jump table.
+    let asm = tsuyu::compile(Source::inline(
+        "type t = A | B of int | C of int;; fun x -> match x with A -> 0 | B n -> n | C n -> -n",
+    ))
+    .unwrap();
+    assert!(asm.contains("jmp rax"));
+}
+
+#[test]
+fn tail_call() {
+    // Each loop overflows the stack unless calls in tail position reuse the frame.
+    let code = "let n = 10_000_000
+let print n = print_int n; print_newline ()
+let rec loop i acc = if i = n then acc else loop (i + 1) (acc + 1);;
+print (loop 0 0);;
+let rec even i = if i = 0 then true else odd (i - 1)
+and odd i = if i = 0 then false else even (i - 1);;
+print_endline (string_of_bool (even n));;
+let count n = let rec go i = if i < n then go (i + 1) else i in go 0;;
+print (count n);;
+let rec many a b c d e f g i = if i = 0 then a + g else many a b c d e f (g + 1) (i - 1);;
+print (many 1 2 3 4 5 6 7 n);;
+let count_many n =
+  let rec go a b c d e f g i = if i = n then i + g else go a b c d e f g (i + 1) in
+  go 1 2 3 4 5 6 7 0;;
+print (count_many n);;
+let rec print_loop i = if i < n then (print_string \"\"; print_loop (i + 1)) else print i;;
+print_loop 0";
+    let output = Output::from(tsuyu::run(Source::dummy("tail.ml", code)).unwrap());
+    assert_eq!(output.stderr_text, "");
+    assert_eq!(
+        output.stdout_text,
+        "10000000\ntrue\n10000000\n10000008\n10000007\n10000000\n"
+    );
+    assert_eq!(output.code, Some(0));
+}
+
+#[test]
+fn check() {
+    tsuyu::check(Source::inline("1;; 2")).unwrap();
+    tsuyu::check(Source::inline("xx")).expect_err("");
+
+    let warnings = tsuyu::check(Source::dummy(
+        "match.ml",
+        "let f l = match l with [] -> 0 | [(1, _)] -> 1 | [(_, true)] -> 2 | [(1, true)] -> 3",
+    ))
+    .unwrap();
+    assert_eq!(
+        warnings.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        [
+            "match.ml:1:11 Warning 8 [partial-match]: this pattern-matching is not exhaustive.\n\
+             Here is an example of a case that is not matched:\n(1, _) :: _ :: _",
+            "match.ml:1:69 Warning 11 [redundant-case]: this match case is unused.",
+        ]
+    );
+
+    // Warnings are kept by the driver until they are taken.
+    let driver = Driver::default();
+    driver
+        .compile(Source::inline("match true with true -> 0"))
+        .unwrap();
+    assert_eq!(driver.take_warnings().len(), 1);
+    assert!(driver.take_warnings().is_empty());
+}
+
+#[test]
+fn emit() {
+    assert_eq!(
+        tsuyu::emit_tokens(Source::inline("1 23")),
+        "1:1 IntLiteral(1)\n1:3 IntLiteral(23)\n"
+    );
+    assert_eq!(
+        tsuyu::emit_ast(Source::inline("1")).unwrap(),
+        "(expr (int 1))\n"
+    );
+    assert_eq!(
+        tsuyu::emit_ir(Source::inline("1")).unwrap(),
+        "function main(): int {\n    eval 1\n    return 0\n}\n"
+    );
+    // The AST is emitted before name resolution.
+    assert_eq!(
+        tsuyu::emit_ast(Source::inline("xx")).unwrap(),
+        "(expr (var xx))\n"
+    );
+    tsuyu::emit_ast(Source::inline("(")).expect_err("");
+}
+
+#[test]
+fn build() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("a.out");
+    tsuyu::build(Source::inline("print_int 1;; print_endline \"2\""), &path).unwrap();
+    let output = process::Command::new(&path).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12\n");
 }
 
 #[derive(Serialize)]
@@ -65,3 +345,138 @@ impl From<process::Output> for Output {
         }
     }
 }
+
+#[test]
+fn driver_missing_assembler() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let driver = Driver::new(Config {
+        assembler: Some(PathBuf::from("tsuyu-no-such-assembler")),
+        ..Config::default()
+    });
+    let errs = driver
+        .build(Source::inline("1"), &dir.path().join("a.out"))
+        .unwrap_err();
+    assert_eq!(errs.len(), 1);
+    match &errs[0].kind {
+        ErrorKind::Toolchain { command, .. } => {
+            assert!(command.starts_with("tsuyu-no-such-assembler -o "))
+        }
+        kind => panic!("unexpected error: {:?}", kind),
+    }
+}
+
+#[test]
+fn driver_link_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let driver = Driver::new(Config {
+        link_args: vec!["-ltsuyu_no_such_library".to_string()],
+        ..Config::default()
+    });
+    let errs = driver
+        .build(Source::inline("1"), &dir.path().join("a.out"))
+        .unwrap_err();
+    match &errs[0].kind {
+        ErrorKind::Toolchain { command, message } => {
+            assert!(command.ends_with(" -ltsuyu_no_such_library"));
+            assert!(message.contains("tsuyu_no_such_library"));
+        }
+        kind => panic!("unexpected error: {:?}", kind),
+    }
+}
+
+#[test]
+fn driver_keep_temps() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let driver = Driver::new(Config {
+        keep_temps: Some(dir.path().to_path_buf()),
+        ..Config::default()
+    });
+    let output = driver
+        .run(Source::dummy("prog.ml", "print_endline \"1\""))
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    for name in ["prog.s", "prog.o", "prog.out"] {
+        assert!(dir.path().join(name).exists(), "{} is not kept", name);
+    }
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let driver = Driver::new(Config {
+        keep_temps: Some(dir.path().to_path_buf()),
+        ..Config::default()
+    });
+    driver.run(Source::dummy("<stdin>", "1")).unwrap();
+    for name in ["a.s", "a.o", "a.out"] {
+        assert!(dir.path().join(name).exists(), "{} is not kept", name);
+    }
+}
+
+#[test]
+fn driver_compiles_runtime_once() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let driver = Driver::new(Config {
+        keep_temps: Some(dir.path().to_path_buf()),
+        ..Config::default()
+    });
+    driver.run(Source::dummy("a.ml", "print_int 1")).unwrap();
+    let runtime = dir.path().join("tsuyu_runtime.c");
+    std::fs::remove_file(&runtime).unwrap();
+
+    // The runtime object of the first build is reused.
+    let output = driver.run(Source::dummy("b.ml", "print_int 2")).unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2");
+    assert!(!runtime.exists());
+}
+
+#[test]
+fn driver_dump() {
+    let driver = Driver::new(Config {
+        dump: true,
+        ..Config::default()
+    });
+    let output = driver
+        .run(Source::dummy(
+            "dump.ml",
+            "1 + 2;; [];; [1; -2; 3];; [\"a\\n\"; \"b\"] @ [\"c\"];; ['x'] :: [[]];; \
+             [(1, true); (2, false)];; (1, [()], ('c', \"s\"));; [fun x -> x];; \
+             let l = [1; 2] in l @ l;; fst;; print_int 1;; \
+             type p = { x : int; s : string; l : p list };; let r = { x = 1; s = \"a\"; l = [] };; \
+             { r with l = [r; r] };; [{ x = 2; s = \"b\"; l = [] }], r.s;; \
+             let c = ref [1] in c := 2 :: !c; c;; ref (ref 'a');; ref;; let c = ref 0 in incr c;; \
+             [| (1, \"a\") |];; [||];; let a = [| [||]; [| 'b' |] |] in a.(0) <- a.(1); a;; Array.make 2 r;; \
+             type shape = Circle of int | Rect of int * int | Empty;; [Circle 1; Rect (2, -3); Empty];; \
+             Circle (-1);; type w = A of shape | B of shape list | C of (int * int);; \
+             (A (Circle 2), A Empty, B [Empty], C (1, 2));; \
+             type tree = Leaf | Node of tree * int * tree;; Node (Leaf, 1, Node (Leaf, 2, Leaf))",
+        ))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        concat!(
+            "3\n",
+            "[]\n",
+            "[1; -2; 3]\n",
+            "[\"a\\n\"; \"b\"; \"c\"]\n",
+            "[['x']; []]\n",
+            "[(1, true); (2, false)]\n",
+            "(1, [()], ('c', \"s\"))\n",
+            "[<fun>]\n",
+            "[1; 2; 1; 2]\n",
+            "<fun>\n",
+            "1()\n",
+            "{x = 1; s = \"a\"; l = [{x = 1; s = \"a\"; l = []}; {x = 1; s = \"a\"; l = []}]}\n",
+            "([{x = 2; s = \"b\"; l = []}], \"a\")\n",
+            "{contents = [2; 1]}\n",
+            "{contents = {contents = 'a'}}\n",
+            "<fun>\n",
+            "()\n",
+            "[|(1, \"a\")|]\n",
+            "[||]\n",
+            "[|[|'b'|]; [|'b'|]|]\n",
+            "[|{x = 1; s = \"a\"; l = []}; {x = 1; s = \"a\"; l = []}|]\n",
+            "[Circle 1; Rect (2, -3); Empty]\n",
+            "Circle (-1)\n",
+            "(A (Circle 2), A Empty, B [Empty], C (1, 2))\n",
+            "Node (Leaf, 1, Node (Leaf, 2, Leaf))\n",
+        ),
+    );
+}
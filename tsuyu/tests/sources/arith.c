@@ -0,0 +1,8 @@
+let print n = print_int n; print_newline ();;
+print (1 + 2 * 3);;
+print ((1 + 2) * 3);; (* comment *)
+print (10 - 2 - 3);;
+print (7 / 2);;
+print (7 mod 3);;
+print ((3 - 10) / 2);;
+print ((3 - 10) mod 2);;
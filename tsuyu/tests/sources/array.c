@@ -0,0 +1,52 @@
+(* Literals, indexing and length *)
+let a = [| 10; 20; 30 |];;
+print_int a.(0); print_char ' '; print_int a.(2); print_char ' '; print_int (Array.length a); print_newline ();;
+print_int (Array.length [||]); print_newline ();;
+(* Assignment *)
+a.(1) <- a.(0) + a.(2);;
+print_int a.(1); print_newline ();;
+(* Array.make shares the initial value *)
+let b = Array.make 5 0;;
+let rec fill i = if i < Array.length b then (b.(i) <- i * i; fill (i + 1)) else ();;
+fill 0;;
+let rec sum i = if i = Array.length b then 0 else b.(i) + sum (i + 1);;
+print_int (sum 0); print_newline ();;
+let shared = Array.make 2 (ref 0);;
+incr shared.(0);;
+print_int !(shared.(1)); print_newline ();;
+(* Arrays of other types *)
+let names = [| "x"; "y" |];;
+names.(0) <- names.(1) ^ names.(0);;
+print_endline names.(0);;
+let grid = [| [| 1; 2 |]; [| 3; 4 |] |];;
+grid.(1).(0) <- 5;;
+print_int (grid.(0).(1) + grid.(1).(0)); print_newline ();;
+(* Indices are evaluated before the array like ocamlopt *)
+let i = ref 0;;
+let c = [| 'a'; 'b'; 'c' |];;
+print_char (incr i; c).(!i); print_char c.(!i); print_newline ();;
+(* Sorting in place *)
+let sort cmp a =
+  let n = Array.length a in
+  let rec outer i =
+    if i < n then begin
+      let rec inner j =
+        if j > 0 && cmp a.(j - 1) a.(j) > 0 then begin
+          let t = a.(j) in
+          a.(j) <- a.(j - 1);
+          a.(j - 1) <- t;
+          inner (j - 1)
+        end else ()
+      in
+      inner i;
+      outer (i + 1)
+    end else ()
+  in
+  outer 1;;
+let d = [| 5; 3; 8; 1; 9; 2 |];;
+sort (fun x y -> x - y) d;;
+let rec show i = if i < Array.length d then (print_int d.(i); print_char ' '; show (i + 1)) else print_newline ();;
+show 0;;
+(* Partial application of Array.make *)
+let make3 = Array.make 3;;
+print_int (Array.length (make3 true)); print_newline ()
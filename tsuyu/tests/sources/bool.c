@@ -0,0 +1,20 @@
+(* Booleans and comparisons *)
+let print b = print_endline (string_of_bool b);;
+print true;;
+print false;;
+print (1 < 2);;
+print (2 <= 1);;
+print (3 > 3);;
+print (3 >= 3);;
+print (1 = 1);;
+print (1 <> 1);;
+print (true = false);;
+print (false < true);;
+print (not true);;
+print (not (1 = 2) && 2 < 3);;
+(* Short-circuit: the right operand would raise Division_by_zero *)
+print (false && 1 / 0 = 0);;
+print (true || 1 / 0 = 0);;
+print (1 > 2 || 3 > 2 && not false);;
+let b = 1 + 2 * 3 = 7;;
+print b
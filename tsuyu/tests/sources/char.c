@@ -0,0 +1,25 @@
+(* Char literals *)
+let print c = print_char c; print_newline ();;
+print 'a';;
+print '\065';;
+print '\x41';;
+print '\'';;
+print '\\';;
+print '"';;
+print_char '\n';;
+(* Conversions *)
+print_int (Char.code 'a'); print_newline ();;
+print_int (Char.code '\255'); print_newline ();;
+print (Char.chr 98);;
+print (Char.chr (Char.code 'x' + 1));;
+let upper c = if 'a' <= c && c <= 'z' then Char.chr (Char.code c - 32) else c;;
+print (upper 'q');;
+print (upper '!');;
+print_endline (string_of_bool ('a' = 'a'));;
+print_endline (string_of_bool ('a' < 'b'));;
+(* Builtins as values *)
+let apply f x = f x;;
+print_int (apply Char.code 'A'); print_newline ();;
+print (apply Char.chr 67);;
+apply print 'D';;
+let id x = x in print (id 'x')
@@ -0,0 +1,46 @@
+(* Anonymous functions *)
+let print n = print_int n; print_newline ();;
+print ((fun x -> x + 1) 41);;
+print ((fun x y -> x * y) 6 7);;
+fun x -> x;;
+(fun s -> print_endline s) "anonymous";;
+(* Closures capture free variables *)
+let make_adder n = fun x -> x + n
+let add10 = make_adder 10
+;;
+print (add10 5);;
+print ((make_adder 1) 2);;
+let twice f x = f (f x)
+let compose f g = fun x -> f (g x)
+;;
+print (twice add10 1);;
+print (twice (fun x -> x * x) 3);;
+print ((compose add10 (fun x -> x * 2)) 5);;
+print_endline (string_of_bool (twice not true));;
+print (let a = 1 in let b = 2 in let f c = a + b + c in let a = 100 in f a);;
+let prefix p = fun s -> print_endline (p ^ s) in
+let warn = prefix "warning: " in
+warn "a"; warn "b";;
+(* Top-level functions as values *)
+let sub x y = x - y
+let apply f x y = f x y
+;;
+print (apply sub 10 3);;
+print (let g = sub in g 1 2);;
+(* Local recursive functions *)
+let sum n =
+  let rec loop i acc = if i > n then acc else loop (i + 1) (acc + i) in
+  loop 1 0
+;;
+print (sum 100);;
+let parity n =
+  let rec even k = if k = 0 then true else odd (k - 1)
+  and odd k = if k = 0 then false else even (k - 1) in
+  if even n then 0 else 1
+;;
+print (parity 9);;
+print (let rec f = fun n -> if n = 0 then 0 else n + f (n - 1) in f 10);;
+(* More arguments than registers *)
+let many a b c d e f g h = a - b + c - d + e - f + g - h in
+let k = 7 in
+print ((fun a b c d e f g h -> many a b c d e f g (h + k)) 1 2 3 4 5 6 7 8)
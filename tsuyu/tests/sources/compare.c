@@ -0,0 +1,53 @@
+(* Tuples are compared by their elements in order *)
+let p = (1, 2);;
+print_endline (string_of_bool ((1, 2) = (1, 2)));;
+print_endline (string_of_bool (p <> (1, 3)));;
+print_endline (string_of_bool ((1, 3) < (2, 0)));;
+print_endline (string_of_bool ((2, 0) > (1, 3)));;
+print_endline (string_of_bool ((1, "b") <= (1, "a")));;
+print_endline (string_of_bool ((1, ("a", 'c')) >= (1, ("a", 'b'))));;
+print_endline (string_of_bool (((), true) = ((), true)));;
+let (x, y) = if (3, 1) > (2, 9) then (3, 1) else (2, 9) in print_int (x + y); print_newline ();;
+(* Functions are not compared once the elements before them differ *)
+let f x = x + 1;;
+print_endline (string_of_bool ((1, f) = (2, f)));;
+(* Lists are compared lexicographically *)
+print_endline (string_of_bool ([1; 2] = [1; 2]));;
+print_endline (string_of_bool ([1; 2] <> [1; 2; 3]));;
+print_endline (string_of_bool ([1; 2] < [0]));;
+print_endline (string_of_bool ([] < [0]));;
+print_endline (string_of_bool ([1; 2] < [1; 2; 0]));;
+print_endline (string_of_bool (["b"; "a"] > ["a"; "b"]));;
+print_endline (string_of_bool ([(1, [2])] = [(1, [2])]));;
+let rec has_three l = l <> [] && (List.hd l = [3] || has_three (List.tl l));;
+print_endline (string_of_bool (has_three [[1]; [2; 3]; [3]]));;
+(* Records are compared by their fields *)
+type point = { x : int; mutable y : int };;
+let q = { x = 1; y = 2 };;
+print_endline (string_of_bool ({ x = 1; y = 2 } = q));;
+print_endline (string_of_bool ({ x = 1; y = 3 } > q));;
+print_endline (string_of_bool ({ q with y = 3 } = { x = 1; y = 3 }));;
+(* Refs are compared by their contents *)
+print_endline (string_of_bool (ref 1 = ref 1));;
+print_endline (string_of_bool (ref "a" < ref "b"));;
+q.y <- 3;;
+print_endline (string_of_bool ({ x = 1; y = 3 } = q));;
+(* Arrays are compared by their lengths first *)
+print_endline (string_of_bool ([|1; 2|] = [|1; 2|]));;
+print_endline (string_of_bool ([|9|] < [|1; 2|]));;
+print_endline (string_of_bool ([|1; 3|] > [|1; 2|]));;
+(* Variants are compared by their constructors and then their arguments *)
+type shape = Empty | Point | Circle of int | Rect of int * int;;
+print_endline (string_of_bool (Circle 1 = Circle 1));;
+print_endline (string_of_bool (Rect (1, 2) <> Rect (1, 2)));;
+print_endline (string_of_bool (Rect (1, 2) < Rect (1, 3)));;
+print_endline (string_of_bool (Circle 5 < Rect (0, 0)));;
+print_endline (string_of_bool (Point < Circle 0));;
+print_endline (string_of_bool (Empty < Point));;
+type tree = Leaf | Node of tree * int * tree;;
+let t = Node (Leaf, 1, Node (Leaf, 2, Leaf));;
+print_endline (string_of_bool (t = Node (Leaf, 1, Node (Leaf, 2, Leaf))));;
+print_endline (string_of_bool (t = Node (Leaf, 1, Leaf)));;
+type int_option = None | Some of int;;
+print_endline (string_of_bool ([Some 1; None] = [Some 1; None]));;
+print_endline (string_of_bool (Some 1 = Some 2))
@@ -0,0 +1,46 @@
+(* Partial application of top-level functions *)
+let print n = print_int n; print_newline ()
+let add x y = x + y
+let inc = add 1
+;;
+print (inc 41);;
+add 1;;
+let twice f x = f (f x);;
+print (twice (add 10) 1);;
+print (twice (twice inc) 0);;
+(* Partial application of closures *)
+print (let mul = fun x y -> x * y in let double = mul 2 in double 21);;
+let sum3 a b c = a * 100 + b * 10 + c;;
+let p1 = sum3 1;;
+let p2 = p1 2;;
+print (p2 3);;
+print (p1 4 5);;
+print ((sum3 7) 8 9);;
+(* Over-application *)
+let pick c = if c then (fun x y -> x) else (fun x y -> y);;
+print (pick true 1 2);;
+print (pick false 1 2);;
+let k x = fun y -> x;;
+print (k 1 2);;
+let compose f g x = f (g x);;
+print (compose inc (add 2) 1);;
+print (compose (add 1) (add 2) 3);;
+(* Partial application with more arguments than registers *)
+let many a b c d e f g h = a - b + c - d + e - f + g - h;;
+let m = many 1 2 3 4 5 6;;
+print (m 7 8);;
+print (let m2 = m 7 in m2 8);;
+print ((many 1) 2 3 4 5 6 7 8);;
+print (let id x = x in id many 1 2 3 4 5 6 7 8);;
+(* Builtins applied partially *)
+let print_sum a b = print (a + b);;
+let print_sum1 = print_sum 1;;
+print_sum1 2;;
+compose print (add 3) 4;;
+(* Local functions *)
+let scale n =
+  let mul a b = a * b in
+  let f = mul n in
+  f 3 + mul n 4
+;;
+print (scale 2)
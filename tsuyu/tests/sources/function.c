@@ -0,0 +1,24 @@
+(* Top-level functions and globals *)
+let print n = print_int n; print_newline ()
+let base = 100
+let add x y = x + y
+let scale x = x * base
+let sum8 a b c d e f g h = a + 2 * b + 3 * c + 4 * d + 5 * e + 6 * f + 7 * g + 8 * h
+let sum7 a b c d e f g = a - b - c - d - e - f - g
+let pick _ x = x
+;;
+print (add 1 2);;
+print (scale (add 1 2));;
+print (1 + (2 + (3 + add (scale 1) (add 4 5))));;
+print (sum8 1 2 3 4 5 6 7 8);;
+print (1 + sum8 1 2 3 4 5 6 7 (sum8 1 1 1 1 1 1 1 1));;
+print (2 * (1 + sum7 100 1 2 3 4 5 (sum7 10 1 1 1 1 1 1)));;
+print (pick 1 2);;
+let base = 1
+let scale' x = scale x + x * base;;
+print (scale' 5);;
+let f x = let y = x * 2 in let z = y + 1 in add y z;;
+print (f 10);;
+let _ = add 1 2;;
+let _ = print 3;;
+print base
@@ -0,0 +1,27 @@
+(* Conditionals make recursion terminate *)
+let print n = print_int n; print_newline ()
+let print_bool b = print_endline (string_of_bool b)
+let rec fact n = if n = 0 then 1 else n * fact (n - 1)
+let rec fib n = if n < 2 then n else fib (n - 1) + fib (n - 2)
+let rec even n = if n = 0 then true else odd (n - 1)
+and odd n = if n = 0 then false else even (n - 1)
+;;
+print (fact 10);;
+print (fib 20);;
+print_bool (even 10);;
+print_bool (odd 7);;
+print (1 + if fact 3 = 6 then 10 else 20);;
+print (if false then 1 else if true then 2 else 3);;
+(* Polymorphic functions *)
+let id x = x
+let choose c a b = if c then a else b
+;;
+print_bool (id true);;
+print (id 42);;
+print (choose (id false) 1 2);;
+print_bool (choose true (not false) false);;
+let rec gcd a b = if b = 0 then a else gcd b (a mod b);;
+print (gcd 1071 1029);;
+(* Branches of unit *)
+if gcd 6 4 = 2 then print_endline "then" else ();;
+if false then print_endline "then" else print_endline "else"
@@ -0,0 +1,14 @@
+let print n = print_int n; print_newline ();;
+print 0x1F;;
+print 0o17;; (* octal *)
+print 0b1010;;
+print 1_000_000;;
+print (-42);;
+print (- (3 + 4));;
+print (-9223372036854775808);;
+print 9223372036854775807;;
+print (- -9223372036854775808);;
+print ((-9223372036854775808) / (-1));;
+print ((-9223372036854775808) mod (-1));;
+print (9223372036854775807 + 1);;
+print_endline (string_of_int (-123) ^ string_of_int 0 ^ string_of_int 9223372036854775807);;
@@ -0,0 +1,8 @@
+let print n = print_int n; print_newline ();;
+print (let x = 6 in x * 7);;
+print (let x = 1 in let y = x + 1 in let x = x * 10 in x + y);;
+print (let a = 3 in (let b = a * a in b + a) + (let c = 100 in c - a));;
+print (let _ = 1 / 1 in 5);;
+print (1 + let x = 2 in x * x);;
+print (let x = let y = 4 in y * y in -x);;
+let x = 3 in print x; print (x + 1);;
@@ -0,0 +1,16 @@
+(* Functions in a recursive group can refer to later ones *)
+let print n = print_int n; print_newline ()
+let rec double_inc x = inc x * 2
+and inc x = x + 1
+and loop x = loop x
+;;
+print (double_inc 4);;
+let x = 10
+let x = 1 and y = x;;
+print (x + y);;
+print (let a = 1 and b = let t = 5 in t * 2 in a + b);;
+let f x = x * 100 and g x = x;;
+let f x = g x and g x = f x;;
+print (f 3 + g 4);;
+let rec countdown n = if n = 0 then () else begin print n; countdown (n - 1) end;;
+countdown 3
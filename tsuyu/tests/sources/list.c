@@ -0,0 +1,40 @@
+(* Construction *)
+let l = [1; 2; 3];;
+let m = 0 :: l;;
+let rec print_list l =
+  if l = [] then print_newline ()
+  else begin
+    print_int (List.hd l);
+    print_char ' ';
+    print_list (List.tl l)
+  end
+;;
+print_list l;;
+print_list m;;
+print_list [];;
+print_list [4; 5;];;
+(* Append *)
+print_list (l @ m @ [9]);;
+print_list ([] @ [7] @ []);;
+(* Recursive functions over lists *)
+let rec length l = if l = [] then 0 else 1 + length (List.tl l);;
+let rec map f l = if l = [] then [] else f (List.hd l) :: map f (List.tl l);;
+let rec fold_left f acc l =
+  if l = [] then acc else fold_left f (f acc (List.hd l)) (List.tl l);;
+let rec range a b = if a > b then [] else a :: range (a + 1) b;;
+print_int (length (range 1 100)); print_newline ();;
+print_list (map (fun x -> x * x) (range 1 5));;
+print_int (fold_left (fun a b -> a + b) 0 (range 1 100)); print_newline ();;
+let rev l = fold_left (fun acc x -> x :: acc) [] l;;
+print_list (rev (range 1 5));;
+(* Lists of other types *)
+let words = ["tsuyu"; "caml"];;
+print_endline (fold_left (fun a b -> a ^ b) "" words);;
+let pairs = [(1, "one"); (2, "two")];;
+print_endline (snd (List.hd (List.tl pairs)));;
+let nested = [[1]; []; [2; 3]];;
+print_int (length nested); print_char ' '; print_int (length (List.hd (List.tl (List.tl nested))));
+print_newline ();;
+print_endline (string_of_bool ([] = []));;
+(* Failure on the empty list *)
+List.hd (List.tl [1])
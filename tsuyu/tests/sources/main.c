@@ -1 +1,2 @@
-10
+print_int 10;;
+print_newline ()
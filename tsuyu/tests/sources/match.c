@@ -0,0 +1,58 @@
+(* Constructors *)
+type shape = Circle of int | Rect of int * int | Empty | Point;;
+let area s = match s with Circle r -> 3 * r * r | Rect (w, h) -> w * h | Empty -> 0 | Point -> 0;;
+print_int (area (Circle 2) + area (Rect (3, 4)) + area Empty); print_newline ();;
+let name s = match s with
+  | Empty -> "empty"
+  | Point -> "point"
+  | Circle 0 -> "dot"
+  | Circle _ -> "circle"
+  | Rect (w, h) -> if w = h then "square" else "rect";;
+print_endline (name Point); print_endline (name (Circle 0)); print_endline (name (Rect (2, 2)));;
+(* Nested patterns *)
+type tree = Leaf | Node of tree * int * tree;;
+let rec insert x t = match t with
+  | Leaf -> Node (Leaf, x, Leaf)
+  | Node (l, y, r) -> if x < y then Node (insert x l, y, r) else Node (l, y, insert x r);;
+let rec sum t = match t with Leaf -> 0 | Node (l, x, r) -> sum l + x + sum r;;
+let rec leftmost t = match t with
+  | Node (Leaf, x, _) -> x
+  | Node (l, _, _) -> leftmost l
+  | Leaf -> -1;;
+let t = insert 5 (insert 2 (insert 8 (insert 1 Leaf)));;
+print_int (sum t); print_newline (); print_int (leftmost t); print_newline ();;
+(* Lists and tuples *)
+let rec length l = match l with [] -> 0 | _ :: rest -> 1 + length rest;;
+let rec pairs l = match l with
+  | x :: y :: rest -> (x, y) :: pairs rest
+  | _ -> [];;
+let describe l = match l with
+  | [] -> "none"
+  | [(0, _)] -> "zero"
+  | [(_, true)] -> "one true"
+  | [_] -> "one"
+  | [_; _] -> "two"
+  | _ -> "many";;
+print_int (length (pairs [1; 2; 3; 4; 5])); print_newline ();;
+print_endline (describe []); print_endline (describe [(0, false)]);;
+print_endline (describe [(1, true)]); print_endline (describe [(1, false); (2, true)]);;
+print_endline (describe [(1, false); (2, true); (3, true)]);;
+let swap p = match p with (a, b) -> (b, a);;
+print_int (fst (swap (1, 2))); print_newline ();;
+(* Literals *)
+let digit c = match c with '0' -> 0 | '1' -> 1 | _ -> -1;;
+let greet s = match s with "en" -> "hello" | "ja" -> "konnichiwa" | _ -> "?";;
+let sign n = match n with 0 -> "zero" | -1 -> "minus one" | n -> if n > 0 then "positive" else "negative";;
+let both b = match b with (true, true) -> "both" | _ -> "not both";;
+print_int (digit '1' + digit 'x'); print_newline ();;
+print_endline (greet "ja"); print_endline (greet "fr");;
+print_endline (sign 0); print_endline (sign (-1)); print_endline (sign 7); print_endline (sign (-7));;
+print_endline (both (true, false));;
+(* Variables bound by cases are captured by closures *)
+let adders l = match l with x :: _ -> (fun y -> x + y) | [] -> (fun y -> y);;
+print_int (adders [10; 20] 5); print_newline ();;
+let rec count_points l = match l with
+  | [] -> 0
+  | Point :: rest -> 1 + count_points rest
+  | _ :: rest -> count_points rest;;
+print_int (count_points [Point; Empty; Point; Circle 1]); print_newline ()
@@ -0,0 +1,41 @@
+(* Guards *)
+let classify n = match n with
+  | 0 -> "zero"
+  | n when n < 0 -> "negative"
+  | n when n mod 2 = 0 -> "even"
+  | _ -> "odd";;
+print_endline (classify 0); print_endline (classify (-3));;
+print_endline (classify 4); print_endline (classify 7);;
+let rec dedup l = match l with
+  | x :: (y :: _ as rest) when x - y = 0 -> dedup rest
+  | x :: rest -> x :: dedup rest
+  | [] -> [];;
+let rec print_list l = match l with
+  | [] -> print_newline ()
+  | x :: rest -> print_int x; print_char ' '; print_list rest;;
+print_list (dedup [1; 1; 2; 3; 3; 3; 1]);;
+(* Or-patterns *)
+type color = Red | Green | Blue | Rgb of int * int * int;;
+let is_primary c = match c with Red | Green | Blue -> true | Rgb _ -> false;;
+let gray c = match c with Rgb (x, 0, 0) | Rgb (0, x, 0) | Rgb (0, 0, x) -> x | _ -> -1;;
+print_endline (string_of_bool (is_primary Green)); print_endline (string_of_bool (is_primary (Rgb (1, 2, 3))));;
+print_int (gray (Rgb (0, 7, 0)) + gray (Rgb (0, 0, 5)) + gray Red); print_newline ();;
+let small n = match n with 1 | 2 | 3 -> "small" | _ -> "large";;
+print_endline (small 2); print_endline (small 5);;
+(* Aliases *)
+let first_pair l = match l with ((a, _) as p) :: _ when a > 0 -> p | _ -> (0, 0);;
+let p = first_pair [(3, 4); (5, 6)];;
+print_int (fst p + snd p); print_newline ();;
+let (a, b) as q = (1, 2);;
+print_int (a + b + fst q); print_newline ();;
+let f x = let (_, y) as z = x in y + fst z;;
+print_int (f (10, 20)); print_newline ();;
+(* Char ranges *)
+let kind c = match c with
+  | 'a'..'z' | 'A'..'Z' -> "letter"
+  | '0'..'9' -> "digit"
+  | ' ' | '\t' | '\n' -> "space"
+  | 'q' -> "never"
+  | _ -> "other";;
+print_endline (kind 'q'); print_endline (kind 'Z'); print_endline (kind '7');;
+print_endline (kind ' '); print_endline (kind '!'); print_endline (kind '{')
@@ -0,0 +1,36 @@
+(* Declaration and construction *)
+type point = { x : int; y : int };;
+let p = { x = 1; y = 2 };;
+let q = { y = 20; x = 10 };;
+print_int p.x; print_char ' '; print_int p.y; print_newline ();;
+print_int q.x; print_char ' '; print_int q.y; print_newline ();;
+(* Functional update does not modify the original *)
+let r = { p with y = 3 };;
+print_int r.x; print_char ' '; print_int r.y; print_char ' '; print_int p.y; print_newline ();;
+let move dx dy p = { x = p.x + dx; y = p.y + dy };;
+let s = move 5 6 r;;
+print_int (s.x + s.y); print_newline ();;
+(* Fields of other types *)
+type person = { name : string; age : int; friends : person list; pos : point * point };;
+let alice = { name = "alice"; age = 30; friends = []; pos = (p, q) };;
+let bob = { name = "bob"; age = 25; friends = [alice]; pos = ({ x = 0; y = 0 }, p) };;
+print_endline (List.hd bob.friends).name;;
+print_int (fst alice.pos).y; print_newline ();;
+let older p = { p with age = p.age + 1; friends = [] };;
+print_int (older (older bob)).age; print_newline ();;
+(* Labels of a later type hide earlier ones *)
+type a = { v : int };;
+type b = { v : string; w : int };;
+let f r = r.v;;
+print_endline (f { v = "later"; w = 1 });;
+(* Records in closures *)
+let offset = { x = 100; y = 200 };;
+let shift p = { x = p.x + offset.x; y = p.y + offset.y };;
+print_int (shift p).y; print_newline ();;
+(* Mutually recursive types *)
+type tree = { label : int; children : forest } and forest = { trees : tree list };;
+let leaf n = { label = n; children = { trees = [] } };;
+let t = { label = 1; children = { trees = [leaf 2; leaf 3] } };;
+let rec sum t = t.label + sum_forest t.children.trees
+and sum_forest l = if l = [] then 0 else sum (List.hd l) + sum_forest (List.tl l);;
+print_int (sum t); print_newline ()
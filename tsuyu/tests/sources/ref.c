@@ -0,0 +1,52 @@
+(* Ref cells *)
+let r = ref 1;;
+print_int !r; print_newline ();;
+r := !r + 41;;
+print_int !r; print_newline ();;
+incr r; incr r; decr r;;
+print_int !r; print_newline ();;
+(* Loops by recursion over a counter *)
+let sum n =
+  let total = ref 0 in
+  let i = ref 1 in
+  let rec loop () = if !i <= n then (total := !total + !i; incr i; loop ()) else () in
+  loop ();
+  !total;;
+print_int (sum 100); print_newline ();;
+(* Refs shared by closures *)
+let make_counter () =
+  let count = ref 0 in
+  (fun () -> incr count; !count), (fun () -> count := 0);;
+let (next, reset) = make_counter ();;
+let _ = next () in let _ = next () in print_int (next ()); print_newline ();;
+reset (); print_int (next ()); print_newline ();;
+(* Refs of other types *)
+let names = ref [];;
+names := "a" :: !names; names := "b" :: !names;;
+print_endline (List.hd !names);;
+let rr = ref (ref 'x');;
+!rr := 'y';;
+print_char !(!rr); print_newline ();;
+(* The argument of incr is evaluated once *)
+let calls = ref 0;;
+let get r = incr calls; r;;
+incr (get r);;
+print_int !r; print_char ' '; print_int !calls; print_newline ();;
+(* Mutable record fields *)
+type account = { owner : string; mutable balance : int };;
+let acc = { owner = "alice"; balance = 100 };;
+acc.balance <- acc.balance + 50;;
+print_int acc.balance; print_newline ();;
+let deposit a n = a.balance <- a.balance + n;;
+deposit acc 25; deposit acc 25;;
+print_int acc.balance; print_newline ();;
+(* Functional update copies the mutable fields *)
+let copy = { acc with owner = "bob" };;
+copy.balance <- 0;;
+print_int acc.balance; print_char ' '; print_int copy.balance; print_newline ();;
+(* Mutable fields in a recursive structure *)
+type node = { value : int; mutable next : node list };;
+let a = { value = 1; next = [] };;
+let b = { value = 2; next = [a] };;
+a.next <- [b];;
+print_int (List.hd (List.hd a.next).next).value; print_newline ()
@@ -0,0 +1,24 @@
+(* String literals and escapes *)
+print_endline "hello";;
+print_endline "";;
+print_endline "quote \" backslash \\ tab \t newline \n";;
+print_endline "\065\x42\o103 \u{3042}";;
+print_endline "あいう";;
+print_endline "line \
+   continued";;
+print_string "no newline"; print_string "\n";;
+(* Concatenation *)
+print_endline ("foo" ^ "bar");;
+let greet name = "Hello, " ^ name ^ "!";;
+print_endline (greet "tsuyu");;
+let rec repeat s n = if n = 0 then "" else s ^ repeat s (n - 1);;
+print_endline (repeat "ab" 3);;
+(* Comparison by contents *)
+let print_bool b = print_endline (string_of_bool b);;
+print_bool ("ab" ^ "c" = "abc");;
+print_bool ("abc" <> "abc");;
+print_bool ("abc" < "abd");;
+print_bool ("ab" < "abc");;
+print_bool ("b" > "abc");;
+print_bool ("\xff" > "a");;
+let id x = x in print_endline (id "poly");;
@@ -0,0 +1,3 @@
+(* Strings are bytes which may be invalid UTF-8 or contain null *)
+print_string "\xff\000a";;
+print_endline "\000"
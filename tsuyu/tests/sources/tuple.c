@@ -0,0 +1,37 @@
+(* Construction and destructuring *)
+let p = 1, "one";;
+let (n, s) = p;;
+print_int n; print_string s; print_newline ();;
+let a, b, c = 'x', true, () in
+print_char a; print_endline (string_of_bool b);;
+(* Nested tuples *)
+let ((x, y), (_, z)) = ((1, 2), (3, 4)) in
+print_int (x + y + z); print_newline ();;
+(* fst and snd *)
+print_int (fst (10, 20) + snd (30, 40)); print_newline ();;
+print_endline (snd (fst ((1, "nested"), 2)));;
+(* Multiple return values *)
+let divmod a b = a / b, a mod b;;
+let (q, r) = divmod 17 5 in
+print_int q; print_char ' '; print_int r; print_newline ();;
+(* Tuple parameters *)
+let add (a, b) = a + b;;
+print_int (add (divmod 7 2)); print_newline ();;
+let swap (a, b) = b, a;;
+let (l, r) = swap ("left", "right") in print_endline (l ^ r);;
+let sum3 = fun (a, b, c) -> a + b + c in
+print_int (sum3 (1, 2, 3)); print_newline ();;
+(* Closures capturing destructured names *)
+let make (base, step) = fun i -> base + step * i;;
+let f = make (100, 10) in print_int (f 3); print_newline ();;
+let (g, h) = (fun x -> x * 2), (fun x -> x + 1) in
+print_int (g (h 4)); print_newline ();;
+(* Tuples of tuples in recursion *)
+let rec fib n = if n = 0 then (0, 1) else let (a, b) = fib (n - 1) in (b, a + b);;
+print_int (fst (fib 30)); print_newline ();;
+(* Unit patterns *)
+let () = print_endline "unit pattern";;
+let hello () = print_endline "hello";;
+hello ();;
+let _ = 1, 2;;
+print_int n; print_newline ()
@@ -0,0 +1,27 @@
+(* Top-level expressions are evaluated only for their effects *)
+1 + 2;;
+"not printed";;
+();;
+(* Sequences *)
+print_string "a"; print_string "b"; print_newline ();;
+let x = print_string "c"; 1 in print_int x; print_newline ();;
+let greet name =
+  print_string "Hello, ";
+  print_string name;
+  print_newline ()
+;;
+greet "tsuyu";;
+(* begin ... end *)
+begin
+  print_int 1;
+  print_int 2
+end;
+print_newline ();;
+let u = begin end in if u = () then print_endline "unit" else ();;
+let twice f = f (); f () in
+twice (fun _ -> print_endline "twice");;
+(* The value of the first expression is discarded *)
+print_int (1; 2); print_newline ();;
+(* Unit as a value *)
+let ignore _ = ();;
+let v = ignore (print_endline "ignored") in v = ()
@@ -0,0 +1,25 @@
+(* Constant constructors *)
+type color = Red | Green | Blue;;
+let name c = if c = Red then "red" else if c = Green then "green" else "blue";;
+print_endline (name Red); print_endline (name Blue);;
+print_endline (if Green <> Blue then "different" else "same");;
+(* Constructors with arguments *)
+type shape = Circle of int | Rect of int * int | Empty;;
+let shapes = [Circle 1; Rect (2, 3); Empty; Circle (-4)];;
+let rec length l = if l = [] then 0 else 1 + length (List.tl l);;
+print_int (length shapes); print_newline ();;
+let count_empty l =
+  let rec loop l n = if l = [] then n else loop (List.tl l) (if List.hd l = Empty then n + 1 else n) in
+  loop l 0;;
+print_int (count_empty (Empty :: shapes)); print_newline ();;
+(* Constructors of recursive types *)
+type tree = Leaf | Node of tree * int * tree;;
+let rec insert x n = Node (Leaf, x, if n = 0 then Leaf else insert (x + 1) (n - 1));;
+let t = insert 1 3;;
+print_endline (if t = Leaf then "leaf" else "node");;
+(* Constructors as values in other structures *)
+let pair = (Circle 5, [| Red; Blue |]);;
+print_endline (name (snd pair).(1));;
+let r = ref Empty;;
+r := Rect (1, 1);;
+print_endline (if !r = Empty then "empty" else "not empty")
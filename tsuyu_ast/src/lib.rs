@@ -4,10 +4,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::rc::Rc;
+use std::{fmt, rc::Rc};
 
-use tsuyu_utils::{clone_option_rc, define_with_params_and_init};
-use tsuyu_source::Source;
+use tsuyu_utils::{clone_option_rc, define_with_params_and_init, escape_bytes, escape_char};
+pub use tsuyu_source::Loc as _Loc;
+use tsuyu_source::{Loc, Source};
 
 define_with_params_and_init! {
     #[derive(Clone, Debug, PartialEq, Eq)]
@@ -20,11 +21,517 @@ define_with_params_and_init! {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Stmt {
     Expr(Expr),
+    /// Top-level `let`.
+    Let(BindingGroup),
+    /// `type t1 = ... and t2 = ...`. Type names in a group can refer to each other.
+    Type(Vec<TypeDecl>),
 }
 
+/// Declaration of a type. Declarations are numbered in order of appearance in the file, and
+/// `Type::Named` refers to them by the number.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Expr {
+pub struct TypeDecl {
+    pub name: String,
+    pub kind: TypeDeclKind,
+    pub loc: Loc,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeDeclKind {
+    /// `{ l1 : t1; l2 : t2; ... }`.
+    Record(Vec<FieldDecl>),
+    /// `C1 of t1 | C2 of t2 * t3 | ...`.
+    Variant(Vec<ConstrDecl>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDecl {
+    /// Declared with `mutable` so that it can be assigned by `r.l <- e`.
+    pub mutable: bool,
+    pub label: Label,
+    pub typ_expr: TypeExpr,
+    /// Type of the field filled by type inference.
+    pub typ: Option<Type>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstrDecl {
+    pub name: String,
+    /// Types after `of` separated by `*`. Empty for constant constructors.
+    pub args: Vec<TypeExpr>,
+    /// Types of the arguments filled by type inference.
+    pub typs: Option<Vec<Type>>,
+    pub loc: Loc,
+}
+
+/// Name of a record field.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub name: String,
+    pub loc: Loc,
+}
+
+// Locations are not compared like `Expr`.
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Label {}
+
+/// Type written in the source like `int * string list`.
+#[derive(Clone, Debug)]
+pub struct TypeExpr {
+    pub kind: TypeExprKind,
+    pub loc: Loc,
+}
+
+// Locations are not compared like `Expr`.
+impl PartialEq for TypeExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Eq for TypeExpr {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeExprKind {
+    /// Type constructor applied to arguments like `int` and `int list`.
+    Constr(Vec<TypeExpr>, String),
+    /// `t1 * t2 * ...` of two or more types.
+    Tuple(Vec<TypeExpr>),
+    Fun(Box<TypeExpr>, Box<TypeExpr>),
+}
+
+/// Bindings of `let [rec] b1 and b2 ...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindingGroup {
+    pub rec: bool,
+    pub bindings: Vec<Binding>,
+}
+
+/// `pattern params = value`. It defines a function if it has parameters. The pattern of a
+/// function is always a variable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub pattern: Pattern,
+    pub params: Vec<Pattern>,
+    pub value: Expr,
+    /// Type of the value filled by type inference.
+    pub typ: Option<Type>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub loc: Loc,
+}
+
+// Locations are not compared like `Expr`.
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Eq for Pattern {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    /// `_`.
+    Wildcard,
+    Var(String),
+    /// `()`.
+    Unit,
+    /// `p1, p2, ...` of two or more patterns.
+    Tuple(Vec<Pattern>),
+    IntLiteral(i64),
+    BoolLiteral(bool),
+    StringLiteral(Vec<u8>),
+    CharLiteral(u8),
+    /// `[p1; p2; ...]`. `[]` is the empty list.
+    List(Vec<Pattern>),
+    /// `p1 :: p2`.
+    Cons(Box<Pattern>, Box<Pattern>),
+    /// Constructor with the pattern of the argument if any like `ExprKind::Constr`.
+    Constr(String, Option<Box<Pattern>>),
+    /// `p1 | p2`. Both sides bind the same names.
+    Or(Box<Pattern>, Box<Pattern>),
+    /// `p as x`.
+    Alias(Box<Pattern>, String),
+    /// `'c1'..'c2'` of the chars from `c1` to `c2` inclusive.
+    CharRange(u8, u8),
+}
+
+/// `pattern when guard -> body` of `match`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Case {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
+
+/// Decision tree which `match` is compiled to. It tests the parts of the matched value until
+/// it finds the case to evaluate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// No case matches.
+    Fail,
+    /// Selects the case at the index with the names bound to the parts of the value.
+    Leaf(usize, Vec<(String, Occurrence)>),
+    /// Selects the case like `Leaf` if its guard holds. Otherwise continues with the decision.
+    Guard(usize, Vec<(String, Occurrence)>, Box<Decision>),
+    /// Branches on the head of the part of the value, which has the type. The default is taken
+    /// if none of the heads is found. It is omitted if the heads cover all values of the type.
+    Switch(
+        Occurrence,
+        Type,
+        Vec<(Head, Decision)>,
+        Option<Box<Decision>>,
+    ),
+}
+
+/// Path from the matched value to its part. Each index selects a word of the block like the
+/// element of a tuple, the head (0) or the tail (1) of a list cell, or an argument of a
+/// constructor which follows the tag.
+pub type Occurrence = Vec<usize>;
+
+/// Head of a value tested by `Decision::Switch`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Head {
+    Constr(String),
+    Nil,
+    Cons,
+    Int(i64),
+    Bool(bool),
+    String(Vec<u8>),
+    Char(u8),
+    /// Chars from the first to the second inclusive, which no case distinguishes.
+    CharRange(u8, u8),
+}
+
+#[derive(Clone, Debug)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub loc: Loc,
+    /// Filled by type inference.
+    pub typ: Option<Type>,
+}
+
+// Locations and types are not compared so that trees written by hand equal parsed ones.
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Eq for Expr {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExprKind {
+    /// `()`.
+    UnitLiteral,
     IntLiteral(i64),
+    BoolLiteral(bool),
+    StringLiteral(Vec<u8>),
+    CharLiteral(u8),
+    /// Variable. Names in modules are qualified like `Char.code`.
+    Var(String),
+    Let(BindingGroup, Box<Expr>),
+    Apply(Box<Expr>, Vec<Expr>),
+    /// `fun params -> body`.
+    Fun(Vec<Pattern>, Box<Expr>),
+    /// `e1, e2, ...` of two or more expressions.
+    Tuple(Vec<Expr>),
+    /// `[e1; e2; ...]`. `[]` is the empty list.
+    List(Vec<Expr>),
+    /// `[| e1; e2; ... |]`.
+    Array(Vec<Expr>),
+    /// `e1.(e2)`.
+    Index(Box<Expr>, Box<Expr>),
+    /// `e1.(e2) <- e3`.
+    SetIndex(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Constructor applied to the argument if any. Constructors of several arguments are applied
+    /// to tuples like `C (e1, e2)`.
+    Constr(String, Option<Box<Expr>>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    UnOp(UnOp, Box<Expr>),
+    BinOp(BinOp, Box<Expr>, Box<Expr>),
+    /// `e1; e2`. The value of `e1` is discarded.
+    Seq(Box<Expr>, Box<Expr>),
+    /// `{ l1 = e1; l2 = e2; ... }`.
+    Record(Vec<(Label, Expr)>),
+    /// `{ e with l1 = e1; ... }`.
+    With(Box<Expr>, Vec<(Label, Expr)>),
+    /// `e.l`.
+    Field(Box<Expr>, Label),
+    /// `e1.l <- e2`.
+    SetField(Box<Expr>, Label, Box<Expr>),
+    /// `match e with p1 -> e1 | ...` with the decision tree filled by match compilation.
+    Match(Box<Expr>, Vec<Case>, Option<Decision>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    /// `!`.
+    Deref,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    /// `^`.
+    Concat,
+    /// `::`.
+    Cons,
+    /// `@`.
+    Append,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `&&`. The right operand is evaluated only if needed.
+    And,
+    /// `||`. The right operand is evaluated only if needed.
+    Or,
+    /// `:=`.
+    Assign,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Type {
+    Unit,
+    Int,
+    Bool,
+    String,
+    /// Byte like OCaml's char.
+    Char,
+    /// Type variable.
+    Var(usize),
+    /// Functions are curried like OCaml. `a -> b -> c` is `a -> (b -> c)`.
+    Fun(Box<Type>, Box<Type>),
+    /// `a * b * ...` of two or more types.
+    Tuple(Vec<Type>),
+    /// `a list`.
+    List(Box<Type>),
+    /// `a ref`.
+    Ref(Box<Type>),
+    /// `a array`.
+    Array(Box<Type>),
+    /// Type declared by `type`. The number is the index of the declaration in the file.
+    Named(String, usize),
+}
+
+/// Functions predefined in every program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Builtin {
+    Not,
+    CharCode,
+    CharChr,
+    StringOfInt,
+    StringOfBool,
+    PrintInt,
+    PrintChar,
+    PrintString,
+    PrintEndline,
+    PrintNewline,
+    Fst,
+    Snd,
+    ListHd,
+    ListTl,
+    Ref,
+    Incr,
+    Decr,
+    ArrayMake,
+    ArrayLength,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, loc: Loc) -> Self {
+        Self {
+            kind,
+            loc,
+            typ: None,
+        }
+    }
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind, loc: Loc) -> Self {
+        Self { kind, loc }
+    }
+
+    /// Names bound by the pattern in order of appearance.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match &self.kind {
+            PatternKind::Wildcard
+            | PatternKind::Unit
+            | PatternKind::IntLiteral(_)
+            | PatternKind::BoolLiteral(_)
+            | PatternKind::StringLiteral(_)
+            | PatternKind::CharLiteral(_)
+            | PatternKind::CharRange(..)
+            | PatternKind::Constr(_, None) => {}
+            PatternKind::Var(name) => names.push(name),
+            PatternKind::Tuple(pats) | PatternKind::List(pats) => {
+                for pat in pats {
+                    pat.collect_names(names);
+                }
+            }
+            PatternKind::Cons(head, tail) => {
+                head.collect_names(names);
+                tail.collect_names(names);
+            }
+            PatternKind::Constr(_, Some(arg)) => arg.collect_names(names),
+            // The right side binds the same names.
+            PatternKind::Or(left, _) => left.collect_names(names),
+            PatternKind::Alias(pat, name) => {
+                pat.collect_names(names);
+                names.push(name);
+            }
+        }
+    }
+}
+
+impl Label {
+    pub fn new(name: String, loc: Loc) -> Self {
+        Self { name, loc }
+    }
+}
+
+impl TypeExpr {
+    pub fn new(kind: TypeExprKind, loc: Loc) -> Self {
+        Self { kind, loc }
+    }
+}
+
+impl Binding {
+    /// Name of the binding if the pattern is a variable.
+    pub fn name(&self) -> Option<&str> {
+        match &self.pattern.kind {
+            PatternKind::Var(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl Type {
+    /// Type of functions which take the parameters in order.
+    pub fn fun(params: Vec<Type>, ret: Type) -> Self {
+        params
+            .into_iter()
+            .rev()
+            .fold(ret, |ret, param| Type::Fun(Box::new(param), Box::new(ret)))
+    }
+}
+
+impl Builtin {
+    pub const ALL: [Builtin; 19] = [
+        Builtin::Not,
+        Builtin::CharCode,
+        Builtin::CharChr,
+        Builtin::StringOfInt,
+        Builtin::StringOfBool,
+        Builtin::PrintInt,
+        Builtin::PrintChar,
+        Builtin::PrintString,
+        Builtin::PrintEndline,
+        Builtin::PrintNewline,
+        Builtin::Fst,
+        Builtin::Snd,
+        Builtin::ListHd,
+        Builtin::ListTl,
+        Builtin::Ref,
+        Builtin::Incr,
+        Builtin::Decr,
+        Builtin::ArrayMake,
+        Builtin::ArrayLength,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Not => "not",
+            Builtin::CharCode => "Char.code",
+            Builtin::CharChr => "Char.chr",
+            Builtin::StringOfInt => "string_of_int",
+            Builtin::StringOfBool => "string_of_bool",
+            Builtin::PrintInt => "print_int",
+            Builtin::PrintChar => "print_char",
+            Builtin::PrintString => "print_string",
+            Builtin::PrintEndline => "print_endline",
+            Builtin::PrintNewline => "print_newline",
+            Builtin::Fst => "fst",
+            Builtin::Snd => "snd",
+            Builtin::ListHd => "List.hd",
+            Builtin::ListTl => "List.tl",
+            Builtin::Ref => "ref",
+            Builtin::Incr => "incr",
+            Builtin::Decr => "decr",
+            Builtin::ArrayMake => "Array.make",
+            Builtin::ArrayLength => "Array.length",
+        }
+    }
+
+    pub fn typ(self) -> Type {
+        match self {
+            Builtin::Not => Type::fun(vec![Type::Bool], Type::Bool),
+            Builtin::CharCode => Type::fun(vec![Type::Char], Type::Int),
+            Builtin::CharChr => Type::fun(vec![Type::Int], Type::Char),
+            Builtin::StringOfInt => Type::fun(vec![Type::Int], Type::String),
+            Builtin::StringOfBool => Type::fun(vec![Type::Bool], Type::String),
+            Builtin::PrintInt => Type::fun(vec![Type::Int], Type::Unit),
+            Builtin::PrintChar => Type::fun(vec![Type::Char], Type::Unit),
+            Builtin::PrintString | Builtin::PrintEndline => {
+                Type::fun(vec![Type::String], Type::Unit)
+            }
+            Builtin::PrintNewline => Type::fun(vec![Type::Unit], Type::Unit),
+            // Type variables are quantified.
+            Builtin::Fst => Type::fun(
+                vec![Type::Tuple(vec![Type::Var(0), Type::Var(1)])],
+                Type::Var(0),
+            ),
+            Builtin::Snd => Type::fun(
+                vec![Type::Tuple(vec![Type::Var(0), Type::Var(1)])],
+                Type::Var(1),
+            ),
+            Builtin::ListHd => Type::fun(vec![Type::List(Box::new(Type::Var(0)))], Type::Var(0)),
+            Builtin::ListTl => Type::fun(
+                vec![Type::List(Box::new(Type::Var(0)))],
+                Type::List(Box::new(Type::Var(0))),
+            ),
+            Builtin::Ref => Type::fun(vec![Type::Var(0)], Type::Ref(Box::new(Type::Var(0)))),
+            Builtin::Incr | Builtin::Decr => {
+                Type::fun(vec![Type::Ref(Box::new(Type::Int))], Type::Unit)
+            }
+            Builtin::ArrayMake => Type::fun(
+                vec![Type::Int, Type::Var(0)],
+                Type::Array(Box::new(Type::Var(0))),
+            ),
+            Builtin::ArrayLength => Type::fun(vec![Type::Array(Box::new(Type::Var(0)))], Type::Int),
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Builtin::ArrayMake => 2,
+            _ => 1,
+        }
+    }
 }
 
 impl File {
@@ -40,6 +547,286 @@ impl File {
     }
 }
 
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stmt in &self.stmts {
+            writeln!(f, "{}", stmt)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Expr(expr) => write!(f, "(expr {})", expr),
+            Stmt::Let(group) => write!(f, "(let{})", group),
+            Stmt::Type(decls) => write!(f, "(type {})", join(decls)),
+        }
+    }
+}
+
+impl fmt::Display for TypeDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TypeDeclKind::Record(fields) => write!(f, "({} (record {}))", self.name, join(fields)),
+            TypeDeclKind::Variant(constrs) => {
+                write!(f, "({} (variant {}))", self.name, join(constrs))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConstrDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}", self.name)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for FieldDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.mutable {
+            write!(f, "(mutable {} {})", self.label, self.typ_expr)
+        } else {
+            write!(f, "({} {})", self.label, self.typ_expr)
+        }
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+// Written like `Type`.
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paren = |t: &TypeExpr| match t.kind {
+            TypeExprKind::Fun(..) | TypeExprKind::Tuple(_) => format!("({})", t),
+            _ => t.to_string(),
+        };
+        match &self.kind {
+            TypeExprKind::Constr(args, name) if args.is_empty() => write!(f, "{}", name),
+            TypeExprKind::Constr(args, name) if args.len() == 1 => {
+                write!(f, "{} {}", paren(&args[0]), name)
+            }
+            TypeExprKind::Constr(args, name) => {
+                let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "({}) {}", args.join(", "), name)
+            }
+            TypeExprKind::Tuple(types) => {
+                let types: Vec<_> = types.iter().map(paren).collect();
+                write!(f, "{}", types.join(" * "))
+            }
+            TypeExprKind::Fun(param, ret) if matches!(param.kind, TypeExprKind::Fun(..)) => {
+                write!(f, "({}) -> {}", param, ret)
+            }
+            TypeExprKind::Fun(param, ret) => write!(f, "{} -> {}", param, ret),
+        }
+    }
+}
+
+// Written after `let` like ` rec (f (x) ...) (g (y) ...)`.
+impl fmt::Display for BindingGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rec {
+            write!(f, " rec")?;
+        }
+        for binding in &self.bindings {
+            write!(f, " {}", binding)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({} ", self.pattern)?;
+        if !self.params.is_empty() {
+            write!(f, "({}) ", join(&self.params))?;
+        }
+        write!(f, "{})", self.value)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatternKind::Wildcard => write!(f, "_"),
+            PatternKind::Var(name) => write!(f, "{}", name),
+            PatternKind::Unit => write!(f, "()"),
+            PatternKind::Tuple(pats) => write!(f, "(tuple {})", join(pats)),
+            PatternKind::IntLiteral(n) => write!(f, "(int {})", n),
+            PatternKind::BoolLiteral(b) => write!(f, "(bool {})", b),
+            PatternKind::StringLiteral(s) => write!(f, "(string \"{}\")", escape_bytes(s)),
+            PatternKind::CharLiteral(c) => write!(f, "(char '{}')", escape_char(*c)),
+            PatternKind::List(pats) if pats.is_empty() => write!(f, "(list)"),
+            PatternKind::List(pats) => write!(f, "(list {})", join(pats)),
+            PatternKind::Cons(head, tail) => write!(f, "(:: {} {})", head, tail),
+            PatternKind::Constr(name, None) => write!(f, "(constr {})", name),
+            PatternKind::Constr(name, Some(arg)) => write!(f, "(constr {} {})", name, arg),
+            PatternKind::Or(left, right) => write!(f, "(| {} {})", left, right),
+            PatternKind::Alias(pat, name) => write!(f, "(as {} {})", pat, name),
+            PatternKind::CharRange(first, last) => write!(
+                f,
+                "(range '{}' '{}')",
+                escape_char(*first),
+                escape_char(*last)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.guard {
+            Some(guard) => write!(f, "({} (when {}) {})", self.pattern, guard, self.body),
+            None => write!(f, "({} {})", self.pattern, self.body),
+        }
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_fields(fields: &[(Label, Expr)]) -> String {
+    fields
+        .iter()
+        .map(|(label, expr)| format!("({} {})", label, expr))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExprKind::UnitLiteral => write!(f, "()"),
+            ExprKind::IntLiteral(n) => write!(f, "(int {})", n),
+            ExprKind::BoolLiteral(b) => write!(f, "(bool {})", b),
+            ExprKind::StringLiteral(s) => write!(f, "(string \"{}\")", escape_bytes(s)),
+            ExprKind::CharLiteral(c) => write!(f, "(char '{}')", escape_char(*c)),
+            ExprKind::Var(name) => write!(f, "(var {})", name),
+            ExprKind::Let(group, body) => write!(f, "(let{} {})", group, body),
+            ExprKind::Apply(func, args) => {
+                write!(f, "(apply {}", func)?;
+                for arg in args {
+                    write!(f, " {}", arg)?;
+                }
+                write!(f, ")")
+            }
+            ExprKind::Fun(params, body) => write!(f, "(fun ({}) {})", join(params), body),
+            ExprKind::Tuple(exprs) => write!(f, "(tuple {})", join(exprs)),
+            ExprKind::List(exprs) if exprs.is_empty() => write!(f, "(list)"),
+            ExprKind::List(exprs) => write!(f, "(list {})", join(exprs)),
+            ExprKind::Array(exprs) if exprs.is_empty() => write!(f, "(array)"),
+            ExprKind::Array(exprs) => write!(f, "(array {})", join(exprs)),
+            ExprKind::Index(expr, index) => write!(f, "(index {} {})", expr, index),
+            ExprKind::SetIndex(expr, index, value) => {
+                write!(f, "(setindex {} {} {})", expr, index, value)
+            }
+            ExprKind::Constr(name, None) => write!(f, "(constr {})", name),
+            ExprKind::Constr(name, Some(arg)) => write!(f, "(constr {} {})", name, arg),
+            ExprKind::If(cond, then, els) => write!(f, "(if {} {} {})", cond, then, els),
+            ExprKind::UnOp(op, expr) => write!(f, "({} {})", op, expr),
+            ExprKind::BinOp(op, lhs, rhs) => write!(f, "({} {} {})", op, lhs, rhs),
+            ExprKind::Seq(first, second) => write!(f, "(seq {} {})", first, second),
+            ExprKind::Record(fields) => write!(f, "(record {})", join_fields(fields)),
+            ExprKind::With(expr, fields) => write!(f, "(with {} {})", expr, join_fields(fields)),
+            ExprKind::Field(expr, label) => write!(f, "(field {} {})", expr, label),
+            ExprKind::SetField(expr, label, value) => {
+                write!(f, "(setfield {} {} {})", expr, label, value)
+            }
+            ExprKind::Match(expr, cases, _) => write!(f, "(match {} {})", expr, join(cases)),
+        }
+    }
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnOp::Neg => "neg",
+            UnOp::Deref => "deref",
+        })
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "mod",
+            BinOp::Concat => "^",
+            BinOp::Cons => "::",
+            BinOp::Append => "@",
+            BinOp::Eq => "=",
+            BinOp::Ne => "<>",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::Assign => ":=",
+        })
+    }
+}
+
+// Type variables are written as `'a`, `'b`, ... in the order of their numbers.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Unit => write!(f, "unit"),
+            Type::Int => write!(f, "int"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Char => write!(f, "char"),
+            Type::Var(n) if *n < 26 => write!(f, "'{}", (b'a' + *n as u8) as char),
+            Type::Var(n) => write!(f, "'t{}", n),
+            Type::Fun(param, ret) if matches!(**param, Type::Fun(..)) => {
+                write!(f, "({}) -> {}", param, ret)
+            }
+            Type::Fun(param, ret) => write!(f, "{} -> {}", param, ret),
+            // `*` binds tighter than `->` and is not associative.
+            Type::Tuple(types) => {
+                let types: Vec<_> = types
+                    .iter()
+                    .map(|t| match t {
+                        Type::Fun(..) | Type::Tuple(_) => format!("({})", t),
+                        _ => t.to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", types.join(" * "))
+            }
+            Type::List(elem) | Type::Ref(elem) | Type::Array(elem) => {
+                let name = match self {
+                    Type::List(_) => "list",
+                    Type::Ref(_) => "ref",
+                    _ => "array",
+                };
+                if matches!(**elem, Type::Fun(..) | Type::Tuple(_)) {
+                    write!(f, "({}) {}", elem, name)
+                } else {
+                    write!(f, "{} {}", elem, name)
+                }
+            }
+            Type::Named(name, _) => write!(f, "{}", name),
+        }
+    }
+}
+
 #[cfg(test)]
 mod file_tests {
     use super::*;
@@ -51,33 +838,339 @@ mod file_tests {
         assert_eq!(f.source, Some(s));
         assert!(f.stmts.is_empty());
     }
+
+    #[test]
+    fn test_fmt_let() {
+        let f = ast! { [{ let x = {int(1)} in { {var(x)} + {int(2)} } }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (let (x (int 1)) (+ (var x) (int 2))))\n"
+        );
+
+        let f = ast! { [{ let x = {int(1)} and y = {int(2)} in {var(y)} }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (let (x (int 1)) (y (int 2)) (var y)))\n"
+        );
+    }
+
+    #[test]
+    fn test_fmt_binding() {
+        let f = ast! { [
+            { let f (x _) = { {var(x)} + {int(1)} } },
+            { let y = { apply {var(f)} {int(1)} {int(2)} } },
+            { let rec g (x) = { apply {var(h)} {var(x)} } and h (x) = {var(x)} },
+        ] };
+        assert_eq!(
+            f.to_string(),
+            concat!(
+                "(let (f (x _) (+ (var x) (int 1))))\n",
+                "(let (y (apply (var f) (int 1) (int 2))))\n",
+                "(let rec (g (x) (apply (var h) (var x))) (h (x) (var x)))\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_fmt() {
+        let f = ast! { [{ int(1) }, { {int(2)} + { {int(3)} mod { neg {int(4)} } } }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (int 1))\n(expr (+ (int 2) (mod (int 3) (neg (int 4)))))\n"
+        );
+    }
+
+    #[test]
+    fn test_fmt_if() {
+        let f = ast! { [{
+            if { { {int(1)} < {int(2)} } && {bool(true)} } then {int(1)} else {int(2)}
+        }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (if (&& (< (int 1) (int 2)) (bool true)) (int 1) (int 2)))\n"
+        );
+    }
+
+    #[test]
+    fn test_fmt_string() {
+        let f = ast! { [{ {string("a\"b")} ^ {string("\n")} }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (^ (string \"a\\\"b\") (string \"\\n\")))\n"
+        );
+
+        let f = ast! { [{ {char(b'\'')} = {char(b'\n')} }] };
+        assert_eq!(f.to_string(), "(expr (= (char '\\'') (char '\\n')))\n");
+    }
+
+    #[test]
+    fn test_fmt_seq() {
+        let f = ast! { [{ { apply {var(print_int)} {int(1)} } ; { {unit} ; {unit} } }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (seq (apply (var print_int) (int 1)) (seq () ())))\n"
+        );
+    }
+
+    #[test]
+    fn test_fmt_fun() {
+        let f = ast! { [{ apply { fun (x _) -> { {var(x)} + {int(1)} } } {int(2)} {int(3)} }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (apply (fun (x _) (+ (var x) (int 1))) (int 2) (int 3)))\n"
+        );
+    }
+
+    #[test]
+    fn test_fmt_tuple() {
+        let f = ast! { [
+            { let (x, _) = { tuple {int(1)} { tuple {bool(true)} {unit} } } },
+            { let f ((a, b) ()) = {var(a)} },
+        ] };
+        assert_eq!(
+            f.to_string(),
+            concat!(
+                "(let ((tuple x _) (tuple (int 1) (tuple (bool true) ()))))\n",
+                "(let (f ((tuple a b) ()) (var a)))\n",
+            ),
+        );
+        assert_eq!(
+            f.stmts
+                .iter()
+                .map(|stmt| match stmt {
+                    Stmt::Let(group) => group.bindings[0].pattern.names(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>(),
+            [vec!["x"], vec!["f"]],
+        );
+    }
+
+    #[test]
+    fn test_fmt_type() {
+        let t = Type::fun(
+            vec![Type::Int, Type::Var(1)],
+            Type::fun(vec![Type::Var(30)], Type::Bool),
+        );
+        assert_eq!(t.to_string(), "int -> 'b -> 't30 -> bool");
+
+        let t = Type::fun(
+            vec![Type::fun(vec![Type::Int, Type::Int], Type::Bool)],
+            Type::Int,
+        );
+        assert_eq!(t.to_string(), "(int -> int -> bool) -> int");
+
+        let t = Type::fun(
+            vec![Type::Tuple(vec![
+                Type::Int,
+                Type::Tuple(vec![Type::Bool, Type::Char]),
+                Type::fun(vec![Type::Unit], Type::String),
+            ])],
+            Type::Tuple(vec![Type::Var(0), Type::Var(0)]),
+        );
+        assert_eq!(
+            t.to_string(),
+            "int * (bool * char) * (unit -> string) -> 'a * 'a"
+        );
+
+        let t = Type::fun(
+            vec![Type::List(Box::new(Type::List(Box::new(Type::Int))))],
+            Type::List(Box::new(Type::Tuple(vec![Type::Var(0), Type::Bool]))),
+        );
+        assert_eq!(t.to_string(), "int list list -> ('a * bool) list");
+
+        let t = Type::List(Box::new(Type::Named("point".to_string(), 0)));
+        assert_eq!(t.to_string(), "point list");
+
+        let t = Type::Ref(Box::new(Type::List(Box::new(Type::Ref(Box::new(
+            Type::fun(vec![Type::Int], Type::Int),
+        ))))));
+        assert_eq!(t.to_string(), "(int -> int) ref list ref");
+
+        let t = Type::Array(Box::new(Type::Array(Box::new(Type::Tuple(vec![
+            Type::Int,
+            Type::Char,
+        ])))));
+        assert_eq!(t.to_string(), "(int * char) array array");
+    }
+
+    #[test]
+    fn test_fmt_list() {
+        let f = ast! { [{ { {int(1)} :: { list {int(2)} {int(3)} } } @ { list } }] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (@ (:: (int 1) (list (int 2) (int 3))) (list)))\n"
+        );
+    }
+
+    #[test]
+    fn test_fmt_array() {
+        let f = ast! { [
+            { array {int(1)} {int(2)} },
+            { array },
+            { setindex {var(a)} {int(0)} { index {var(a)} {int(1)} } },
+        ] };
+        assert_eq!(
+            f.to_string(),
+            concat!(
+                "(expr (array (int 1) (int 2)))\n",
+                "(expr (array))\n",
+                "(expr (setindex (var a) (int 0) (index (var a) (int 1))))\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_fmt_record() {
+        let f = ast! { [
+            { record x = {int(1)}; y = {int(2)} },
+            { with {var(p)} x = { field {var(q)} x } },
+            { setfield {var(p)} x { {var(r)} := { deref {var(r)} } } },
+        ] };
+        assert_eq!(
+            f.to_string(),
+            concat!(
+                "(expr (record (x (int 1)) (y (int 2))))\n",
+                "(expr (with (var p) (x (field (var q) x))))\n",
+                "(expr (setfield (var p) x (:= (var r) (deref (var r)))))\n",
+            ),
+        );
+
+        let loc = Loc::head(None);
+        let constr = |args, name: &str| {
+            TypeExpr::new(TypeExprKind::Constr(args, name.to_string()), loc.clone())
+        };
+        let fun = TypeExpr::new(
+            TypeExprKind::Fun(
+                Box::new(constr(vec![], "int")),
+                Box::new(constr(vec![], "a")),
+            ),
+            loc.clone(),
+        );
+        let tuple = TypeExpr::new(
+            TypeExprKind::Tuple(vec![constr(vec![], "int"), fun.clone()]),
+            loc.clone(),
+        );
+        let decl = TypeDecl {
+            name: "t".to_string(),
+            kind: TypeDeclKind::Record(vec![
+                FieldDecl {
+                    mutable: false,
+                    label: Label::new("f".to_string(), loc.clone()),
+                    typ_expr: constr(vec![tuple], "list"),
+                    typ: None,
+                },
+                FieldDecl {
+                    mutable: true,
+                    label: Label::new("g".to_string(), loc.clone()),
+                    typ_expr: constr(vec![constr(vec![], "int"), fun], "map"),
+                    typ: None,
+                },
+            ]),
+            loc: loc.clone(),
+        };
+        assert_eq!(
+            Stmt::Type(vec![decl]).to_string(),
+            "(type (t (record (f (int * (int -> a)) list) (mutable g (int, int -> a) map))))"
+        );
+
+        let constr_decl = |name: &str, args| ConstrDecl {
+            name: name.to_string(),
+            args,
+            typs: None,
+            loc: loc.clone(),
+        };
+        let decl = TypeDecl {
+            name: "shape".to_string(),
+            kind: TypeDeclKind::Variant(vec![
+                constr_decl("Circle", vec![constr(vec![], "int")]),
+                constr_decl(
+                    "Rect",
+                    vec![
+                        constr(vec![], "int"),
+                        constr(vec![constr(vec![], "int")], "list"),
+                    ],
+                ),
+                constr_decl("Empty", vec![]),
+            ]),
+            loc,
+        };
+        assert_eq!(
+            Stmt::Type(vec![decl]).to_string(),
+            "(type (shape (variant (Circle int) (Rect int int list) (Empty))))"
+        );
+    }
+
+    #[test]
+    fn test_fmt_constr() {
+        let f = ast! { [
+            { constr Empty },
+            { constr Circle {int(1)} },
+            { constr Rect { tuple {int(1)} { constr Empty } } },
+        ] };
+        assert_eq!(
+            f.to_string(),
+            concat!(
+                "(expr (constr Empty))\n",
+                "(expr (constr Circle (int 1)))\n",
+                "(expr (constr Rect (tuple (int 1) (constr Empty))))\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn test_fmt_match() {
+        let f = ast! { [
+            { match {var(x)}
+                | (constr Some ((int 1), (char b'a'))) -> {int(1)}
+                | (:: x [_; ()]) -> {var(x)}
+                | [] -> {int(0)}
+                | (as (| (range b'a' b'z') (char b'_')) c) when {var(c)} -> {int(2)}
+            },
+        ] };
+        assert_eq!(
+            f.to_string(),
+            "(expr (match (var x) ((constr Some (tuple (int 1) (char 'a'))) (int 1)) \
+             ((:: x (list _ ())) (var x)) ((list) (int 0)) \
+             ((as (| (range 'a' 'z') (char '_')) c) (when (var c)) (int 2))))\n",
+        );
+        let Stmt::Expr(Expr {
+            kind: ExprKind::Match(_, cases, _),
+            ..
+        }) = &f.stmts[0]
+        else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(cases[1].pattern.names(), ["x"]);
+        assert_eq!(cases[3].pattern.names(), ["c"]);
+    }
 }
 
 #[macro_export]
 macro_rules! ast {
     (
-        $source:expr => [
+        [
             $(
                 { $( $stmt:tt )* }
             ),+ $(,)?
         ]
     ) => {
         $crate::File::init($crate::FileInitParams{
-            source: Some(Rc::clone(&$source)),
+            source: None,
             stmts: vec![
                 $( $crate::stmt!($( $stmt )*) ),+
             ]
         })
     };
     (
-        [
+        $source:expr => [
             $(
                 { $( $stmt:tt )* }
             ),+ $(,)?
         ]
     ) => {
         $crate::File::init($crate::FileInitParams{
-            source: None,
+            source: Some(Rc::clone(&$source)),
             stmts: vec![
                 $( $crate::stmt!($( $stmt )*) ),+
             ]
@@ -87,6 +1180,12 @@ macro_rules! ast {
 
 #[macro_export]
 macro_rules! stmt {
+    (let rec $( $name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* } )and+) => {
+        $crate::Stmt::Let($crate::bindings!(rec $( $name $( ( $( $param )+ ) )? = { $( $value )* } )and+))
+    };
+    (let $( $name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* } )and+) => {
+        $crate::Stmt::Let($crate::bindings!($( $name $( ( $( $param )+ ) )? = { $( $value )* } )and+))
+    };
     ($( $input:tt )* ) => {
         $crate::Stmt::Expr($crate::expr!($( $input )*))
     };
@@ -94,8 +1193,419 @@ macro_rules! stmt {
 
 #[macro_export]
 macro_rules! expr {
+    (unit) => {
+        $crate::Expr::new($crate::ExprKind::UnitLiteral, $crate::_Loc::head(None))
+    };
     (int ( $expr:expr )) => {
-        $crate::Expr::IntLiteral($expr)
+        $crate::Expr::new(
+            $crate::ExprKind::IntLiteral($expr),
+            $crate::_Loc::head(None),
+        )
+    };
+    (bool ( $expr:expr )) => {
+        $crate::Expr::new(
+            $crate::ExprKind::BoolLiteral($expr),
+            $crate::_Loc::head(None),
+        )
+    };
+    (string ( $expr:expr )) => {
+        $crate::Expr::new(
+            $crate::ExprKind::StringLiteral($expr.as_bytes().to_vec()),
+            $crate::_Loc::head(None),
+        )
+    };
+    (char ( $expr:expr )) => {
+        $crate::Expr::new(
+            $crate::ExprKind::CharLiteral($expr),
+            $crate::_Loc::head(None),
+        )
+    };
+    (var ( $name:tt )) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Var(stringify!($name).to_string()),
+            $crate::_Loc::head(None),
+        )
+    };
+    (let rec $( $name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* } )and+ in { $( $body:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Let(
+                $crate::bindings!(rec $( $name $( ( $( $param )+ ) )? = { $( $value )* } )and+),
+                Box::new($crate::expr!($( $body )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (let $( $name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* } )and+ in { $( $body:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Let(
+                $crate::bindings!($( $name $( ( $( $param )+ ) )? = { $( $value )* } )and+),
+                Box::new($crate::expr!($( $body )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (apply { $( $func:tt )* } $( { $( $arg:tt )* } )+) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Apply(
+                Box::new($crate::expr!($( $func )*)),
+                vec![$( $crate::expr!($( $arg )*) ),+],
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (fun ( $( $param:tt )+ ) -> { $( $body:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Fun(
+                vec![$( $crate::pattern!($param) ),+],
+                Box::new($crate::expr!($( $body )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (tuple $( { $( $expr:tt )* } )+) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Tuple(vec![$( $crate::expr!($( $expr )*) ),+]),
+            $crate::_Loc::head(None),
+        )
+    };
+    (list $( { $( $expr:tt )* } )*) => {
+        $crate::Expr::new(
+            $crate::ExprKind::List(vec![$( $crate::expr!($( $expr )*) ),*]),
+            $crate::_Loc::head(None),
+        )
+    };
+    (if { $( $cond:tt )* } then { $( $then:tt )* } else { $( $else:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::If(
+                Box::new($crate::expr!($( $cond )*)),
+                Box::new($crate::expr!($( $then )*)),
+                Box::new($crate::expr!($( $else )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (neg { $( $expr:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::UnOp(
+                $crate::UnOp::Neg,
+                Box::new($crate::expr!($( $expr )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (constr $name:ident) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Constr(stringify!($name).to_string(), None),
+            $crate::_Loc::head(None),
+        )
+    };
+    (constr $name:ident { $( $arg:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Constr(
+                stringify!($name).to_string(),
+                Some(Box::new($crate::expr!($( $arg )*))),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (match { $( $expr:tt )* }
+        $( | $pat:tt $( when { $( $guard:tt )* } )? -> { $( $body:tt )* } )+) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Match(
+                Box::new($crate::expr!($( $expr )*)),
+                vec![$(
+                    $crate::Case {
+                        pattern: $crate::pattern!($pat),
+                        guard: None $( .or(Some($crate::expr!($( $guard )*))) )?,
+                        body: $crate::expr!($( $body )*),
+                    }
+                ),+],
+                None,
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (array $( { $( $expr:tt )* } )*) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Array(vec![$( $crate::expr!($( $expr )*) ),*]),
+            $crate::_Loc::head(None),
+        )
+    };
+    (index { $( $expr:tt )* } { $( $index:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Index(
+                Box::new($crate::expr!($( $expr )*)),
+                Box::new($crate::expr!($( $index )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (setindex { $( $expr:tt )* } { $( $index:tt )* } { $( $value:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::SetIndex(
+                Box::new($crate::expr!($( $expr )*)),
+                Box::new($crate::expr!($( $index )*)),
+                Box::new($crate::expr!($( $value )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (deref { $( $expr:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::UnOp(
+                $crate::UnOp::Deref,
+                Box::new($crate::expr!($( $expr )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (setfield { $( $expr:tt )* } $label:ident { $( $value:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::SetField(
+                Box::new($crate::expr!($( $expr )*)),
+                $crate::Label::new(stringify!($label).to_string(), $crate::_Loc::head(None)),
+                Box::new($crate::expr!($( $value )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (record $( $label:ident = { $( $value:tt )* } );+) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Record(vec![$(
+                (
+                    $crate::Label::new(stringify!($label).to_string(), $crate::_Loc::head(None)),
+                    $crate::expr!($( $value )*),
+                )
+            ),+]),
+            $crate::_Loc::head(None),
+        )
+    };
+    (with { $( $expr:tt )* } $( $label:ident = { $( $value:tt )* } );+) => {
+        $crate::Expr::new(
+            $crate::ExprKind::With(
+                Box::new($crate::expr!($( $expr )*)),
+                vec![$(
+                    (
+                        $crate::Label::new(stringify!($label).to_string(), $crate::_Loc::head(None)),
+                        $crate::expr!($( $value )*),
+                    )
+                ),+],
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (field { $( $expr:tt )* } $label:ident) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Field(
+                Box::new($crate::expr!($( $expr )*)),
+                $crate::Label::new(stringify!($label).to_string(), $crate::_Loc::head(None)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    ({ $( $first:tt )* } ; { $( $second:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::Seq(
+                Box::new($crate::expr!($( $first )*)),
+                Box::new($crate::expr!($( $second )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    // `<>` and `:=` are not single token trees.
+    ({ $( $lhs:tt )* } <> { $( $rhs:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::BinOp(
+                $crate::BinOp::Ne,
+                Box::new($crate::expr!($( $lhs )*)),
+                Box::new($crate::expr!($( $rhs )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    ({ $( $lhs:tt )* } := { $( $rhs:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::BinOp(
+                $crate::BinOp::Assign,
+                Box::new($crate::expr!($( $lhs )*)),
+                Box::new($crate::expr!($( $rhs )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    ({ $( $lhs:tt )* } $op:tt { $( $rhs:tt )* }) => {
+        $crate::Expr::new(
+            $crate::ExprKind::BinOp(
+                $crate::binop!($op),
+                Box::new($crate::expr!($( $lhs )*)),
+                Box::new($crate::expr!($( $rhs )*)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! bindings {
+    (rec $( $name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* } )and+) => {
+        $crate::BindingGroup {
+            rec: true,
+            bindings: vec![$( $crate::binding!($name $( ( $( $param )+ ) )? = { $( $value )* }) ),+],
+        }
+    };
+    ($( $name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* } )and+) => {
+        $crate::BindingGroup {
+            rec: false,
+            bindings: vec![$( $crate::binding!($name $( ( $( $param )+ ) )? = { $( $value )* }) ),+],
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! binding {
+    ($name:tt $( ( $( $param:tt )+ ) )? = { $( $value:tt )* }) => {
+        $crate::Binding {
+            pattern: $crate::pattern!($name),
+            params: vec![$( $( $crate::pattern!($param) ),+ )?],
+            value: $crate::expr!($( $value )*),
+            typ: None,
+        }
+    };
+}
+
+/// Pattern of a token tree like `x`, `_`, `()`, `(x, (y, _))`, `(int 1)`, `[x; _]`,
+/// `(:: x l)` and `(constr Some x)`.
+#[macro_export]
+macro_rules! pattern {
+    (_) => {
+        $crate::Pattern::new($crate::PatternKind::Wildcard, $crate::_Loc::head(None))
+    };
+    (()) => {
+        $crate::Pattern::new($crate::PatternKind::Unit, $crate::_Loc::head(None))
+    };
+    ((int $n:literal)) => {
+        $crate::Pattern::new($crate::PatternKind::IntLiteral($n), $crate::_Loc::head(None))
+    };
+    ((char $c:literal)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::CharLiteral($c),
+            $crate::_Loc::head(None),
+        )
+    };
+    ([ $( $pat:tt );* ]) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::List(vec![$( $crate::pattern!($pat) ),*]),
+            $crate::_Loc::head(None),
+        )
+    };
+    ((:: $head:tt $tail:tt)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Cons(
+                Box::new($crate::pattern!($head)),
+                Box::new($crate::pattern!($tail)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    ((| $left:tt $right:tt)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Or(
+                Box::new($crate::pattern!($left)),
+                Box::new($crate::pattern!($right)),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    ((as $pat:tt $name:ident)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Alias(
+                Box::new($crate::pattern!($pat)),
+                stringify!($name).to_string(),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    ((range $first:literal $last:literal)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::CharRange($first, $last),
+            $crate::_Loc::head(None),
+        )
+    };
+    ((constr $name:ident)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Constr(stringify!($name).to_string(), None),
+            $crate::_Loc::head(None),
+        )
+    };
+    ((constr $name:ident $arg:tt)) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Constr(
+                stringify!($name).to_string(),
+                Some(Box::new($crate::pattern!($arg))),
+            ),
+            $crate::_Loc::head(None),
+        )
+    };
+    (( $( $pat:tt ),+ )) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Tuple(vec![$( $crate::pattern!($pat) ),+]),
+            $crate::_Loc::head(None),
+        )
+    };
+    ($name:tt) => {
+        $crate::Pattern::new(
+            $crate::PatternKind::Var(stringify!($name).to_string()),
+            $crate::_Loc::head(None),
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! binop {
+    (+) => {
+        $crate::BinOp::Add
+    };
+    (-) => {
+        $crate::BinOp::Sub
+    };
+    (*) => {
+        $crate::BinOp::Mul
+    };
+    (/) => {
+        $crate::BinOp::Div
+    };
+    (mod) => {
+        $crate::BinOp::Mod
+    };
+    (^) => {
+        $crate::BinOp::Concat
+    };
+    (::) => {
+        $crate::BinOp::Cons
+    };
+    (@) => {
+        $crate::BinOp::Append
+    };
+    (=) => {
+        $crate::BinOp::Eq
+    };
+    (<) => {
+        $crate::BinOp::Lt
+    };
+    (<=) => {
+        $crate::BinOp::Le
+    };
+    (>) => {
+        $crate::BinOp::Gt
+    };
+    (>=) => {
+        $crate::BinOp::Ge
+    };
+    (&&) => {
+        $crate::BinOp::And
+    };
+    (||) => {
+        $crate::BinOp::Or
     };
 }
 
@@ -103,10 +1613,38 @@ macro_rules! expr {
 mod macro_tests {
     use std::rc::Rc;
 
-    use super::ast;
     use crate as ast;
-    use tsuyu_asserts::assert_eq;
-    use tsuyu_source::Source;
+    use tsuyu_asserts::{assert_eq, assert_ne};
+    use tsuyu_source::{loc, Loc, Source};
+
+    fn int(n: i64) -> ast::Expr {
+        ast::Expr::new(ast::ExprKind::IntLiteral(n), Loc::head(None))
+    }
+
+    fn var(name: &str) -> ast::Pattern {
+        ast::Pattern::new(ast::PatternKind::Var(name.to_string()), Loc::head(None))
+    }
+
+    fn wildcard() -> ast::Pattern {
+        ast::Pattern::new(ast::PatternKind::Wildcard, Loc::head(None))
+    }
+
+    fn binop(op: ast::BinOp, lhs: ast::Expr, rhs: ast::Expr) -> ast::Expr {
+        ast::Expr::new(
+            ast::ExprKind::BinOp(op, Box::new(lhs), Box::new(rhs)),
+            Loc::head(None),
+        )
+    }
+
+    #[test]
+    fn test_expr_eq_ignores_loc() {
+        let s = Rc::new(Source::inline("1"));
+        assert_eq!(
+            ast::Expr::new(ast::ExprKind::IntLiteral(1), loc! {s => 0,1; 1,1}),
+            int(1),
+        );
+        assert_ne!(int(2), int(1));
+    }
 
     #[test]
     fn test_macro() {
@@ -120,10 +1658,7 @@ mod macro_tests {
             },
             ast::File {
                 source: Some(Rc::clone(&s)),
-                stmts: vec![
-                    ast::Stmt::Expr(ast::Expr::IntLiteral(1)),
-                    ast::Stmt::Expr(ast::Expr::IntLiteral(2)),
-                ],
+                stmts: vec![ast::Stmt::Expr(int(1)), ast::Stmt::Expr(int(2))],
             }
         );
 
@@ -131,14 +1666,128 @@ mod macro_tests {
             ast! { [{ int(1) }, { int(2) }] },
             ast::File {
                 source: None,
-                stmts: vec![
-                    ast::Stmt::Expr(ast::Expr::IntLiteral(1)),
-                    ast::Stmt::Expr(ast::Expr::IntLiteral(2)),
-                ],
+                stmts: vec![ast::Stmt::Expr(int(1)), ast::Stmt::Expr(int(2))],
             }
         );
 
-        assert_eq!(stmt! { int(1) }, ast::Stmt::Expr(ast::Expr::IntLiteral(1)));
-        assert_eq!(expr! { int(1) }, ast::Expr::IntLiteral(1));
+        assert_eq!(stmt! { int(1) }, ast::Stmt::Expr(int(1)));
+        assert_eq!(
+            stmt! { let f (x) = {int(1)} },
+            ast::Stmt::Let(ast::BindingGroup {
+                rec: false,
+                bindings: vec![ast::Binding {
+                    pattern: var("f"),
+                    params: vec![var("x")],
+                    value: int(1),
+                    typ: None,
+                }],
+            }),
+        );
+        assert_eq!(
+            stmt! { let rec f (x _) = {int(1)} and g = {int(2)} },
+            ast::Stmt::Let(ast::BindingGroup {
+                rec: true,
+                bindings: vec![
+                    ast::Binding {
+                        pattern: var("f"),
+                        params: vec![var("x"), wildcard()],
+                        value: int(1),
+                        typ: None,
+                    },
+                    ast::Binding {
+                        pattern: var("g"),
+                        params: vec![],
+                        value: int(2),
+                        typ: None,
+                    },
+                ],
+            }),
+        );
+        assert_eq!(
+            expr! { apply {int(1)} {int(2)} {int(3)} },
+            ast::Expr::new(
+                ast::ExprKind::Apply(Box::new(int(1)), vec![int(2), int(3)]),
+                Loc::head(None),
+            ),
+        );
+        assert_eq!(expr! { int(1) }, int(1));
+        assert_eq!(
+            expr! { {int(1)} - { {int(2)} * {int(3)} } },
+            binop(
+                ast::BinOp::Sub,
+                int(1),
+                binop(ast::BinOp::Mul, int(2), int(3))
+            ),
+        );
+        assert_eq!(
+            expr! { let _ = {int(1)} in {var(x)} },
+            ast::Expr::new(
+                ast::ExprKind::Let(
+                    ast::BindingGroup {
+                        rec: false,
+                        bindings: vec![ast::Binding {
+                            pattern: wildcard(),
+                            params: vec![],
+                            value: int(1),
+                            typ: None,
+                        }],
+                    },
+                    Box::new(ast::Expr::new(
+                        ast::ExprKind::Var("x".to_string()),
+                        Loc::head(None)
+                    )),
+                ),
+                Loc::head(None),
+            ),
+        );
+        assert_eq!(
+            expr! { neg { {int(1)} + {int(2)} } },
+            ast::Expr::new(
+                ast::ExprKind::UnOp(
+                    ast::UnOp::Neg,
+                    Box::new(binop(ast::BinOp::Add, int(1), int(2)))
+                ),
+                Loc::head(None),
+            ),
+        );
+        assert_eq!(
+            expr! { {int(1)} / {int(2)} },
+            binop(ast::BinOp::Div, int(1), int(2))
+        );
+        assert_eq!(
+            expr! { {int(1)} + {int(2)} },
+            binop(ast::BinOp::Add, int(1), int(2))
+        );
+        assert_eq!(
+            expr! { fun ((x, (_, ())) y) -> { tuple {var(x)} {int(1)} } },
+            ast::Expr::new(
+                ast::ExprKind::Fun(
+                    vec![
+                        ast::Pattern::new(
+                            ast::PatternKind::Tuple(vec![
+                                var("x"),
+                                ast::Pattern::new(
+                                    ast::PatternKind::Tuple(vec![
+                                        wildcard(),
+                                        ast::Pattern::new(ast::PatternKind::Unit, Loc::head(None)),
+                                    ]),
+                                    Loc::head(None),
+                                ),
+                            ]),
+                            Loc::head(None),
+                        ),
+                        var("y"),
+                    ],
+                    Box::new(ast::Expr::new(
+                        ast::ExprKind::Tuple(vec![
+                            ast::Expr::new(ast::ExprKind::Var("x".to_string()), Loc::head(None)),
+                            int(1),
+                        ]),
+                        Loc::head(None),
+                    )),
+                ),
+                Loc::head(None),
+            ),
+        );
     }
 }
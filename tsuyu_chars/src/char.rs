@@ -27,6 +27,20 @@ impl<'a> Char<'a> {
         }
     }
 
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// The byte if the character is a single byte. OCaml's `char` is a byte, so other characters
+    /// cannot be a char literal.
+    pub fn byte_value(&self) -> Option<u8> {
+        if self.len() == 1 {
+            Some(self.byte())
+        } else {
+            None
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -50,6 +64,15 @@ fn test_from_str() {
     assert_eq!(Char::from(c.to_string().as_str()).char(), c)
 }
 
+#[test]
+fn test_byte_value() {
+    assert_eq!(Char::from("a").byte_value(), Some(b'a'));
+    assert_eq!(Char::from("\n").byte_value(), Some(b'\n'));
+    assert_eq!(Char::from("あ").byte_value(), None);
+    assert_eq!(Char::from("a\u{310}").byte_value(), None);
+    assert_eq!(Char::from("a\u{310}").as_str(), "a\u{310}");
+}
+
 macro_rules! impl_code_eq {
     ($other: ty) => {
         #[allow(unused_lifetimes)]
@@ -105,6 +128,10 @@ pub fn is_newline(c: Char<'_>) -> bool {
     matches!(c.char(), '\n' | '\r')
 }
 
+pub fn is_binary_digit(c: Char<'_>) -> bool {
+    matches!(c.byte(), b'0' | b'1')
+}
+
 pub fn is_octal_digit(c: Char<'_>) -> bool {
     (b'0'..b'8').contains(&c.byte())
 }
@@ -117,6 +144,14 @@ pub fn is_hex_digit(c: Char<'_>) -> bool {
     c.char().is_ascii_hexdigit()
 }
 
+pub fn is_ident_start(c: Char<'_>) -> bool {
+    c.len() == 1 && matches!(c.char(), 'a'..='z' | 'A'..='Z' | '_')
+}
+
+pub fn is_ident_continue(c: Char<'_>) -> bool {
+    is_ident_start(c) || is_decimal_digit(c) || c == "'"
+}
+
 #[cfg(test)]
 mod tests {
     use crate::*;
@@ -180,6 +215,14 @@ mod tests {
         assert!(!is_newline(c.into()));
     }
 
+    #[rstest]
+    fn test_binary_digits(#[values("0", "1")] c: &str) {
+        assert!(is_binary_digit(c.into()));
+        assert!(is_octal_digit(c.into()));
+        assert!(is_decimal_digit(c.into()));
+        assert!(is_hex_digit(c.into()));
+    }
+
     #[rstest]
     fn test_octal_digits(#[values("0", "3", "7")] c: &str) {
         assert!(is_decimal_digit(c.into()));
@@ -189,6 +232,7 @@ mod tests {
 
     #[rstest]
     fn test_decimal_digits(#[values("8", "9")] c: &str) {
+        assert!(!is_binary_digit(c.into()));
         assert!(!is_octal_digit(c.into()));
         assert!(is_decimal_digit(c.into()));
         assert!(is_hex_digit(c.into()));
@@ -201,8 +245,27 @@ mod tests {
         assert!(is_hex_digit(c.into()));
     }
 
+    #[rstest]
+    fn test_ident_start(#[values("a", "z", "A", "Z", "_")] c: &str) {
+        assert!(is_ident_start(c.into()));
+        assert!(is_ident_continue(c.into()));
+    }
+
+    #[rstest]
+    fn test_ident_continue(#[values("0", "9", "'")] c: &str) {
+        assert!(!is_ident_start(c.into()));
+        assert!(is_ident_continue(c.into()));
+    }
+
+    #[rstest]
+    fn test_not_ident(#[values(" ", "+", "(", "あ", "a̐")] c: &str) {
+        assert!(!is_ident_start(c.into()));
+        assert!(!is_ident_continue(c.into()));
+    }
+
     #[rstest]
     fn test_not_digits(#[values("g", "三")] c: &str) {
+        assert!(!is_binary_digit(c.into()));
         assert!(!is_octal_digit(c.into()));
         assert!(!is_decimal_digit(c.into()));
         assert!(!is_hex_digit(c.into()));
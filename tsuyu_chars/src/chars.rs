@@ -8,6 +8,7 @@ use crate::Char;
 
 use unicode_segmentation::{Graphemes, UnicodeSegmentation};
 
+#[derive(Clone)]
 pub struct Chars<'a> {
     graphemes: Graphemes<'a>,
 }
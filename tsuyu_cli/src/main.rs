@@ -4,8 +4,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use clap::Parser;
-use std::io::{self, Write};
+use clap::{ArgEnum, Args, Parser, Subcommand};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+};
+use tsuyu_error::Error;
 use tsuyu_source::Source;
 
 #[derive(Parser)]
@@ -13,6 +19,7 @@ use tsuyu_source::Source;
     name = "tsuyu",
     version,
     about = "TsuyuCaml compiler",
+    args_conflicts_with_subcommands = true,
     help_template = "\
 {name} - {about}
 
@@ -20,23 +27,286 @@ use tsuyu_source::Source;
     {usage}
 {all-args}"
 )]
-struct App {}
+struct App {
+    #[clap(subcommand)]
+    command: Option<Command>,
 
-#[cfg(not(tarpaulin_include))]
-fn main() {
-    let _cli = App::parse();
-    match tsuyu::run(Source::inline("42")) {
+    /// Source files to compile and run. Same as `tsuyu run`.
+    #[clap(parse(from_os_str))]
+    files: Vec<PathBuf>,
+
+    /// Pipeline stage to emit for each file instead of running it. Same as `tsuyu build`.
+    #[clap(long, arg_enum)]
+    emit: Option<Emit>,
+
+    #[clap(flatten)]
+    driver: DriverArgs,
+}
+
+#[derive(Args)]
+struct DriverArgs {
+    /// C compiler used to assemble and link.
+    #[clap(long, default_value = "cc", parse(from_os_str))]
+    cc: PathBuf,
+
+    /// Assembler to use instead of the C compiler.
+    #[clap(long, parse(from_os_str))]
+    assembler: Option<PathBuf>,
+
+    /// Linker to use instead of the C compiler.
+    #[clap(long, parse(from_os_str))]
+    linker: Option<PathBuf>,
+
+    /// Extra argument passed to the linker. Can be repeated.
+    #[clap(
+        long = "link-arg",
+        multiple_occurrences = true,
+        allow_hyphen_values = true
+    )]
+    link_args: Vec<String>,
+
+    /// Keep intermediate files in the current directory.
+    #[clap(long)]
+    keep_temps: bool,
+
+    /// Print the values of top-level expressions like OCaml's toplevel.
+    #[clap(long)]
+    dump: bool,
+
+    /// Omit bounds checks of array accesses. Out-of-bounds accesses are undefined behavior.
+    #[clap(long = "unsafe")]
+    no_bounds_check: bool,
+}
+
+impl DriverArgs {
+    fn driver(self) -> tsuyu::Driver {
+        tsuyu::Driver::new(tsuyu::Config {
+            cc: self.cc,
+            assembler: self.assembler,
+            linker: self.linker,
+            link_args: self.link_args,
+            keep_temps: self.keep_temps.then(|| PathBuf::from(".")),
+            dump: self.dump,
+            no_bounds_check: self.no_bounds_check,
+        })
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compile a source file into an executable
+    Build {
+        /// Source file to compile. Use `-` to read from stdin.
+        #[clap(parse(from_os_str))]
+        file: PathBuf,
+
+        /// Output path. Defaults to the source file name without extension for `exe`,
+        /// with `.o` for `obj`, and stdout for the others.
+        #[clap(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+
+        /// Pipeline stage to emit.
+        #[clap(long, arg_enum, default_value = "exe")]
+        emit: Emit,
+
+        #[clap(flatten)]
+        driver: DriverArgs,
+    },
+    /// Compile and run source files
+    Run {
+        /// Source files to run. Use `-` to read from stdin.
+        #[clap(required = true, parse(from_os_str))]
+        files: Vec<PathBuf>,
+
+        #[clap(flatten)]
+        driver: DriverArgs,
+    },
+    /// Check source files and report errors without generating code
+    Check {
+        /// Source files to check. Use `-` to read from stdin.
+        #[clap(required = true, parse(from_os_str))]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+    Asm,
+    Obj,
+    Exe,
+}
+
+const FAILURE: i32 = 1;
+
+fn read_source(path: &Path) -> Option<Source> {
+    let result = if path.as_os_str() == "-" {
+        Source::read_from("<stdin>", io::stdin())
+    } else {
+        Source::read_file(path)
+    };
+    result
+        .map_err(|err| eprintln!("tsuyu: {}: {}", path.to_string_lossy(), err))
+        .ok()
+}
+
+fn report_errors(errs: &[Error]) {
+    eprintln!(
+        "{}",
+        errs.iter()
+            .map(|e| format!("{}", e))
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+// Warnings are printed like errors but do not fail the command.
+fn report_warnings(warnings: &[Error]) {
+    if !warnings.is_empty() {
+        report_errors(warnings);
+    }
+}
+
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(FAILURE)
+}
+
+fn default_output(file: &Path, emit: Emit) -> PathBuf {
+    let stem = match file.file_stem() {
+        Some(stem) if file.as_os_str() != "-" => PathBuf::from(stem),
+        _ => PathBuf::from("a"),
+    };
+    match emit {
+        Emit::Obj => stem.with_extension("o"),
+        _ if file.as_os_str() == "-" => PathBuf::from("a.out"),
+        _ => stem,
+    }
+}
+
+fn write_text(text: &str, output: Option<&Path>) -> i32 {
+    let result = match output {
+        Some(path) => fs::write(path, text)
+            .map_err(|err| eprintln!("tsuyu: {}: {}", path.to_string_lossy(), err)),
+        None => io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|err| eprintln!("tsuyu: {}", err)),
+    };
+    result.map_or(FAILURE, |_| 0)
+}
+
+fn build_file(driver: &tsuyu::Driver, file: &Path, output: Option<PathBuf>, emit: Emit) -> i32 {
+    let source = match read_source(file) {
+        Some(source) => source,
+        None => return FAILURE,
+    };
+    let output = output.as_deref();
+    let result = match emit {
+        Emit::Tokens => Ok(write_text(&tsuyu::emit_tokens(source), output)),
+        Emit::Ast => tsuyu::emit_ast(source).map(|text| write_text(&text, output)),
+        Emit::Ir => driver.emit_ir(source).map(|text| write_text(&text, output)),
+        Emit::Asm => driver.compile(source).map(|text| write_text(&text, output)),
+        Emit::Obj | Emit::Exe => {
+            let output = output.map_or_else(|| default_output(file, emit), Path::to_path_buf);
+            if emit == Emit::Obj {
+                driver.build_object(source, &output)
+            } else {
+                driver.build(source, &output)
+            }
+            .map(|_| 0)
+        }
+    };
+    report_warnings(&driver.take_warnings());
+    result.unwrap_or_else(|errs| {
+        report_errors(&errs);
+        FAILURE
+    })
+}
+
+fn run_file(driver: &tsuyu::Driver, file: &Path) -> i32 {
+    let source = match read_source(file) {
+        Some(source) => source,
+        None => return FAILURE,
+    };
+    let result = driver.run(source);
+    report_warnings(&driver.take_warnings());
+    match result {
         Ok(output) => {
             io::stdout().write_all(&output.stdout).unwrap();
             io::stderr().write_all(&output.stderr).unwrap();
-            std::process::exit(output.status.code().unwrap());
-        }
-        Err(errs) => eprintln!(
-            "{}",
-            errs.iter()
-                .map(|e| format!("{}", e))
-                .collect::<Vec<_>>()
-                .join("\n")
-        ),
+            exit_code(output.status)
+        }
+        Err(errs) => {
+            report_errors(&errs);
+            FAILURE
+        }
     }
 }
+
+fn check_file(file: &Path) -> i32 {
+    let source = match read_source(file) {
+        Some(source) => source,
+        None => return FAILURE,
+    };
+    match tsuyu::check(source) {
+        Ok(warnings) => {
+            report_warnings(&warnings);
+            0
+        }
+        Err(errs) => {
+            report_errors(&errs);
+            FAILURE
+        }
+    }
+}
+
+fn for_each_file(files: &[PathBuf], f: impl Fn(&Path) -> i32) -> i32 {
+    let mut code = 0;
+    for file in files {
+        let c = f(file);
+        if c != 0 {
+            code = c;
+        }
+    }
+    code
+}
+
+#[cfg(not(tarpaulin_include))]
+fn main() {
+    let cli = App::parse();
+    let code = match cli.command {
+        Some(Command::Build {
+            file,
+            output,
+            emit,
+            driver,
+        }) => build_file(&driver.driver(), &file, output, emit),
+        Some(Command::Run { files, driver }) => {
+            let driver = driver.driver();
+            for_each_file(&files, |file| run_file(&driver, file))
+        }
+        Some(Command::Check { files }) => for_each_file(&files, check_file),
+        None if cli.files.is_empty() => {
+            eprintln!("tsuyu: no input files");
+            FAILURE
+        }
+        None => {
+            let driver = cli.driver.driver();
+            match cli.emit {
+                Some(emit) => {
+                    for_each_file(&cli.files, |file| build_file(&driver, file, None, emit))
+                }
+                None => for_each_file(&cli.files, |file| run_file(&driver, file)),
+            }
+        }
+    };
+    std::process::exit(code);
+}
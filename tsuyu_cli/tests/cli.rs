@@ -0,0 +1,265 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+use tempfile::TempDir;
+
+fn tsuyu() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_tsuyu"))
+}
+
+fn write_source(dir: &TempDir, name: &str, code: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    fs::write(&path, code).unwrap();
+    path
+}
+
+fn run_with_stdin(args: &[&str], stdin: &str) -> Output {
+    let mut child = tsuyu()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn run_file() {
+    let dir = TempDir::new().unwrap();
+    let path = write_source(&dir, "a.ml", "1;; print_int 2;; print_newline ()");
+    let output = tsuyu().arg(&path).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn run_stdin() {
+    let output = run_with_stdin(&["-"], "print_endline \"42\"");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn run_dump() {
+    let output = run_with_stdin(&["--dump", "-"], "1 :: [2];; (\"a\", 'b')");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[1; 2]\n(\"a\", 'b')\n"
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_with_stdin(&["build", "--emit=ir", "--dump", "-"], "[]");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "function main(): int {\n    dump poly list []\n    return 0\n}\n"
+    );
+}
+
+#[test]
+fn unsafe_flag() {
+    let code = "let a = [| 1 |];; print_int a.(0)";
+    let output = run_with_stdin(&["build", "--emit=asm", "-"], code);
+    let asm = String::from_utf8(output.stdout).unwrap();
+    assert!(asm.contains("tsuyu_raise_index_out_of_bounds"));
+
+    let output = run_with_stdin(&["build", "--emit=asm", "--unsafe", "-"], code);
+    let asm = String::from_utf8(output.stdout).unwrap();
+    assert!(!asm.contains("tsuyu_raise_index_out_of_bounds"));
+
+    let output = run_with_stdin(&["--unsafe", "-"], code);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1");
+}
+
+#[test]
+fn compile_error() {
+    let output = run_with_stdin(&["-"], "x");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<stdin>:1:1 Unbound value x\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn match_warnings() {
+    let code = "type t = A | B;;\nprint_int (match A with A -> 1 | A -> 2)";
+    let partial =
+        "<stdin>:2:11 Warning 8 [partial-match]: this pattern-matching is not exhaustive.\n\
+                   Here is an example of a case that is not matched:\nB\n";
+    let unused = "<stdin>:2:34 Warning 11 [redundant-case]: this match case is unused.\n";
+    let output = run_with_stdin(&["-"], code);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("{}{}", partial, unused)
+    );
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_with_stdin(&["check", "-"], code);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("{}{}", partial, unused)
+    );
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn file_not_found() {
+    let output = tsuyu().arg(Path::new("not_found.ml")).output().unwrap();
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("tsuyu: not_found.ml: "));
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn no_input() {
+    let output = tsuyu().output().unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn run_subcommand() {
+    let output = run_with_stdin(&["run", "-"], "print_int 3; print_newline ()");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn build_subcommand() {
+    let dir = TempDir::new().unwrap();
+    let path = write_source(&dir, "a.ml", "print_int 5; print_newline ()");
+    let exe = dir.path().join("a");
+    let output = tsuyu()
+        .args([
+            "build".as_ref(),
+            path.as_os_str(),
+            "-o".as_ref(),
+            exe.as_os_str(),
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = Command::new(&exe).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "5\n");
+}
+
+#[test]
+fn build_subcommand_default_output() {
+    let dir = TempDir::new().unwrap();
+    write_source(&dir, "prog.ml", "5");
+    let output = tsuyu()
+        .current_dir(dir.path())
+        .args(["build", "prog.ml"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(dir.path().join("prog").exists());
+}
+
+#[test]
+fn check_subcommand() {
+    let output = run_with_stdin(&["check", "-"], "1;; 2");
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run_with_stdin(&["check", "-"], "x");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "<stdin>:1:1 Unbound value x\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn emit() {
+    let output = run_with_stdin(&["build", "--emit=tokens", "-"], "1\n 2");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "1:1 IntLiteral(1)\n2:2 IntLiteral(2)\n"
+    );
+
+    let output = run_with_stdin(&["build", "--emit=ast", "-"], "1");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "(expr (int 1))\n"
+    );
+
+    let output = run_with_stdin(&["build", "--emit=ir", "-"], "1");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "function main(): int {\n    eval 1\n    return 0\n}\n"
+    );
+
+    let output = run_with_stdin(&["build", "--emit=ir", "-"], "1 < 2; ()");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "function main(): int {\n    eval ((1 < 2); ())\n    return 0\n}\n"
+    );
+
+    let output = run_with_stdin(&["build", "--emit=asm", "-"], "1");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with(".intel_syntax noprefix\n"));
+
+    // The top-level form emits like `tsuyu build` instead of running.
+    let output = run_with_stdin(&["--emit=ir", "-"], "1");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "function main(): int {\n    eval 1\n    return 0\n}\n"
+    );
+}
+
+#[test]
+fn emit_to_file() {
+    let dir = TempDir::new().unwrap();
+    let path = write_source(&dir, "a.ml", "7");
+    let output = tsuyu()
+        .current_dir(dir.path())
+        .args(["build", "--emit=obj", "a.ml"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(dir.path().join("a.o").exists());
+
+    let ir = dir.path().join("a.ir");
+    let output = tsuyu()
+        .args(["build".as_ref(), "--emit=ir".as_ref(), path.as_os_str()])
+        .args(["-o".as_ref(), ir.as_os_str()])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        fs::read_to_string(ir).unwrap(),
+        "function main(): int {\n    eval 7\n    return 0\n}\n"
+    );
+}
+
+#[test]
+fn emit_compile_error() {
+    let output = run_with_stdin(&["build", "--emit=ast", "-"], "(");
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn toolchain_error() {
+    let output = run_with_stdin(&["run", "--cc", "tsuyu-no-such-cc", "-"], "1");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("tsuyu-no-such-cc -c -o "));
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -11,6 +11,47 @@ use tsuyu_source::Loc;
 #[derive(Display, Debug, Eq, PartialEq, Clone)]
 pub enum ErrorKind {
     Message(String),
+    /// An external tool such as the assembler or the linker could not be run or failed.
+    #[display(fmt = "{}: {}", command, message)]
+    Toolchain { command: String, message: String },
+    #[display(fmt = "Unbound value {}", _0)]
+    UnboundValue(String),
+    #[display(
+        fmt = "This expression has type {} but an expression was expected of type {}",
+        actual,
+        expected
+    )]
+    TypeMismatch {
+        actual: String,
+        expected: String,
+    },
+    #[display(
+        fmt = "This pattern matches values of type {} but a pattern was expected which matches values of type {}",
+        actual,
+        expected
+    )]
+    PatternTypeMismatch {
+        actual: String,
+        expected: String,
+    },
+    /// Warning of a `match` which does not cover the value printed like a pattern.
+    #[display(
+        fmt = "Warning 8 [partial-match]: this pattern-matching is not exhaustive.\n\
+               Here is an example of a case that is not matched:\n{}",
+        _0
+    )]
+    PartialMatch(String),
+    /// Same as `PartialMatch` for a value which the guard of a case may match.
+    #[display(
+        fmt = "Warning 8 [partial-match]: this pattern-matching is not exhaustive.\n\
+               Here is an example of a case that is not matched:\n{}\n\
+               (However, some guarded clause may match this value.)",
+        _0
+    )]
+    GuardedPartialMatch(String),
+    /// Warning of a case which matches no value left by the cases above it.
+    #[display(fmt = "Warning 11 [redundant-case]: this match case is unused.")]
+    UnusedCase,
 }
 
 impl From<String> for ErrorKind {
@@ -96,6 +137,48 @@ mod tests {
     #[test]
     fn test_error_format() {
         assert_eq!(format!("{}", error!("abc")), "abc");
+        assert_eq!(
+            format!(
+                "{}",
+                Error::new(ErrorKind::Toolchain {
+                    command: "cc".to_string(),
+                    message: "not found".to_string(),
+                })
+            ),
+            "cc: not found",
+        );
+        assert_eq!(
+            format!("{}", Error::new(ErrorKind::UnboundValue("x".to_string()))),
+            "Unbound value x",
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Error::new(ErrorKind::PatternTypeMismatch {
+                    actual: "int".to_string(),
+                    expected: "bool".to_string(),
+                })
+            ),
+            "This pattern matches values of type int but a pattern was expected which matches values of type bool",
+        );
+        assert_eq!(
+            format!("{}", Error::new(ErrorKind::PartialMatch("C _".to_string()))),
+            "Warning 8 [partial-match]: this pattern-matching is not exhaustive.\n\
+             Here is an example of a case that is not matched:\nC _",
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                Error::new(ErrorKind::GuardedPartialMatch("C _".to_string()))
+            ),
+            "Warning 8 [partial-match]: this pattern-matching is not exhaustive.\n\
+             Here is an example of a case that is not matched:\nC _\n\
+             (However, some guarded clause may match this value.)",
+        );
+        assert_eq!(
+            format!("{}", Error::new(ErrorKind::UnusedCase)),
+            "Warning 11 [redundant-case]: this match case is unused.",
+        );
         let s = Rc::new(Source::inline(""));
 
         let loc = loc! {s => 0,2;1,1};
@@ -9,7 +9,7 @@ use std::{iter::Peekable, rc::Rc};
 use tsuyu_utils::must;
 use tsuyu_chars::*;
 use tsuyu_source::{Loc, Source};
-use tsuyu_token::{Token, TokenKind, TokenReader};
+use tsuyu_token::{Token, TokenKind, TokenReader, INT_LITERAL_OVERFLOW};
 
 pub struct Lexer<'a> {
     chars: Peekable<Chars<'a>>,
@@ -26,7 +26,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn peek_char(&mut self) -> Option<Char<'_>> {
+    fn peek_char(&mut self) -> Option<Char<'a>> {
         self.chars.peek().copied()
     }
 
@@ -34,7 +34,7 @@ impl<'a> Lexer<'a> {
         self.peek_char().is_none()
     }
 
-    fn consume(&mut self) -> Option<Char<'_>> {
+    fn consume(&mut self) -> Option<Char<'a>> {
         if self.eof() {
             panic!("Unexpected EOF")
         }
@@ -45,7 +45,7 @@ impl<'a> Lexer<'a> {
         self.loc_head.column += 1;
         self.loc.len += c.len();
 
-        if c == "\r" && self.peek_char().map_or(false, |c| c == "\n") {
+        if c == "\r" && self.peek_char().is_some_and(|c| c == "\n") {
             self.consume();
             self.loc_head.line -= 1; // rollback incremented line.
         }
@@ -67,47 +67,395 @@ impl<'a> Lexer<'a> {
     }
 
     fn consume_while(&mut self, mut f: impl FnMut(Char<'_>) -> bool) {
-        while self.consume_if(|c| f(c)).is_some() {}
+        while self.consume_if(&mut f).is_some() {}
     }
 
     fn skip_whitespaces(&mut self) {
         self.consume_while(is_whitespace);
     }
 
+    fn starts_with(&self, s: &str) -> bool {
+        let mut chars = self.chars.clone();
+        Chars::from(s).all(|c| chars.next() == Some(c))
+    }
+
     fn new_token(&self, kind: TokenKind) -> Token {
         Token {
             loc: self.loc.clone(),
             kind,
         }
     }
+
+    fn error_token(&self, msg: &str) -> Token {
+        self.new_token(TokenKind::Error(msg.to_string()))
+    }
 }
 
 impl<'a> Lexer<'a> {
     fn read(&mut self) -> Option<Token> {
-        self.skip_whitespaces();
-        self.loc = self.loc_head.clone();
-        self.loc.len = 0;
+        loop {
+            self.skip_whitespaces();
+            self.loc = self.loc_head.clone();
+            self.loc.len = 0;
 
-        if self.eof() {
-            return None;
+            if !self.starts_with("(*") {
+                break;
+            }
+            if !self.skip_comment() {
+                return Some(self.error_token("Unterminated comment"));
+            }
         }
 
-        if is_decimal_digit(self.peek_char()?) {
+        let c = self.peek_char()?;
+        if is_decimal_digit(c) {
             Some(self.read_number())
+        } else if c == "\"" {
+            Some(self.read_string())
+        } else if c == "'" {
+            Some(self.read_quote())
+        } else if is_ident_start(c) {
+            Some(self.read_word())
         } else {
-            self.consume_while(|c| !is_decimal_digit(c));
-            Some(self.new_token(TokenKind::Error("err".to_string())))
+            Some(self.read_symbol())
+        }
+    }
+
+    fn skip_comment(&mut self) -> bool {
+        let mut depth = 0;
+        loop {
+            if self.starts_with("(*") {
+                depth += 1;
+            } else if self.starts_with("*)") {
+                depth -= 1;
+            } else if self.eof() {
+                return false;
+            } else {
+                self.consume();
+                continue;
+            }
+
+            self.consume();
+            self.consume();
+            if depth == 0 {
+                return true;
+            }
         }
     }
 
+    // Consumes `0x`, `0o` or `0b` only if a digit follows like OCaml.
+    fn read_radix_prefix(&mut self) -> u32 {
+        let mut chars = self.chars.clone();
+        let radix = match (chars.next(), chars.next().map(|c| c.char()), chars.next()) {
+            (Some(zero), Some(prefix), Some(digit)) if zero == "0" => match prefix {
+                'x' | 'X' if is_hex_digit(digit) => 16,
+                'o' | 'O' if is_octal_digit(digit) => 8,
+                'b' | 'B' if is_binary_digit(digit) => 2,
+                _ => 10,
+            },
+            _ => 10,
+        };
+        if radix != 10 {
+            self.consume();
+            self.consume();
+        }
+        radix
+    }
+
     fn read_number(&mut self) -> Token {
-        let mut value = 0;
-        while let Some(c) = self.consume_if(is_decimal_digit) {
-            value *= 10;
-            value += must!(c.char().to_digit(10));
+        let radix = self.read_radix_prefix();
+        let is_digit = match radix {
+            2 => is_binary_digit,
+            8 => is_octal_digit,
+            16 => is_hex_digit,
+            _ => is_decimal_digit,
+        };
+
+        let mut value = Some(0u64);
+        while let Some(c) = self.consume_if(|c| is_digit(c) || c == "_") {
+            if let Some(digit) = c.char().to_digit(radix) {
+                value = value
+                    .and_then(|v| v.checked_mul(radix.into()))
+                    .and_then(|v| v.checked_add(digit.into()));
+            }
+        }
+
+        if self.peek_char().is_some_and(is_ident_continue) {
+            self.consume_while(is_ident_continue);
+            return self.error_token("Invalid integer literal");
+        }
+        match value {
+            // 2^63 becomes min_int. The parser accepts it only with unary minus.
+            Some(v) if v <= 1 << 63 => self.new_token(TokenKind::IntLiteral(v as i64)),
+            _ => self.error_token(INT_LITERAL_OVERFLOW),
+        }
+    }
+
+    // Reads to the closing quote even after an error to report only the first one.
+    fn read_string(&mut self) -> Token {
+        self.consume();
+        let mut bytes = Vec::new();
+        let mut error = None;
+        loop {
+            let c = match self.peek_char() {
+                Some(c) => c,
+                None => return self.error_token("Unterminated string literal"),
+            };
+            self.consume();
+            match c.as_str() {
+                "\"" => break,
+                "\\" => match self.read_escape() {
+                    Ok(escaped) => bytes.extend(escaped),
+                    Err(msg) => {
+                        error.get_or_insert(msg);
+                    }
+                },
+                s => bytes.extend(s.as_bytes()),
+            }
+        }
+        match error {
+            Some(msg) => self.error_token(&msg),
+            None => self.new_token(TokenKind::StringLiteral(bytes)),
+        }
+    }
+
+    // Reads a char literal, or a quote of a type variable like `'a`.
+    fn read_quote(&mut self) -> Token {
+        self.consume();
+        let mut chars = self.chars.clone();
+        let (c, next) = match (chars.next(), chars.next()) {
+            (Some(c), next) => (c, next),
+            (None, _) => return self.new_token(TokenKind::Quote),
+        };
+        let byte = match c.as_str() {
+            "\\" => {
+                self.consume();
+                // Unicode escapes and line continuations are only for strings.
+                let escaped = match self.peek_char().map(|c| c.char()) {
+                    Some('u') | Some('\n') | Some('\r') => {
+                        let c = must!(self.consume());
+                        Err(format!(
+                            "Illegal backslash escape in string or character (\\{})",
+                            c.as_str().escape_default()
+                        ))
+                    }
+                    _ => self.read_escape(),
+                };
+                let closed = self.consume_if(|c| c == "'").is_some();
+                match escaped {
+                    Err(msg) => return self.error_token(&msg),
+                    Ok(_) if !closed => return self.error_token("Unterminated char literal"),
+                    Ok(bytes) => bytes[0],
+                }
+            }
+            "'" | "\n" | "\r" | "\r\n" => return self.new_token(TokenKind::Quote),
+            _ if next.is_some_and(|c| c == "'") => {
+                self.consume();
+                self.consume();
+                match c.byte_value() {
+                    Some(b) => b,
+                    None => return self.error_token("Char literal must be a single byte"),
+                }
+            }
+            _ => return self.new_token(TokenKind::Quote),
+        };
+        self.new_token(TokenKind::CharLiteral(byte))
+    }
+
+    // Reads an escape sequence after a backslash and returns its bytes.
+    fn read_escape(&mut self) -> Result<Vec<u8>, String> {
+        let illegal = |seq: &str, reason: Option<String>| {
+            let msg = format!(
+                "Illegal backslash escape in string or character (\\{})",
+                seq
+            );
+            match reason {
+                Some(reason) => format!("{}: {}", msg, reason),
+                None => msg,
+            }
+        };
+        let c = match self.peek_char() {
+            Some(c) => c.char(),
+            None => return Ok(Vec::new()),
+        };
+        self.consume();
+        let byte = match c {
+            '\\' | '"' | '\'' | ' ' => c as u8,
+            'n' => b'\n',
+            't' => b'\t',
+            'b' => b'\x08',
+            'r' => b'\r',
+            // Skips the newline and blanks at the beginning of the next line.
+            '\n' | '\r' => {
+                self.consume_while(|c| c == " " || c == "\t");
+                return Ok(Vec::new());
+            }
+            '0'..='9' => {
+                let digits = self.read_digits(c, 2, is_decimal_digit);
+                return match digits.parse::<u8>() {
+                    Ok(b) if digits.len() == 3 => Ok(vec![b]),
+                    Ok(_) => Err(illegal(&digits, None)),
+                    Err(_) => Err(illegal(&digits, Some(format!("{} > 255", digits)))),
+                };
+            }
+            'o' => {
+                let digits = self.read_digits(c, 3, is_octal_digit);
+                return match u8::from_str_radix(&digits[1..], 8) {
+                    Ok(b) if digits.len() == 4 => Ok(vec![b]),
+                    Ok(_) => Err(illegal(&digits, None)),
+                    Err(_) => Err(illegal(&digits, Some(format!("o{} > o377", &digits[1..])))),
+                };
+            }
+            'x' => {
+                let digits = self.read_digits(c, 2, is_hex_digit);
+                return match u8::from_str_radix(&digits[1..], 16) {
+                    Ok(b) if digits.len() == 3 => Ok(vec![b]),
+                    _ => Err(illegal(&digits, None)),
+                };
+            }
+            'u' if self.peek_char().is_some_and(|c| c == "{") => {
+                let mut seq = String::from("u{");
+                self.consume();
+                while let Some(c) = self.consume_if(is_hex_digit) {
+                    seq.push(c.char());
+                }
+                let hex = seq[2..].to_string();
+                if self.consume_if(|c| c == "}").is_none() {
+                    return Err(illegal(&seq, None));
+                }
+                seq.push('}');
+                if hex.is_empty() || hex.len() > 6 {
+                    return Err(illegal(&seq, None));
+                }
+                return match char::from_u32(u32::from_str_radix(&hex, 16).unwrap()) {
+                    Some(c) => Ok(c.encode_utf8(&mut [0; 4]).as_bytes().to_vec()),
+                    None => Err(illegal(
+                        &seq,
+                        Some(format!(
+                            "{} is not a Unicode scalar value",
+                            hex.to_uppercase()
+                        )),
+                    )),
+                };
+            }
+            _ => return Err(illegal(&c.to_string(), None)),
+        };
+        Ok(vec![byte])
+    }
+
+    // Reads at most `n` digits following the first character of an escape sequence.
+    fn read_digits(&mut self, first: char, n: usize, is_digit: fn(Char<'_>) -> bool) -> String {
+        let mut digits = first.to_string();
+        for _ in 0..n {
+            match self.consume_if(is_digit) {
+                Some(c) => digits.push(c.char()),
+                None => break,
+            }
+        }
+        digits
+    }
+
+    fn read_word(&mut self) -> Token {
+        let mut word = String::new();
+        while let Some(c) = self.consume_if(is_ident_continue) {
+            word.push(c.char());
         }
-        self.new_token(TokenKind::IntLiteral(value.into()))
+        let kind = keyword(&word).unwrap_or(TokenKind::Ident(word));
+        self.new_token(kind)
     }
+
+    fn read_symbol(&mut self) -> Token {
+        let kind = match must!(self.consume()).char() {
+            '+' => TokenKind::Plus,
+            '-' if self.peek_char().is_some_and(|c| c == ">") => {
+                self.consume_symbol(TokenKind::Arrow)
+            }
+            '-' => TokenKind::Minus,
+            '*' => TokenKind::Star,
+            '/' => TokenKind::Slash,
+            '^' => TokenKind::Caret,
+            '@' => TokenKind::At,
+            ':' if self.peek_char().is_some_and(|c| c == ":") => {
+                self.consume_symbol(TokenKind::ColonColon)
+            }
+            ':' if self.peek_char().is_some_and(|c| c == "=") => {
+                self.consume_symbol(TokenKind::ColonEq)
+            }
+            ':' => TokenKind::Colon,
+            '!' => TokenKind::Bang,
+            '.' if self.peek_char().is_some_and(|c| c == ".") => {
+                self.consume_symbol(TokenKind::DotDot)
+            }
+            '.' => TokenKind::Dot,
+            ',' => TokenKind::Comma,
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '[' if self.peek_char().is_some_and(|c| c == "|") => {
+                self.consume_symbol(TokenKind::LBracketBar)
+            }
+            '[' => TokenKind::LBracket,
+            ']' => TokenKind::RBracket,
+            '{' => TokenKind::LBrace,
+            '}' => TokenKind::RBrace,
+            '=' => TokenKind::Eq,
+            '<' => match self.peek_char().map(|c| c.char()) {
+                Some('=') => self.consume_symbol(TokenKind::Le),
+                Some('>') => self.consume_symbol(TokenKind::Ne),
+                Some('-') => self.consume_symbol(TokenKind::LArrow),
+                _ => TokenKind::Lt,
+            },
+            '>' if self.peek_char().is_some_and(|c| c == "=") => self.consume_symbol(TokenKind::Ge),
+            '>' => TokenKind::Gt,
+            '&' if self.peek_char().is_some_and(|c| c == "&") => {
+                self.consume_symbol(TokenKind::AndAnd)
+            }
+            '|' if self.peek_char().is_some_and(|c| c == "|") => {
+                self.consume_symbol(TokenKind::BarBar)
+            }
+            '|' if self.peek_char().is_some_and(|c| c == "]") => {
+                self.consume_symbol(TokenKind::BarRBracket)
+            }
+            '|' => TokenKind::Bar,
+            ';' if self.peek_char().is_some_and(|c| c == ";") => {
+                self.consume_symbol(TokenKind::SemiSemi)
+            }
+            ';' => TokenKind::Semi,
+            _ => return self.error_token("Unexpected character"),
+        };
+        self.new_token(kind)
+    }
+
+    // Consumes the second character of a two-character symbol.
+    fn consume_symbol(&mut self, kind: TokenKind) -> TokenKind {
+        self.consume();
+        kind
+    }
+}
+
+fn keyword(word: &str) -> Option<TokenKind> {
+    Some(match word {
+        "_" => TokenKind::Underscore,
+        "and" => TokenKind::And,
+        "as" => TokenKind::As,
+        "begin" => TokenKind::Begin,
+        "else" => TokenKind::Else,
+        "end" => TokenKind::End,
+        "false" => TokenKind::False,
+        "fun" => TokenKind::Fun,
+        "if" => TokenKind::If,
+        "in" => TokenKind::In,
+        "let" => TokenKind::Let,
+        "match" => TokenKind::Match,
+        "mod" => TokenKind::Mod,
+        "mutable" => TokenKind::Mutable,
+        "of" => TokenKind::Of,
+        "rec" => TokenKind::Rec,
+        "then" => TokenKind::Then,
+        "true" => TokenKind::True,
+        "type" => TokenKind::Type,
+        "when" => TokenKind::When,
+        "with" => TokenKind::With,
+        _ => return None,
+    })
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -127,7 +475,7 @@ mod tests {
     use crate::*;
     use tsuyu_asserts::*;
     use tsuyu_source::{loc, Source};
-    use tsuyu_token::token;
+    use tsuyu_token::{token, Token, TokenKind};
 
     #[test]
     fn test_newline() {
@@ -147,6 +495,456 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_symbols() {
+        let s = Rc::new(Source::inline("(1+2,)*3-4/5 mod 6"));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::LParen,
+                TokenKind::IntLiteral(1),
+                TokenKind::Plus,
+                TokenKind::IntLiteral(2),
+                TokenKind::Comma,
+                TokenKind::RParen,
+                TokenKind::Star,
+                TokenKind::IntLiteral(3),
+                TokenKind::Minus,
+                TokenKind::IntLiteral(4),
+                TokenKind::Slash,
+                TokenKind::IntLiteral(5),
+                TokenKind::Mod,
+                TokenKind::IntLiteral(6),
+            ],
+        );
+
+        let s = Rc::new(Source::inline("[1; 2]::[]@x : y"));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::LBracket,
+                TokenKind::IntLiteral(1),
+                TokenKind::Semi,
+                TokenKind::IntLiteral(2),
+                TokenKind::RBracket,
+                TokenKind::ColonColon,
+                TokenKind::LBracket,
+                TokenKind::RBracket,
+                TokenKind::At,
+                TokenKind::Ident("x".to_string()),
+                TokenKind::Colon,
+                TokenKind::Ident("y".to_string()),
+            ],
+        );
+
+        let s = Rc::new(Source::inline(
+            "{x = 1; y : int} type with mutable of match when as",
+        ));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::LBrace,
+                TokenKind::Ident("x".to_string()),
+                TokenKind::Eq,
+                TokenKind::IntLiteral(1),
+                TokenKind::Semi,
+                TokenKind::Ident("y".to_string()),
+                TokenKind::Colon,
+                TokenKind::Ident("int".to_string()),
+                TokenKind::RBrace,
+                TokenKind::Type,
+                TokenKind::With,
+                TokenKind::Mutable,
+                TokenKind::Of,
+                TokenKind::Match,
+                TokenKind::When,
+                TokenKind::As,
+            ],
+        );
+
+        let s = Rc::new(Source::inline("1 mod 2"));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                token!(1, loc! {s => 0,1; 1,1}),
+                Token::new(TokenKind::Mod, loc! {s => 2,5; 1,3}),
+                token!(2, loc! {s => 6,7; 1,7}),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_two_char_symbols() {
+        let s = Rc::new(Source::inline(
+            "= <> < <= > >= && || <<> & | -> - > := <- !r <<- ::= [|1|] [||] [ | ] a.(0) 'a'..'z' . .",
+        ));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::Eq,
+                TokenKind::Ne,
+                TokenKind::Lt,
+                TokenKind::Le,
+                TokenKind::Gt,
+                TokenKind::Ge,
+                TokenKind::AndAnd,
+                TokenKind::BarBar,
+                TokenKind::Lt,
+                TokenKind::Ne,
+                TokenKind::Error("Unexpected character".to_string()),
+                TokenKind::Bar,
+                TokenKind::Arrow,
+                TokenKind::Minus,
+                TokenKind::Gt,
+                TokenKind::ColonEq,
+                TokenKind::LArrow,
+                TokenKind::Bang,
+                TokenKind::Ident("r".to_string()),
+                TokenKind::Lt,
+                TokenKind::LArrow,
+                TokenKind::ColonColon,
+                TokenKind::Eq,
+                TokenKind::LBracketBar,
+                TokenKind::IntLiteral(1),
+                TokenKind::BarRBracket,
+                TokenKind::LBracketBar,
+                TokenKind::BarRBracket,
+                TokenKind::LBracket,
+                TokenKind::Bar,
+                TokenKind::RBracket,
+                TokenKind::Ident("a".to_string()),
+                TokenKind::Dot,
+                TokenKind::LParen,
+                TokenKind::IntLiteral(0),
+                TokenKind::RParen,
+                TokenKind::CharLiteral(b'a'),
+                TokenKind::DotDot,
+                TokenKind::CharLiteral(b'z'),
+                TokenKind::Dot,
+                TokenKind::Dot,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_comment() {
+        let s = Rc::new(Source::inline("1 (* a (* nested *) *) 2 (**) 3"));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                token!(1, loc! {s => 0,1; 1,1}),
+                token!(2, loc! {s => 23,24; 1,24}),
+                token!(3, loc! {s => 30,31; 1,31}),
+            ],
+        );
+
+        let s = Rc::new(Source::inline("1 (* (* *)"));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                token!(1, loc! {s => 0,1; 1,1}),
+                Token::new(
+                    TokenKind::Error("Unterminated comment".to_string()),
+                    loc! {s => 2,10; 1,3}
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_error() {
+        let s = Rc::new(Source::inline("1 ? abc"));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                token!(1, loc! {s => 0,1; 1,1}),
+                Token::new(
+                    TokenKind::Error("Unexpected character".to_string()),
+                    loc! {s => 2,3; 1,3}
+                ),
+                Token::new(TokenKind::Ident("abc".to_string()), loc! {s => 4,7; 1,5}),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_semi_semi() {
+        let s = Rc::new(Source::inline("1;; ;;;"));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                token!(1, loc! {s => 0,1; 1,1}),
+                Token::new(TokenKind::SemiSemi, loc! {s => 1,3; 1,2}),
+                Token::new(TokenKind::SemiSemi, loc! {s => 4,6; 1,5}),
+                Token::new(TokenKind::Semi, loc! {s => 6,7; 1,7}),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_ident() {
+        let s = Rc::new(Source::inline(
+            "let x' = _a1 in _ letx Mod_ rec and if then else true false not fun begin end",
+        ));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                Token::new(TokenKind::Let, loc! {s => 0,3; 1,1}),
+                Token::new(TokenKind::Ident("x'".to_string()), loc! {s => 4,6; 1,5}),
+                Token::new(TokenKind::Eq, loc! {s => 7,8; 1,8}),
+                Token::new(TokenKind::Ident("_a1".to_string()), loc! {s => 9,12; 1,10}),
+                Token::new(TokenKind::In, loc! {s => 13,15; 1,14}),
+                Token::new(TokenKind::Underscore, loc! {s => 16,17; 1,17}),
+                Token::new(
+                    TokenKind::Ident("letx".to_string()),
+                    loc! {s => 18,22; 1,19}
+                ),
+                Token::new(
+                    TokenKind::Ident("Mod_".to_string()),
+                    loc! {s => 23,27; 1,24}
+                ),
+                Token::new(TokenKind::Rec, loc! {s => 28,31; 1,29}),
+                Token::new(TokenKind::And, loc! {s => 32,35; 1,33}),
+                Token::new(TokenKind::If, loc! {s => 36,38; 1,37}),
+                Token::new(TokenKind::Then, loc! {s => 39,43; 1,40}),
+                Token::new(TokenKind::Else, loc! {s => 44,48; 1,45}),
+                Token::new(TokenKind::True, loc! {s => 49,53; 1,50}),
+                Token::new(TokenKind::False, loc! {s => 54,59; 1,55}),
+                Token::new(TokenKind::Ident("not".to_string()), loc! {s => 60,63; 1,61}),
+                Token::new(TokenKind::Fun, loc! {s => 64,67; 1,65}),
+                Token::new(TokenKind::Begin, loc! {s => 68,73; 1,69}),
+                Token::new(TokenKind::End, loc! {s => 74,77; 1,75}),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_number_radix() {
+        let s = Rc::new(Source::inline("0x1F 0XfF 0o17 0O7 0b101 0B1 0 00 0x 0b"));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::IntLiteral(31),
+                TokenKind::IntLiteral(255),
+                TokenKind::IntLiteral(15),
+                TokenKind::IntLiteral(7),
+                TokenKind::IntLiteral(5),
+                TokenKind::IntLiteral(1),
+                TokenKind::IntLiteral(0),
+                TokenKind::IntLiteral(0),
+                TokenKind::Error("Invalid integer literal".to_string()),
+                TokenKind::Error("Invalid integer literal".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_number_underscore() {
+        let s = Rc::new(Source::inline("1_000_000 0x_ 0xFF_FF 0b1_0 1__2_"));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::IntLiteral(1_000_000),
+                TokenKind::Error("Invalid integer literal".to_string()),
+                TokenKind::IntLiteral(0xFFFF),
+                TokenKind::IntLiteral(2),
+                TokenKind::IntLiteral(12),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_number_range() {
+        let s = Rc::new(Source::inline(
+            "9223372036854775807 9223372036854775808 9223372036854775809 \
+             0x7fffffffffffffff 0x8000000000000000 0x1_0000_0000_0000_0000 \
+             99999999999999999999999",
+        ));
+        let overflow = TokenKind::Error(INT_LITERAL_OVERFLOW.to_string());
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::IntLiteral(i64::MAX),
+                TokenKind::IntLiteral(i64::MIN),
+                overflow.clone(),
+                TokenKind::IntLiteral(i64::MAX),
+                TokenKind::IntLiteral(i64::MIN),
+                overflow.clone(),
+                overflow,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        let s = Rc::new(Source::inline("0b12 0o8 12ab 0xfg 1"));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                Token::new(
+                    TokenKind::Error("Invalid integer literal".to_string()),
+                    loc! {s => 0,4; 1,1}
+                ),
+                Token::new(
+                    TokenKind::Error("Invalid integer literal".to_string()),
+                    loc! {s => 5,8; 1,6}
+                ),
+                Token::new(
+                    TokenKind::Error("Invalid integer literal".to_string()),
+                    loc! {s => 9,13; 1,10}
+                ),
+                Token::new(
+                    TokenKind::Error("Invalid integer literal".to_string()),
+                    loc! {s => 14,18; 1,15}
+                ),
+                token!(1, loc! {s => 19,20; 1,20}),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_string() {
+        let s = Rc::new(Source::inline(
+            r#""abc" "" "a\"b\\c\n\t\b\r\ \'" "あ" "a^"^"b""#,
+        ));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                Token::new(
+                    TokenKind::StringLiteral(b"abc".to_vec()),
+                    loc! {s => 0,5; 1,1}
+                ),
+                Token::new(TokenKind::StringLiteral(vec![]), loc! {s => 6,8; 1,7}),
+                Token::new(
+                    TokenKind::StringLiteral(b"a\"b\\c\n\t\x08\r '".to_vec()),
+                    loc! {s => 9,30; 1,10}
+                ),
+                Token::new(
+                    TokenKind::StringLiteral("あ".as_bytes().to_vec()),
+                    loc! {s => 31,36; 1,32}
+                ),
+                Token::new(
+                    TokenKind::StringLiteral(b"a^".to_vec()),
+                    loc! {s => 37,41; 1,36}
+                ),
+                Token::new(TokenKind::Caret, loc! {s => 41,42; 1,40}),
+                Token::new(
+                    TokenKind::StringLiteral(b"b".to_vec()),
+                    loc! {s => 42,45; 1,41}
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_string_escape() {
+        let s = Rc::new(Source::inline(
+            r#""\065\x41\x4a\o101\o377\255" "\u{3042}\u{1F408}\u{0}" "a\
+               b\
+c""#,
+        ));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::StringLiteral(vec![b'A', b'A', b'J', b'A', 255, 255]),
+                TokenKind::StringLiteral("あ🐈\0".as_bytes().to_vec()),
+                TokenKind::StringLiteral(b"abc".to_vec()),
+            ],
+        );
+
+        // Every byte of a grapheme cluster is kept.
+        let s = Rc::new(Source::inline("\"a\u{310}\r\n\""));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [TokenKind::StringLiteral("a\u{310}\r\n".as_bytes().to_vec())],
+        );
+    }
+
+    #[test]
+    fn test_string_error() {
+        let s = Rc::new(Source::inline(
+            r#""\q" "\256\q" "\12" "\o400" "\xg" "\u{D800}" "\u{1234567}" "\u{12" 1 "abc"#,
+        ));
+        let illegal = |seq: &str| {
+            TokenKind::Error(format!(
+                "Illegal backslash escape in string or character (\\{})",
+                seq
+            ))
+        };
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                illegal("q"),
+                TokenKind::Error(
+                    "Illegal backslash escape in string or character (\\256): 256 > 255"
+                        .to_string()
+                ),
+                illegal("12"),
+                TokenKind::Error(
+                    "Illegal backslash escape in string or character (\\o400): o400 > o377"
+                        .to_string()
+                ),
+                illegal("x"),
+                TokenKind::Error(
+                    "Illegal backslash escape in string or character (\\u{D800}): \
+                     D800 is not a Unicode scalar value"
+                        .to_string()
+                ),
+                illegal("u{1234567}"),
+                illegal("u{12"),
+                TokenKind::IntLiteral(1),
+                TokenKind::Error("Unterminated string literal".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_char() {
+        let s = Rc::new(Source::inline(
+            r#"'a' '\n' '\065' '\x41' '\'' '\\' '"' ' ' 'a 'b' x'"#,
+        ));
+        assert_iter_eq!(
+            tokenize(&s),
+            [
+                Token::new(TokenKind::CharLiteral(b'a'), loc! {s => 0,3; 1,1}),
+                Token::new(TokenKind::CharLiteral(b'\n'), loc! {s => 4,8; 1,5}),
+                Token::new(TokenKind::CharLiteral(b'A'), loc! {s => 9,15; 1,10}),
+                Token::new(TokenKind::CharLiteral(b'A'), loc! {s => 16,22; 1,17}),
+                Token::new(TokenKind::CharLiteral(b'\''), loc! {s => 23,27; 1,24}),
+                Token::new(TokenKind::CharLiteral(b'\\'), loc! {s => 28,32; 1,29}),
+                Token::new(TokenKind::CharLiteral(b'"'), loc! {s => 33,36; 1,34}),
+                Token::new(TokenKind::CharLiteral(b' '), loc! {s => 37,40; 1,38}),
+                Token::new(TokenKind::Quote, loc! {s => 41,42; 1,42}),
+                Token::new(TokenKind::Ident("a".to_string()), loc! {s => 42,43; 1,43}),
+                Token::new(TokenKind::CharLiteral(b'b'), loc! {s => 44,47; 1,45}),
+                Token::new(TokenKind::Ident("x'".to_string()), loc! {s => 48,50; 1,49}),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_char_error() {
+        let s = Rc::new(Source::inline(r"'あ' '\q' '\256' '\x4' '\065 1"));
+        assert_iter_eq!(
+            tokenize(&s).map(|t| t.kind),
+            [
+                TokenKind::Error("Char literal must be a single byte".to_string()),
+                TokenKind::Error(
+                    "Illegal backslash escape in string or character (\\q)".to_string()
+                ),
+                TokenKind::Error(
+                    "Illegal backslash escape in string or character (\\256): 256 > 255"
+                        .to_string()
+                ),
+                TokenKind::Error(
+                    "Illegal backslash escape in string or character (\\x4)".to_string()
+                ),
+                TokenKind::Error("Unterminated char literal".to_string()),
+                TokenKind::IntLiteral(1),
+            ],
+        );
+    }
+
     #[test]
     fn test_number() {
         let s = Rc::new(Source::inline("1 16 256 2048"));
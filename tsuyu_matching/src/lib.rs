@@ -0,0 +1,902 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tsuyu_ast::{
+    BindingGroup, Case, ConstrDecl, Decision, Expr, ExprKind, File, Head, Occurrence, Pattern,
+    PatternKind, Stmt, Type, TypeDecl, TypeDeclKind,
+};
+use tsuyu_error::{Error, ErrorKind};
+use tsuyu_utils::{escape_bytes, escape_char};
+
+/// Compiles the cases of every `match` in a typed file to a decision tree stored in the
+/// expression. Returns warnings of matches which are not exhaustive and of unused cases.
+///
+/// The cases are tried from the top like a matrix of patterns. Each test of the tree
+/// specializes the matrix to the rows which can match the head found in the value, so that no
+/// part of the value is tested twice on a path.
+pub fn compile_matches(file: &mut File) -> Vec<Error> {
+    let mut compiler = Compiler::default();
+    for stmt in &mut file.stmts {
+        match stmt {
+            Stmt::Expr(expr) => compiler.expr(expr),
+            Stmt::Let(group) => compiler.group(group),
+            Stmt::Type(decls) => compiler.decls.extend(decls.iter().cloned()),
+        }
+    }
+    compiler.warnings
+}
+
+/// Pattern in a row of the matrix.
+#[derive(Clone, Copy)]
+enum Cell<'a> {
+    /// Matches any value without binding it.
+    Any,
+    Pattern(&'a Pattern),
+    /// `[p1; p2; ...]` of the elements. The rest of a list pattern after its head.
+    List(&'a [Pattern]),
+}
+
+#[derive(Clone)]
+struct Row<'a> {
+    cells: Vec<Cell<'a>>,
+    case: usize,
+    bindings: Vec<(String, Occurrence)>,
+    // Whether the case has a guard.
+    guarded: bool,
+}
+
+/// Part of the matched value tested by the cells of the column.
+#[derive(Clone)]
+struct Column {
+    occurrence: Occurrence,
+    typ: Type,
+}
+
+#[derive(Default)]
+struct Compiler {
+    // Declarations in the order of `Type::Named` indices.
+    decls: Vec<TypeDecl>,
+    warnings: Vec<Error>,
+}
+
+impl Compiler {
+    fn group(&mut self, group: &mut BindingGroup) {
+        for binding in &mut group.bindings {
+            self.expr(&mut binding.value);
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match &mut expr.kind {
+            ExprKind::UnitLiteral
+            | ExprKind::IntLiteral(_)
+            | ExprKind::BoolLiteral(_)
+            | ExprKind::StringLiteral(_)
+            | ExprKind::CharLiteral(_)
+            | ExprKind::Var(_)
+            | ExprKind::Constr(_, None) => {}
+            ExprKind::Let(group, body) => {
+                self.group(group);
+                self.expr(body);
+            }
+            ExprKind::Apply(func, args) => {
+                self.expr(func);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::Fun(_, body) => self.expr(body),
+            ExprKind::Tuple(exprs) | ExprKind::List(exprs) | ExprKind::Array(exprs) => {
+                for expr in exprs {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::If(cond, then, els) => {
+                self.expr(cond);
+                self.expr(then);
+                self.expr(els);
+            }
+            ExprKind::Record(fields) => {
+                for (_, expr) in fields {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::With(base, fields) => {
+                self.expr(base);
+                for (_, expr) in fields {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::UnOp(_, operand)
+            | ExprKind::Field(operand, _)
+            | ExprKind::Constr(_, Some(operand)) => self.expr(operand),
+            ExprKind::BinOp(_, lhs, rhs)
+            | ExprKind::Seq(lhs, rhs)
+            | ExprKind::SetField(lhs, _, rhs)
+            | ExprKind::Index(lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::SetIndex(array, index, value) => {
+                self.expr(array);
+                self.expr(index);
+                self.expr(value);
+            }
+            // Warnings are reported in the order of the source.
+            ExprKind::Match(scrutinee, cases, decision) => {
+                self.expr(scrutinee);
+                let typ = scrutinee.typ.as_ref().expect("untyped expression");
+                let tree = self.compile(cases, typ.clone());
+                if let Some(kind) = self.counterexample(&tree, typ) {
+                    self.warnings
+                        .push(Error::new(kind).with_loc(expr.loc.clone()));
+                }
+                for (i, case) in cases.iter().enumerate() {
+                    if !selects(&tree, i) {
+                        self.warnings.push(
+                            Error::new(ErrorKind::UnusedCase).with_loc(case.pattern.loc.clone()),
+                        );
+                    }
+                }
+                *decision = Some(tree);
+                for case in cases.iter_mut() {
+                    if let Some(guard) = &mut case.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&mut case.body);
+                }
+            }
+        }
+    }
+
+    fn compile(&self, cases: &[Case], typ: Type) -> Decision {
+        let columns = [Column {
+            occurrence: Vec::new(),
+            typ,
+        }];
+        let rows = cases
+            .iter()
+            .enumerate()
+            .map(|(case, c)| Row {
+                cells: vec![Cell::Pattern(&c.pattern)],
+                case,
+                bindings: Vec::new(),
+                guarded: c.guard.is_some(),
+            })
+            .collect();
+        self.decide(&columns, rows)
+    }
+
+    fn decide(&self, columns: &[Column], rows: Vec<Row<'_>>) -> Decision {
+        let rows: Vec<_> = rows
+            .into_iter()
+            .flat_map(|row| row.expand(columns))
+            .collect();
+        let Some(first) = rows.first() else {
+            return Decision::Fail;
+        };
+        let Some(col) = first.cells.iter().position(|c| !matches!(c, Cell::Any)) else {
+            if first.guarded {
+                // The rows below are tried if the guard does not hold.
+                let fallback = self.decide(columns, rows[1..].to_vec());
+                return Decision::Guard(first.case, first.bindings.clone(), Box::new(fallback));
+            }
+            return Decision::Leaf(first.case, first.bindings.clone());
+        };
+        let column = &columns[col];
+
+        // Tuples have a single shape. Their elements are tested as columns instead.
+        if let Type::Tuple(typs) = &column.typ {
+            let elems: Vec<_> = typs
+                .iter()
+                .enumerate()
+                .map(|(i, typ)| column.part(i, typ.clone()))
+                .collect();
+            let rows = rows
+                .iter()
+                .map(|row| {
+                    let cells = match row.cells[col] {
+                        Cell::Any => vec![Cell::Any; elems.len()],
+                        Cell::Pattern(Pattern {
+                            kind: PatternKind::Tuple(pats),
+                            ..
+                        }) => pats.iter().map(Cell::Pattern).collect(),
+                        _ => unreachable!("non-tuple pattern of a tuple"),
+                    };
+                    row.replace(col, cells)
+                })
+                .collect();
+            return self.decide(&replace(columns, col, elems), rows);
+        }
+
+        let heads = match &column.typ {
+            Type::Char => char_heads(rows.iter().map(|row| row.cells[col])),
+            _ => {
+                let mut heads = Vec::new();
+                for row in &rows {
+                    if let Some(head) = head(row.cells[col]) {
+                        if !heads.contains(&head) {
+                            heads.push(head);
+                        }
+                    }
+                }
+                heads
+            }
+        };
+        let branches = heads
+            .iter()
+            .map(|head| {
+                let args = self.args(column, head);
+                let rows = rows
+                    .iter()
+                    .filter_map(|row| row.specialize(col, head, args.len()))
+                    .collect();
+                (
+                    head.clone(),
+                    self.decide(&replace(columns, col, args), rows),
+                )
+            })
+            .collect();
+        let default = (!self.is_complete(&column.typ, &heads)).then(|| {
+            let rows = rows
+                .iter()
+                .filter(|row| matches!(row.cells[col], Cell::Any))
+                .map(|row| row.replace(col, Vec::new()))
+                .collect();
+            Box::new(self.decide(&replace(columns, col, Vec::new()), rows))
+        });
+        Decision::Switch(
+            column.occurrence.clone(),
+            column.typ.clone(),
+            branches,
+            default,
+        )
+    }
+
+    // Columns of the parts of a value with the head.
+    fn args(&self, column: &Column, head: &Head) -> Vec<Column> {
+        match head {
+            Head::Constr(name) => self
+                .constr_args(&column.typ, name)
+                .iter()
+                .enumerate()
+                // Arguments follow the tag.
+                .map(|(i, typ)| column.part(i + 1, typ.clone()))
+                .collect(),
+            Head::Cons => {
+                let Type::List(elem) = &column.typ else {
+                    unreachable!("cons of a non-list type");
+                };
+                vec![
+                    column.part(0, (**elem).clone()),
+                    column.part(1, column.typ.clone()),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn constrs(&self, typ: &Type) -> Option<&[ConstrDecl]> {
+        let Type::Named(_, id) = typ else {
+            return None;
+        };
+        match &self.decls[*id].kind {
+            TypeDeclKind::Variant(constrs) => Some(constrs),
+            TypeDeclKind::Record(_) => None,
+        }
+    }
+
+    fn constr_args(&self, typ: &Type, name: &str) -> &[Type] {
+        self.constrs(typ)
+            .and_then(|constrs| constrs.iter().find(|c| c.name == name))
+            .and_then(|constr| constr.typs.as_deref())
+            .expect("unknown constructor")
+    }
+
+    // Warning with a value which no case matches printed like a pattern. Parts which are not
+    // tested on the path to the failure are `_`.
+    fn counterexample(&self, decision: &Decision, typ: &Type) -> Option<ErrorKind> {
+        let mut heads = Vec::new();
+        let mut guarded = false;
+        if !self.failure(decision, &mut heads, &mut guarded) {
+            return None;
+        }
+        let example = self.example(&[], typ, &heads);
+        Some(if guarded {
+            ErrorKind::GuardedPartialMatch(example)
+        } else {
+            ErrorKind::PartialMatch(example)
+        })
+    }
+
+    // Finds a path to `Decision::Fail` and pushes the heads taken on it. `guarded` is set if
+    // the path passes a guard.
+    fn failure(
+        &self,
+        decision: &Decision,
+        heads: &mut Vec<(Occurrence, Head)>,
+        guarded: &mut bool,
+    ) -> bool {
+        let (occurrence, typ, branches, default) = match decision {
+            Decision::Fail => return true,
+            Decision::Leaf(..) => return false,
+            Decision::Guard(_, _, fallback) => {
+                let found = self.failure(fallback, heads, guarded);
+                *guarded |= found;
+                return found;
+            }
+            Decision::Switch(occurrence, typ, branches, default) => {
+                (occurrence, typ, branches, default)
+            }
+        };
+        let missing = default.as_ref().map(|default| {
+            let found: Vec<_> = branches.iter().map(|(head, _)| head.clone()).collect();
+            (self.missing_head(typ, &found), &**default)
+        });
+        for (head, branch) in branches.iter().map(|(h, b)| (h.clone(), b)).chain(missing) {
+            heads.push((occurrence.clone(), head));
+            if self.failure(branch, heads, guarded) {
+                return true;
+            }
+            heads.pop();
+        }
+        false
+    }
+
+    // Head of a value of the type which is not one of the found heads.
+    fn missing_head(&self, typ: &Type, found: &[Head]) -> Head {
+        let candidates: Box<dyn Iterator<Item = Head>> = match typ {
+            Type::Bool => Box::new([true, false].into_iter().map(Head::Bool)),
+            Type::List(_) => Box::new([Head::Nil, Head::Cons].into_iter()),
+            Type::Int => Box::new((0..).map(Head::Int)),
+            // Printable characters come first.
+            Type::Char => Box::new((b'a'..=u8::MAX).chain(0..b'a').map(Head::Char)),
+            Type::String => Box::new((0..).map(|n| Head::String(vec![b'*'; n]))),
+            _ => Box::new(
+                self.constrs(typ)
+                    .expect("switch on a non-variant type")
+                    .iter()
+                    .map(|constr| Head::Constr(constr.name.clone())),
+            ),
+        };
+        candidates
+            .into_iter()
+            .find(|head| !found.iter().any(|f| covers(f, head)))
+            .expect("no missing head")
+    }
+
+    // Part of the example at the occurrence.
+    fn example(&self, occurrence: &[usize], typ: &Type, heads: &[(Occurrence, Head)]) -> String {
+        let head_at = |occurrence: &[usize]| {
+            heads
+                .iter()
+                .find(|(o, _)| o == occurrence)
+                .map(|(_, head)| head)
+        };
+        let part = |i: usize, typ: &Type| self.example(&[occurrence, &[i]].concat(), typ, heads);
+        let parts = |typs: &[Type], first: usize| -> Vec<_> {
+            typs.iter()
+                .enumerate()
+                .map(|(i, typ)| part(first + i, typ))
+                .collect()
+        };
+        // Arguments of constructors and heads of lists are enclosed in parentheses unless they
+        // are atomic.
+        let atom = |i: usize, typ: &Type| {
+            let example = part(i, typ);
+            match head_at(&[occurrence, &[i]].concat()) {
+                Some(Head::Cons) => format!("({})", example),
+                Some(Head::Int(n)) if *n < 0 => format!("({})", example),
+                Some(Head::Constr(name)) if !self.constr_args(typ, name).is_empty() => {
+                    format!("({})", example)
+                }
+                _ => example,
+            }
+        };
+        let Some(head) = head_at(occurrence) else {
+            // Tuples are printed if their elements are tested.
+            return match typ {
+                Type::Tuple(typs) if heads.iter().any(|(o, _)| o.starts_with(occurrence)) => {
+                    format!("({})", parts(typs, 0).join(", "))
+                }
+                _ => "_".to_string(),
+            };
+        };
+        match head {
+            // Arguments follow the tag.
+            Head::Constr(name) => match self.constr_args(typ, name) {
+                [] => name.clone(),
+                [arg] => format!("{} {}", name, atom(1, arg)),
+                args => format!("{} ({})", name, parts(args, 1).join(", ")),
+            },
+            Head::Nil => "[]".to_string(),
+            Head::Cons => {
+                let Type::List(elem) = typ else {
+                    unreachable!("cons of a non-list type");
+                };
+                format!("{} :: {}", atom(0, elem), part(1, typ))
+            }
+            Head::Int(n) => n.to_string(),
+            Head::Bool(b) => b.to_string(),
+            Head::String(s) => format!("\"{}\"", escape_bytes(s)),
+            // The first char of a range stands for the others.
+            Head::Char(c) | Head::CharRange(c, _) => format!("'{}'", escape_char(*c)),
+        }
+    }
+
+    // Whether the heads cover all values of the type so that no default is needed.
+    fn is_complete(&self, typ: &Type, heads: &[Head]) -> bool {
+        match typ {
+            Type::Bool => heads.len() == 2,
+            Type::List(_) => heads.len() == 2,
+            Type::Char => {
+                let len: usize = heads
+                    .iter()
+                    .filter_map(char_range)
+                    .map(|(lo, hi)| usize::from(hi - lo) + 1)
+                    .sum();
+                len == 256
+            }
+            Type::Named(..) => self
+                .constrs(typ)
+                .is_some_and(|constrs| constrs.len() == heads.len()),
+            _ => false,
+        }
+    }
+}
+
+impl<'a> Row<'a> {
+    // Binds the row like `bind` and splits it into a row for each side of its or-patterns.
+    fn expand(mut self, columns: &[Column]) -> Vec<Self> {
+        self.bind(columns);
+        let or = self
+            .cells
+            .iter()
+            .enumerate()
+            .find_map(|(col, cell)| match cell {
+                Cell::Pattern(Pattern {
+                    kind: PatternKind::Or(left, right),
+                    ..
+                }) => Some((col, left, right)),
+                _ => None,
+            });
+        let Some((col, left, right)) = or else {
+            return vec![self];
+        };
+        [left, right]
+            .into_iter()
+            .flat_map(|side| self.replace(col, vec![Cell::Pattern(side)]).expand(columns))
+            .collect()
+    }
+
+    // Binds variables and aliases to the occurrences of their columns and replaces patterns
+    // which match any value with `Cell::Any`.
+    fn bind(&mut self, columns: &[Column]) {
+        for (cell, column) in self.cells.iter_mut().zip(columns) {
+            while let Cell::Pattern(pattern) = *cell {
+                match &pattern.kind {
+                    PatternKind::Var(name) => {
+                        self.bindings
+                            .push((name.clone(), column.occurrence.clone()));
+                        *cell = Cell::Any;
+                    }
+                    PatternKind::Alias(pat, name) => {
+                        self.bindings
+                            .push((name.clone(), column.occurrence.clone()));
+                        *cell = Cell::Pattern(pat);
+                    }
+                    PatternKind::Wildcard | PatternKind::Unit => *cell = Cell::Any,
+                    PatternKind::List(pats) => *cell = Cell::List(pats),
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    fn replace(&self, col: usize, cells: Vec<Cell<'a>>) -> Self {
+        Self {
+            cells: replace(&self.cells, col, cells),
+            case: self.case,
+            bindings: self.bindings.clone(),
+            guarded: self.guarded,
+        }
+    }
+
+    // Row matching the parts of a value with the head in place of the column, or `None` if
+    // the row cannot match such a value.
+    fn specialize(&self, col: usize, head: &Head, arity: usize) -> Option<Self> {
+        let cells = match self.cells[col] {
+            Cell::Any => vec![Cell::Any; arity],
+            cell if !self::head(cell).is_some_and(|h| covers(&h, head)) => return None,
+            Cell::List([]) => Vec::new(),
+            Cell::List([first, rest @ ..]) => vec![Cell::Pattern(first), Cell::List(rest)],
+            Cell::Pattern(pattern) => match &pattern.kind {
+                PatternKind::Cons(first, rest) => vec![Cell::Pattern(first), Cell::Pattern(rest)],
+                PatternKind::Constr(_, Some(arg)) => match &arg.kind {
+                    PatternKind::Wildcard => vec![Cell::Any; arity],
+                    PatternKind::Tuple(pats) if arity > 1 => {
+                        pats.iter().map(Cell::Pattern).collect()
+                    }
+                    _ => vec![Cell::Pattern(arg)],
+                },
+                _ => Vec::new(),
+            },
+        };
+        Some(self.replace(col, cells))
+    }
+}
+
+impl Column {
+    fn part(&self, index: usize, typ: Type) -> Self {
+        let mut occurrence = self.occurrence.clone();
+        occurrence.push(index);
+        Self { occurrence, typ }
+    }
+}
+
+fn head(cell: Cell<'_>) -> Option<Head> {
+    match cell {
+        Cell::Any => None,
+        Cell::List([]) => Some(Head::Nil),
+        Cell::List(_) => Some(Head::Cons),
+        Cell::Pattern(pattern) => Some(match &pattern.kind {
+            PatternKind::Constr(name, _) => Head::Constr(name.clone()),
+            PatternKind::Cons(..) => Head::Cons,
+            PatternKind::IntLiteral(n) => Head::Int(*n),
+            PatternKind::BoolLiteral(b) => Head::Bool(*b),
+            PatternKind::StringLiteral(s) => Head::String(s.clone()),
+            PatternKind::CharLiteral(c) => Head::Char(*c),
+            PatternKind::CharRange(lo, hi) => Head::CharRange(*lo, *hi),
+            PatternKind::Wildcard
+            | PatternKind::Var(_)
+            | PatternKind::Unit
+            | PatternKind::Tuple(_)
+            | PatternKind::List(_)
+            | PatternKind::Or(..)
+            | PatternKind::Alias(..) => unreachable!("pattern without a head"),
+        }),
+    }
+}
+
+// Splits the chars matched by the cells into ranges which each cell either contains or does
+// not overlap. The ranges are in ascending order.
+fn char_heads<'a>(cells: impl Iterator<Item = Cell<'a>>) -> Vec<Head> {
+    let ranges: Vec<_> = cells
+        .filter_map(head)
+        .filter_map(|head| char_range(&head))
+        .collect();
+    let mut bounds: Vec<u16> = ranges
+        .iter()
+        .flat_map(|&(lo, hi)| [u16::from(lo), u16::from(hi) + 1])
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+    bounds
+        .windows(2)
+        .filter_map(|bound| {
+            let (lo, hi) = (bound[0] as u8, (bound[1] - 1) as u8);
+            ranges
+                .iter()
+                .any(|&(l, h)| l <= lo && hi <= h)
+                .then_some(if lo == hi {
+                    Head::Char(lo)
+                } else {
+                    Head::CharRange(lo, hi)
+                })
+        })
+        .collect()
+}
+
+fn char_range(head: &Head) -> Option<(u8, u8)> {
+    match *head {
+        Head::Char(c) => Some((c, c)),
+        Head::CharRange(lo, hi) => Some((lo, hi)),
+        _ => None,
+    }
+}
+
+// Whether a cell with the head matches values with the other. A char range matches the chars
+// and ranges within it.
+fn covers(cell: &Head, head: &Head) -> bool {
+    match (char_range(cell), char_range(head)) {
+        (Some((lo, hi)), Some((l, h))) => lo <= l && h <= hi,
+        _ => cell == head,
+    }
+}
+
+// Whether a leaf of the tree selects the case.
+fn selects(decision: &Decision, case: usize) -> bool {
+    match decision {
+        Decision::Fail => false,
+        Decision::Leaf(i, _) => *i == case,
+        Decision::Guard(i, _, fallback) => *i == case || selects(fallback, case),
+        Decision::Switch(_, _, branches, default) => {
+            branches.iter().any(|(_, branch)| selects(branch, case))
+                || default
+                    .as_deref()
+                    .is_some_and(|default| selects(default, case))
+        }
+    }
+}
+
+// Copy of the items with the item at the index replaced by the others.
+fn replace<T: Clone>(items: &[T], index: usize, others: Vec<T>) -> Vec<T> {
+    let mut items = items.to_vec();
+    items.splice(index..=index, others);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::rc::Rc;
+    use tsuyu_asserts::assert_eq;
+    use tsuyu_lexer::tokenize;
+    use tsuyu_parser::parse;
+    use tsuyu_resolver::resolve;
+    use tsuyu_source::Source;
+    use tsuyu_typing::infer;
+
+    // Decision trees of top-level `match` expressions.
+    fn decisions(s: &str) -> Vec<Decision> {
+        let s = Rc::new(Source::inline(s));
+        let mut file = parse(tokenize(&s)).unwrap();
+        resolve(&file).unwrap();
+        infer(&mut file).unwrap();
+        compile_matches(&mut file);
+        file.stmts
+            .into_iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Expr(Expr {
+                    kind: ExprKind::Match(_, _, decision),
+                    ..
+                }) => decision,
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn warnings(s: &str) -> Vec<String> {
+        let s = Rc::new(Source::inline(s));
+        let mut file = parse(tokenize(&s)).unwrap();
+        resolve(&file).unwrap();
+        infer(&mut file).unwrap();
+        compile_matches(&mut file)
+            .iter()
+            .map(|warning| {
+                format!(
+                    "{:?} {}",
+                    warning.loc.as_ref().map(|l| (l.line, l.column)),
+                    warning.kind
+                )
+            })
+            .collect()
+    }
+
+    fn leaf(case: usize, bindings: &[(&str, &[usize])]) -> Decision {
+        let bindings = bindings
+            .iter()
+            .map(|(name, occurrence)| (name.to_string(), occurrence.to_vec()))
+            .collect();
+        Decision::Leaf(case, bindings)
+    }
+
+    fn switch(
+        occurrence: &[usize],
+        typ: Type,
+        branches: Vec<(Head, Decision)>,
+        default: Option<Decision>,
+    ) -> Decision {
+        Decision::Switch(occurrence.to_vec(), typ, branches, default.map(Box::new))
+    }
+
+    #[test]
+    fn test_tuple() {
+        assert_eq!(
+            decisions("match (1, true) with (0, _) -> 1 | (_, true) -> 2 | _ -> 3;; match () with () -> 0"),
+            [
+                switch(
+                    &[0],
+                    Type::Int,
+                    vec![(Head::Int(0), leaf(0, &[]))],
+                    Some(switch(
+                        &[1],
+                        Type::Bool,
+                        vec![(Head::Bool(true), leaf(1, &[]))],
+                        Some(leaf(2, &[])),
+                    )),
+                ),
+                leaf(0, &[]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_variant() {
+        let t = || Type::Named("t".to_string(), 0);
+        assert_eq!(
+            decisions(
+                "type t = A | B of int | C of int * t;; \
+                 match A with A -> 0 | B n -> n | C (n, A) -> n | C (_, u) -> 1;; \
+                 match A with B _ -> 0"
+            ),
+            [
+                switch(
+                    &[],
+                    t(),
+                    vec![
+                        (Head::Constr("A".to_string()), leaf(0, &[])),
+                        (Head::Constr("B".to_string()), leaf(1, &[("n", &[1])])),
+                        (
+                            Head::Constr("C".to_string()),
+                            switch(
+                                &[2],
+                                t(),
+                                vec![(Head::Constr("A".to_string()), leaf(2, &[("n", &[1])]))],
+                                Some(leaf(3, &[("u", &[2])])),
+                            ),
+                        ),
+                    ],
+                    None,
+                ),
+                switch(
+                    &[],
+                    t(),
+                    vec![(Head::Constr("B".to_string()), leaf(0, &[]))],
+                    Some(Decision::Fail),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let list = || Type::List(Box::new(Type::Int));
+        assert_eq!(
+            decisions(
+                "match [] with [] -> 0 | [x] -> x | x :: _ -> 1;; match [] with [true; _] -> 0"
+            ),
+            [
+                switch(
+                    &[],
+                    list(),
+                    vec![
+                        (Head::Nil, leaf(0, &[])),
+                        (
+                            Head::Cons,
+                            switch(
+                                &[1],
+                                list(),
+                                vec![(Head::Nil, leaf(1, &[("x", &[0])]))],
+                                Some(leaf(2, &[("x", &[0])])),
+                            ),
+                        ),
+                    ],
+                    None,
+                ),
+                switch(
+                    &[],
+                    Type::List(Box::new(Type::Bool)),
+                    vec![(
+                        Head::Cons,
+                        switch(
+                            &[0],
+                            Type::Bool,
+                            vec![(
+                                Head::Bool(true),
+                                switch(
+                                    &[1],
+                                    Type::List(Box::new(Type::Bool)),
+                                    vec![(
+                                        Head::Cons,
+                                        switch(
+                                            &[1, 1],
+                                            Type::List(Box::new(Type::Bool)),
+                                            vec![(Head::Nil, leaf(0, &[]))],
+                                            Some(Decision::Fail),
+                                        ),
+                                    )],
+                                    Some(Decision::Fail),
+                                ),
+                            )],
+                            Some(Decision::Fail),
+                        ),
+                    )],
+                    Some(Decision::Fail),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guard() {
+        assert_eq!(
+            decisions(
+                "match 'c' with 'a'..'z' | '_' -> 0 | 'm' -> 1 | c when c = 'A' -> 2 | _ as c -> 3;; \
+                 match (1, [2]) with (0, _) | (_, []) as p -> fst p | (n, x :: _) -> n + x"
+            ),
+            [
+                switch(
+                    &[],
+                    Type::Char,
+                    vec![
+                        (Head::Char(b'_'), leaf(0, &[])),
+                        (Head::CharRange(b'a', b'l'), leaf(0, &[])),
+                        (Head::Char(b'm'), leaf(0, &[])),
+                        (Head::CharRange(b'n', b'z'), leaf(0, &[])),
+                    ],
+                    Some(Decision::Guard(
+                        2,
+                        vec![("c".to_string(), Vec::new())],
+                        Box::new(leaf(3, &[("c", &[])])),
+                    )),
+                ),
+                switch(
+                    &[0],
+                    Type::Int,
+                    vec![(Head::Int(0), leaf(0, &[("p", &[])]))],
+                    Some(switch(
+                        &[1],
+                        Type::List(Box::new(Type::Int)),
+                        vec![
+                            (Head::Nil, leaf(0, &[("p", &[])])),
+                            (Head::Cons, leaf(1, &[("n", &[0]), ("x", &[1, 0])])),
+                        ],
+                        None,
+                    )),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let partial = |line: usize, column: usize, example: &str| {
+            format!(
+                "{:?} {}",
+                Some((line, column)),
+                ErrorKind::PartialMatch(example.to_string())
+            )
+        };
+        let guarded = |line: usize, column: usize, example: &str| {
+            format!(
+                "{:?} {}",
+                Some((line, column)),
+                ErrorKind::GuardedPartialMatch(example.to_string())
+            )
+        };
+        let unused = |line: usize, column: usize| {
+            format!("{:?} {}", Some((line, column)), ErrorKind::UnusedCase)
+        };
+        assert_eq!(
+            warnings(
+                "type t = A | B of int | C of t * bool;;
+match A with A -> 0 | B 1 -> 1 | C (_, true) -> 2;;
+match A with B _ -> 0 | C (C (_, _), _) -> 1 | A -> 2 | C (B (-1), false) -> 3;;
+match (1, 'a', \"\") with (0, _, \"\") -> 0;;
+match [] with [] -> 0 | [_; true] -> 1 | _ :: _ :: _ -> 2 | [_] -> 3 | [_; _] -> 4;;
+match 'a' with 'a' -> 0 | 'b' -> 1;;
+match \"\" with \"\" -> 0;;
+match [] with _ :: [] -> 0 | [] -> 1;;
+match 'c' with 'a'..'z' -> 0 | 'm' | '_' -> 1;;
+match 1 with n when n > 0 -> 0 | 0 -> 1;;
+match (true, 1) with (true, _) | (false, 0) -> 0 | (false, _) as p -> 1 | _ -> 2"
+            ),
+            [
+                partial(2, 1, "B 0"),
+                partial(3, 1, "C (B (-1), true)"),
+                partial(4, 1, "(0, _, \"*\")"),
+                unused(5, 72),
+                partial(6, 1, "'c'"),
+                partial(7, 1, "\"*\""),
+                partial(8, 1, "_ :: _ :: _"),
+                partial(9, 1, "'{'"),
+                guarded(10, 1, "1"),
+                unused(11, 75),
+            ]
+        );
+    }
+}
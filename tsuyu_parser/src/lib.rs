@@ -4,10 +4,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use tsuyu_ast::{expr, Expr, File, Stmt};
+use tsuyu_ast::{
+    BinOp, Binding, BindingGroup, Case, ConstrDecl, Expr, ExprKind, FieldDecl, File, Label,
+    Pattern, PatternKind, Stmt, TypeDecl, TypeDeclKind, TypeExpr, TypeExprKind, UnOp,
+};
 use tsuyu_error::{error, ComposedResult, Error, Result};
 use tsuyu_source::Loc;
-use tsuyu_token::{Token, TokenKind, TokenReader};
+use tsuyu_token::{token_kind, Token, TokenKind, TokenKindKey, TokenReader, INT_LITERAL_OVERFLOW};
 
 pub struct Parser<'a> {
     reader: TokenReader<'a>,
@@ -30,17 +33,18 @@ macro_rules! expect_token_kind {
     (
         $tok:expr => {
             $(
-                $kind:ident $( ( $( $field:ident ),* ) )? => $body:expr
+                $kind:ident $( ( $( $field:ident ),* ) )? $( if $guard:expr )? => $body:expr
             ),+ $(,)?
         }
     ) => {
         let __tok = $tok;
         match __tok.kind {
             $(
-                TokenKind::$kind $( ( $( $field ),* ) )?
+                TokenKind::$kind $( ( $( $field ),* ) )? $( if $guard )?
                     => Ok($body),
             )+
-            _ => Err(error!("unexpected token", __tok.loc)),
+            TokenKind::Error(msg) => Err(error!(msg, __tok.loc)),
+            _ => Err(error!("Unexpected token", __tok.loc)),
         }
     }
 }
@@ -57,16 +61,111 @@ impl<'a> Parser<'a> {
     fn read(&mut self) -> Result<Token> {
         self.reader
             .read()
-            .map(|tok| {
+            .inspect(|tok| {
                 self.loc = tok.loc.clone();
-                tok
             })
-            .ok_or_else(|| self.error("unexpected EOF"))
+            .ok_or_else(|| self.error("Unexpected EOF"))
     }
 
     fn eof(&mut self) -> bool {
         self.peek().is_none()
     }
+
+    fn peek_is(&mut self, kind: TokenKindKey) -> bool {
+        self.peek().is_some_and(|t| t.is(kind))
+    }
+
+    fn expect(&mut self, kind: TokenKindKey) -> Result<Token> {
+        let tok = self.read()?;
+        if tok.is(kind) {
+            Ok(tok)
+        } else if let TokenKind::Error(msg) = tok.kind {
+            Err(error!(msg, tok.loc))
+        } else {
+            Err(error!("Unexpected token", tok.loc))
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    Right,
+}
+
+// Higher binds tighter. Same as OCaml's.
+fn binop_info(kind: &TokenKind) -> Option<(BinOp, u8, Assoc)> {
+    Some(match kind {
+        TokenKind::BarBar => (BinOp::Or, 1, Assoc::Right),
+        TokenKind::AndAnd => (BinOp::And, 2, Assoc::Right),
+        TokenKind::Eq => (BinOp::Eq, 3, Assoc::Left),
+        TokenKind::Ne => (BinOp::Ne, 3, Assoc::Left),
+        TokenKind::Lt => (BinOp::Lt, 3, Assoc::Left),
+        TokenKind::Le => (BinOp::Le, 3, Assoc::Left),
+        TokenKind::Gt => (BinOp::Gt, 3, Assoc::Left),
+        TokenKind::Ge => (BinOp::Ge, 3, Assoc::Left),
+        TokenKind::Caret => (BinOp::Concat, 4, Assoc::Right),
+        TokenKind::At => (BinOp::Append, 4, Assoc::Right),
+        TokenKind::ColonColon => (BinOp::Cons, 5, Assoc::Right),
+        TokenKind::Plus => (BinOp::Add, 6, Assoc::Left),
+        TokenKind::Minus => (BinOp::Sub, 6, Assoc::Left),
+        TokenKind::Star => (BinOp::Mul, 7, Assoc::Left),
+        TokenKind::Slash => (BinOp::Div, 7, Assoc::Left),
+        TokenKind::Mod => (BinOp::Mod, 7, Assoc::Left),
+        _ => return None,
+    })
+}
+
+fn is_module_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+// Tokens which can start an argument of function application.
+fn starts_argument(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::IntLiteral(_)
+            | TokenKind::StringLiteral(_)
+            | TokenKind::CharLiteral(_)
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Ident(_)
+            | TokenKind::LParen
+            | TokenKind::LBracket
+            | TokenKind::LBracketBar
+            | TokenKind::LBrace
+            | TokenKind::Begin
+            | TokenKind::Bang
+    )
+}
+
+// Tokens which can start a pattern without `,`, `::` or constructor application.
+fn starts_simple_pattern(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Ident(_)
+            | TokenKind::Underscore
+            | TokenKind::LParen
+            | TokenKind::LBracket
+            | TokenKind::IntLiteral(_)
+            | TokenKind::StringLiteral(_)
+            | TokenKind::CharLiteral(_)
+            | TokenKind::True
+            | TokenKind::False
+    )
+}
+
+// Patterns of `let` and `fun` are irrefutable. Other patterns are only allowed in `match`.
+fn check_irrefutable(pattern: &Pattern) -> Result<()> {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Var(_) | PatternKind::Unit => Ok(()),
+        PatternKind::Tuple(pats) => pats.iter().try_for_each(check_irrefutable),
+        PatternKind::Alias(pat, _) => check_irrefutable(pat),
+        _ => Err(error!(
+            "This pattern is only supported in match",
+            pattern.loc.clone()
+        )),
+    }
 }
 
 impl<'a> Parser<'a> {
@@ -74,6 +173,10 @@ impl<'a> Parser<'a> {
         let mut f = File::new(self.loc.source());
         let mut errors = Vec::new();
         while !self.eof() {
+            if self.peek_is(token_kind!(semi_semi)) {
+                self.reader.read();
+                continue;
+            }
             match self.parse_stmt() {
                 Ok(v) => {
                     f.stmts.push(v);
@@ -83,24 +186,761 @@ impl<'a> Parser<'a> {
                 }
             }
         }
-        errors.is_empty().then(|| f).ok_or(errors)
+        errors.is_empty().then_some(f).ok_or(errors)
     }
 
     fn parse_stmt(&mut self) -> Result<Stmt> {
-        Ok(Stmt::Expr(self.parse_expr()?))
+        if self.peek_is(token_kind!(r#type)) {
+            self.read()?;
+            return self.parse_type_decls();
+        }
+        if !self.peek_is(token_kind!(r#let)) {
+            return Ok(Stmt::Expr(self.parse_seq_expr()?));
+        }
+        let let_loc = self.read()?.loc;
+        let group = self.parse_binding_group()?;
+        if self.peek_is(token_kind!(r#in)) {
+            let expr = self.parse_let_body(let_loc, group)?;
+            return Ok(Stmt::Expr(expr));
+        }
+        Ok(Stmt::Let(group))
     }
 
+    // `e1; e2` binds looser than any other expression. So it is only allowed where the end of the
+    // expression is clear, like in parentheses or in the body of `let`.
+    fn parse_seq_expr(&mut self) -> Result<Expr> {
+        let first = self.parse_expr()?;
+        if !self.peek_is(token_kind!(semi)) {
+            return Ok(first);
+        }
+        self.read()?;
+        let second = self.parse_seq_expr()?;
+        let loc = first.loc.merge(&second.loc);
+        Ok(Expr::new(
+            ExprKind::Seq(Box::new(first), Box::new(second)),
+            loc,
+        ))
+    }
+
+    // `e1 := e2` and `e1.l <- e2` are right associative and bind looser than `,`.
     fn parse_expr(&mut self) -> Result<Expr> {
-        self.parse_literal()
+        let lhs = self.parse_tuple()?;
+        if self.peek_is(token_kind!(colon_eq)) {
+            self.read()?;
+            let rhs = self.parse_expr()?;
+            let loc = lhs.loc.merge(&rhs.loc);
+            return Ok(Expr::new(
+                ExprKind::BinOp(BinOp::Assign, Box::new(lhs), Box::new(rhs)),
+                loc,
+            ));
+        }
+        if !self.peek_is(token_kind!(l_arrow)) {
+            return Ok(lhs);
+        }
+        let arrow = self.read()?.loc;
+        let value = self.parse_expr()?;
+        let loc = lhs.loc.merge(&value.loc);
+        let kind = match lhs.kind {
+            ExprKind::Field(record, label) => ExprKind::SetField(record, label, Box::new(value)),
+            ExprKind::Index(array, index) => ExprKind::SetIndex(array, index, Box::new(value)),
+            _ => return Err(error!("Unexpected token", arrow)),
+        };
+        Ok(Expr::new(kind, loc))
+    }
+
+    // `e1, e2, ...` binds looser than binary operators.
+    fn parse_tuple(&mut self) -> Result<Expr> {
+        let first = self.parse_binop(0)?;
+        if !self.peek_is(token_kind!(comma)) {
+            return Ok(first);
+        }
+        let mut exprs = vec![first];
+        while self.peek_is(token_kind!(comma)) {
+            self.read()?;
+            exprs.push(self.parse_binop(0)?);
+        }
+        let loc = exprs[0].loc.merge(&exprs[exprs.len() - 1].loc);
+        Ok(Expr::new(ExprKind::Tuple(exprs), loc))
+    }
+
+    fn parse_binop(&mut self, min_prec: u8) -> Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        while let Some((op, prec, assoc)) = self.peek().and_then(|t| binop_info(&t.kind)) {
+            if prec < min_prec {
+                break;
+            }
+            self.read()?;
+            let rhs = self.parse_binop(match assoc {
+                Assoc::Left => prec + 1,
+                Assoc::Right => prec,
+            })?;
+            let loc = lhs.loc.merge(&rhs.loc);
+            lhs = Expr::new(ExprKind::BinOp(op, Box::new(lhs), Box::new(rhs)), loc);
+        }
+        Ok(lhs)
+    }
+
+    // Unary minus binds tighter than any binary operator.
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if !self.peek_is(token_kind!(minus)) {
+            return self.parse_apply();
+        }
+        let minus = self.read()?.loc;
+        if let Some(&TokenKind::IntLiteral(n)) = self.peek().map(|t| &t.kind) {
+            // Folded so that `-9223372036854775808` is min_int.
+            let tok = self.read()?;
+            return Ok(Expr::new(
+                ExprKind::IntLiteral(n.wrapping_neg()),
+                minus.merge(&tok.loc),
+            ));
+        }
+        let expr = self.parse_unary()?;
+        let loc = minus.merge(&expr.loc);
+        Ok(Expr::new(ExprKind::UnOp(UnOp::Neg, Box::new(expr)), loc))
+    }
+
+    fn parse_apply(&mut self) -> Result<Expr> {
+        let mut func = self.parse_primary()?;
+        // A constructor takes one argument. The rest makes an application.
+        if let ExprKind::Constr(name, None) = &func.kind {
+            if self.peek().is_some_and(|t| starts_argument(&t.kind)) {
+                let arg = self.parse_primary()?;
+                let loc = func.loc.merge(&arg.loc);
+                func = Expr::new(ExprKind::Constr(name.clone(), Some(Box::new(arg))), loc);
+            }
+        }
+        let mut args = Vec::new();
+        while self.peek().is_some_and(|t| starts_argument(&t.kind)) {
+            args.push(self.parse_primary()?);
+        }
+        match args.last() {
+            Some(last) => {
+                let loc = func.loc.merge(&last.loc);
+                Ok(Expr::new(ExprKind::Apply(Box::new(func), args), loc))
+            }
+            None => Ok(func),
+        }
+    }
+
+    // Field access `e.l` binds tighter than function application.
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let mut expr = self.parse_atom()?;
+        while self.peek_is(token_kind!(dot)) {
+            self.read()?;
+            if self.peek_is(token_kind!(l_paren)) {
+                self.read()?;
+                let index = self.parse_seq_expr()?;
+                let rparen = self.expect(token_kind!(r_paren))?;
+                let loc = expr.loc.merge(&rparen.loc);
+                expr = Expr::new(ExprKind::Index(Box::new(expr), Box::new(index)), loc);
+                continue;
+            }
+            let label = self.parse_label()?;
+            let loc = expr.loc.merge(&label.loc);
+            expr = Expr::new(ExprKind::Field(Box::new(expr), label), loc);
+        }
+        Ok(expr)
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr> {
+        let tok = self.read()?;
+        let loc = tok.loc.clone();
+        expect_token_kind! {
+            tok => {
+                // 2^63 is only representable when negated.
+                IntLiteral(n) => if n == i64::MIN {
+                    return Err(error!(INT_LITERAL_OVERFLOW, loc));
+                } else {
+                    Expr::new(ExprKind::IntLiteral(n), loc)
+                },
+                StringLiteral(s) => Expr::new(ExprKind::StringLiteral(s), loc),
+                CharLiteral(c) => Expr::new(ExprKind::CharLiteral(c), loc),
+                True => Expr::new(ExprKind::BoolLiteral(true), loc),
+                False => Expr::new(ExprKind::BoolLiteral(false), loc),
+                Ident(name) if is_module_name(&name) && self.peek_is(token_kind!(dot)) => {
+                    self.parse_value_path(name, loc)?
+                },
+                Ident(name) if is_module_name(&name) => Expr::new(ExprKind::Constr(name, None), loc),
+                Ident(name) => Expr::new(ExprKind::Var(name), loc),
+                Let => self.parse_let(loc)?,
+                If => self.parse_if(loc)?,
+                Fun => self.parse_fun(loc)?,
+                Match => self.parse_match(loc)?,
+                LParen => self.parse_paren(loc)?,
+                LBracket => self.parse_list(loc)?,
+                LBracketBar => self.parse_array(loc)?,
+                LBrace => self.parse_record(loc)?,
+                // Prefix `!` binds tighter than anything else.
+                Bang => {
+                    let operand = self.parse_atom()?;
+                    let loc = loc.merge(&operand.loc);
+                    Expr::new(ExprKind::UnOp(UnOp::Deref, Box::new(operand)), loc)
+                },
+                Begin => self.parse_begin(loc)?,
+            }
+        }
+    }
+
+    fn parse_let(&mut self, let_loc: Loc) -> Result<Expr> {
+        let group = self.parse_binding_group()?;
+        self.parse_let_body(let_loc, group)
+    }
+
+    fn parse_let_body(&mut self, let_loc: Loc, group: BindingGroup) -> Result<Expr> {
+        self.expect(token_kind!(r#in))?;
+        let body = self.parse_seq_expr()?;
+        let loc = let_loc.merge(&body.loc);
+        Ok(Expr::new(ExprKind::Let(group, Box::new(body)), loc))
+    }
+
+    // Branches extend as far as possible like the body of `let` except over `;`.
+    fn parse_if(&mut self, if_loc: Loc) -> Result<Expr> {
+        let cond = self.parse_seq_expr()?;
+        self.expect(token_kind!(then))?;
+        let then = self.parse_expr()?;
+        self.expect(token_kind!(r#else))?;
+        let els = self.parse_expr()?;
+        let loc = if_loc.merge(&els.loc);
+        Ok(Expr::new(
+            ExprKind::If(Box::new(cond), Box::new(then), Box::new(els)),
+            loc,
+        ))
     }
 
-    fn parse_literal(&mut self) -> Result<Expr> {
+    fn parse_fun(&mut self, fun_loc: Loc) -> Result<Expr> {
+        let mut params = vec![self.parse_simple_pattern()?];
+        while !self.peek_is(token_kind!(arrow)) {
+            params.push(self.parse_simple_pattern()?);
+        }
+        self.read()?;
+        let body = self.parse_seq_expr()?;
+        // Checked after the whole expression is read so that parsing continues after it.
+        params.iter().try_for_each(check_irrefutable)?;
+        let loc = fun_loc.merge(&body.loc);
+        Ok(Expr::new(ExprKind::Fun(params, Box::new(body)), loc))
+    }
+
+    // `|` may precede the first case. The body of each case extends as far as possible like the
+    // body of `fun`, so a nested `match` takes the following cases.
+    fn parse_match(&mut self, match_loc: Loc) -> Result<Expr> {
+        let expr = self.parse_seq_expr()?;
+        self.expect(token_kind!(with))?;
+        if self.peek_is(token_kind!(bar)) {
+            self.read()?;
+        }
+        let mut cases = vec![self.parse_case()?];
+        while self.peek_is(token_kind!(bar)) {
+            self.read()?;
+            cases.push(self.parse_case()?);
+        }
+        let loc = match_loc.merge(&cases[cases.len() - 1].body.loc);
+        Ok(Expr::new(ExprKind::Match(Box::new(expr), cases, None), loc))
+    }
+
+    fn parse_case(&mut self) -> Result<Case> {
+        let pattern = self.parse_pattern()?;
+        let guard = if self.peek_is(token_kind!(when)) {
+            self.read()?;
+            Some(self.parse_seq_expr()?)
+        } else {
+            None
+        };
+        self.expect(token_kind!(arrow))?;
+        let body = self.parse_seq_expr()?;
+        Ok(Case {
+            pattern,
+            guard,
+            body,
+        })
+    }
+
+    // Parses after `let`.
+    fn parse_binding_group(&mut self) -> Result<BindingGroup> {
+        let rec = self.peek_is(token_kind!(rec));
+        if rec {
+            self.read()?;
+        }
+        let mut bindings = vec![self.parse_binding()?];
+        while self.peek_is(token_kind!(and)) {
+            self.read()?;
+            bindings.push(self.parse_binding()?);
+        }
+        Ok(BindingGroup { rec, bindings })
+    }
+
+    // Only a variable can be followed by parameters.
+    fn parse_binding(&mut self) -> Result<Binding> {
+        let first = self.parse_simple_pattern()?;
+        let mut params = Vec::new();
+        let pattern = match first.kind {
+            PatternKind::Var(_)
+                if !self.peek_is(token_kind!(comma)) && !self.peek_is(token_kind!(as)) =>
+            {
+                while self.peek().is_some_and(|t| starts_simple_pattern(&t.kind)) {
+                    params.push(self.parse_simple_pattern()?);
+                }
+                first
+            }
+            _ => {
+                let pattern = self.parse_tuple_pattern(first)?;
+                self.parse_alias_pattern(pattern)?
+            }
+        };
+        self.expect(token_kind!(eq))?;
+        let value = self.parse_seq_expr()?;
+        check_irrefutable(&pattern)?;
+        params.iter().try_for_each(check_irrefutable)?;
+        Ok(Binding {
+            pattern,
+            params,
+            value,
+            typ: None,
+        })
+    }
+
+    // `Module.name`. Only modules of builtins exist for now.
+    fn parse_value_path(&mut self, module: String, module_loc: Loc) -> Result<Expr> {
+        self.expect(token_kind!(dot))?;
+        let tok = self.read()?;
+        let loc = module_loc.merge(&tok.loc);
         expect_token_kind! {
-            self.read()? => {
-                IntLiteral(n) => expr!{ int(n) },
+            tok => {
+                Ident(name) if !is_module_name(&name) => {
+                    Expr::new(ExprKind::Var(format!("{}.{}", module, name)), loc)
+                },
             }
         }
     }
+
+    fn parse_pattern(&mut self) -> Result<Pattern> {
+        let pattern = self.parse_or_pattern()?;
+        self.parse_alias_pattern(pattern)
+    }
+
+    // `p as x` binds the whole pattern on its left including `|` and `,`.
+    fn parse_alias_pattern(&mut self, mut pattern: Pattern) -> Result<Pattern> {
+        while self.peek_is(token_kind!(as)) {
+            self.read()?;
+            let tok = self.read()?;
+            let loc = pattern.loc.merge(&tok.loc);
+            let name = match tok.kind {
+                TokenKind::Ident(name) if !is_module_name(&name) => name,
+                _ => return Err(error!("Expected a variable name", tok.loc)),
+            };
+            pattern = Pattern::new(PatternKind::Alias(Box::new(pattern), name), loc);
+        }
+        Ok(pattern)
+    }
+
+    // `p1 | p2` is left associative and binds looser than `,`.
+    fn parse_or_pattern(&mut self) -> Result<Pattern> {
+        let first = self.parse_cons_pattern()?;
+        let mut pattern = self.parse_tuple_pattern(first)?;
+        while self.peek_is(token_kind!(bar)) {
+            self.read()?;
+            let first = self.parse_cons_pattern()?;
+            let right = self.parse_tuple_pattern(first)?;
+            let loc = pattern.loc.merge(&right.loc);
+            pattern = Pattern::new(PatternKind::Or(Box::new(pattern), Box::new(right)), loc);
+        }
+        Ok(pattern)
+    }
+
+    // Parses the rest of `p1, p2, ...` after the first pattern.
+    fn parse_tuple_pattern(&mut self, first: Pattern) -> Result<Pattern> {
+        if !self.peek_is(token_kind!(comma)) {
+            return Ok(first);
+        }
+        let mut pats = vec![first];
+        while self.peek_is(token_kind!(comma)) {
+            self.read()?;
+            pats.push(self.parse_cons_pattern()?);
+        }
+        let loc = pats[0].loc.merge(&pats[pats.len() - 1].loc);
+        Ok(Pattern::new(PatternKind::Tuple(pats), loc))
+    }
+
+    // `p1 :: p2` is right associative and binds tighter than `,`.
+    fn parse_cons_pattern(&mut self) -> Result<Pattern> {
+        let head = self.parse_constr_pattern()?;
+        if !self.peek_is(token_kind!(colon_colon)) {
+            return Ok(head);
+        }
+        self.read()?;
+        let tail = self.parse_cons_pattern()?;
+        let loc = head.loc.merge(&tail.loc);
+        Ok(Pattern::new(
+            PatternKind::Cons(Box::new(head), Box::new(tail)),
+            loc,
+        ))
+    }
+
+    // A constructor takes one simple pattern as the argument.
+    fn parse_constr_pattern(&mut self) -> Result<Pattern> {
+        let pattern = self.parse_simple_pattern()?;
+        match pattern.kind {
+            PatternKind::Constr(name, None)
+                if self.peek().is_some_and(|t| starts_simple_pattern(&t.kind)) =>
+            {
+                let arg = self.parse_simple_pattern()?;
+                let loc = pattern.loc.merge(&arg.loc);
+                Ok(Pattern::new(
+                    PatternKind::Constr(name, Some(Box::new(arg))),
+                    loc,
+                ))
+            }
+            _ => Ok(pattern),
+        }
+    }
+
+    fn parse_simple_pattern(&mut self) -> Result<Pattern> {
+        let tok = self.read()?;
+        let loc = tok.loc.clone();
+        expect_token_kind! {
+            tok => {
+                Ident(name) if is_module_name(&name) => {
+                    Pattern::new(PatternKind::Constr(name, None), loc)
+                },
+                Ident(name) => Pattern::new(PatternKind::Var(name), loc),
+                Underscore => Pattern::new(PatternKind::Wildcard, loc),
+                // 2^63 is only representable when negated.
+                IntLiteral(n) => if n == i64::MIN {
+                    return Err(error!(INT_LITERAL_OVERFLOW, loc));
+                } else {
+                    Pattern::new(PatternKind::IntLiteral(n), loc)
+                },
+                Minus => {
+                    let tok = self.expect(token_kind!(int_literal))?;
+                    let loc = loc.merge(&tok.loc);
+                    let TokenKind::IntLiteral(n) = tok.kind else {
+                        unreachable!();
+                    };
+                    Pattern::new(PatternKind::IntLiteral(n.wrapping_neg()), loc)
+                },
+                StringLiteral(s) => Pattern::new(PatternKind::StringLiteral(s), loc),
+                // The bounds of a range may be in either order.
+                CharLiteral(c) => if self.peek_is(token_kind!(dot_dot)) {
+                    self.read()?;
+                    let tok = self.expect(token_kind!(char_literal))?;
+                    let loc = loc.merge(&tok.loc);
+                    let TokenKind::CharLiteral(d) = tok.kind else {
+                        unreachable!();
+                    };
+                    Pattern::new(PatternKind::CharRange(c.min(d), c.max(d)), loc)
+                } else {
+                    Pattern::new(PatternKind::CharLiteral(c), loc)
+                },
+                True => Pattern::new(PatternKind::BoolLiteral(true), loc),
+                False => Pattern::new(PatternKind::BoolLiteral(false), loc),
+                LParen => self.parse_paren_pattern(loc)?,
+                LBracket => self.parse_list_pattern(loc)?,
+            }
+        }
+    }
+
+    // Elements are separated by `;` which may also follow the last one like `parse_list`.
+    fn parse_list_pattern(&mut self, lbracket: Loc) -> Result<Pattern> {
+        let mut pats = Vec::new();
+        while !self.peek_is(token_kind!(r_bracket)) {
+            pats.push(self.parse_pattern()?);
+            if !self.peek_is(token_kind!(semi)) {
+                break;
+            }
+            self.read()?;
+        }
+        let rbracket = self.expect(token_kind!(r_bracket))?;
+        Ok(Pattern::new(
+            PatternKind::List(pats),
+            lbracket.merge(&rbracket.loc),
+        ))
+    }
+
+    fn parse_paren_pattern(&mut self, lparen: Loc) -> Result<Pattern> {
+        if self.peek_is(token_kind!(r_paren)) {
+            let rparen = self.read()?;
+            return Ok(Pattern::new(PatternKind::Unit, lparen.merge(&rparen.loc)));
+        }
+        let mut pattern = self.parse_pattern()?;
+        let rparen = self.expect(token_kind!(r_paren))?;
+        pattern.loc = lparen.merge(&rparen.loc);
+        Ok(pattern)
+    }
+
+    fn parse_paren(&mut self, lparen: Loc) -> Result<Expr> {
+        self.parse_group(lparen, token_kind!(r_paren))
+    }
+
+    // Elements are separated by `;` which may also follow the last one.
+    fn parse_list(&mut self, lbracket: Loc) -> Result<Expr> {
+        let mut exprs = Vec::new();
+        while !self.peek_is(token_kind!(r_bracket)) {
+            exprs.push(self.parse_expr()?);
+            if !self.peek_is(token_kind!(semi)) {
+                break;
+            }
+            self.read()?;
+        }
+        let rbracket = self.expect(token_kind!(r_bracket))?;
+        Ok(Expr::new(
+            ExprKind::List(exprs),
+            lbracket.merge(&rbracket.loc),
+        ))
+    }
+
+    fn parse_array(&mut self, lbracket: Loc) -> Result<Expr> {
+        let mut exprs = Vec::new();
+        while !self.peek_is(token_kind!(bar_r_bracket)) {
+            exprs.push(self.parse_expr()?);
+            if !self.peek_is(token_kind!(semi)) {
+                break;
+            }
+            self.read()?;
+        }
+        let rbracket = self.expect(token_kind!(bar_r_bracket))?;
+        Ok(Expr::new(
+            ExprKind::Array(exprs),
+            lbracket.merge(&rbracket.loc),
+        ))
+    }
+
+    // `{ l1 = e1; ... }` or `{ e with l1 = e1; ... }`. `;` may follow the last field.
+    fn parse_record(&mut self, lbrace: Loc) -> Result<Expr> {
+        let first = self.parse_primary()?;
+        let kind = if self.peek_is(token_kind!(with)) {
+            self.read()?;
+            ExprKind::With(Box::new(first), self.parse_fields(None)?)
+        } else {
+            let label = match first.kind {
+                ExprKind::Var(name) if !name.contains('.') => Label::new(name, first.loc),
+                _ => return Err(error!("Unexpected token", first.loc)),
+            };
+            ExprKind::Record(self.parse_fields(Some(label))?)
+        };
+        let rbrace = self.expect(token_kind!(r_brace))?;
+        Ok(Expr::new(kind, lbrace.merge(&rbrace.loc)))
+    }
+
+    // Parses `l1 = e1; l2 = e2; ...` until `}`. The first label may be already read.
+    fn parse_fields(&mut self, first: Option<Label>) -> Result<Vec<(Label, Expr)>> {
+        let mut fields = Vec::new();
+        let mut label = first;
+        loop {
+            let l = match label.take() {
+                Some(l) => l,
+                None => self.parse_label()?,
+            };
+            self.expect(token_kind!(eq))?;
+            fields.push((l, self.parse_expr()?));
+            if !self.peek_is(token_kind!(semi)) {
+                break;
+            }
+            self.read()?;
+            if self.peek_is(token_kind!(r_brace)) {
+                break;
+            }
+        }
+        Ok(fields)
+    }
+
+    fn parse_label(&mut self) -> Result<Label> {
+        let tok = self.read()?;
+        let loc = tok.loc.clone();
+        expect_token_kind! {
+            tok => {
+                Ident(name) if !is_module_name(&name) => Label::new(name, loc),
+            }
+        }
+    }
+
+    // Parses after `type`.
+    fn parse_type_decls(&mut self) -> Result<Stmt> {
+        let mut decls = vec![self.parse_type_decl()?];
+        while self.peek_is(token_kind!(and)) {
+            self.read()?;
+            decls.push(self.parse_type_decl()?);
+        }
+        Ok(Stmt::Type(decls))
+    }
+
+    fn parse_type_decl(&mut self) -> Result<TypeDecl> {
+        let name = self.parse_label()?;
+        self.expect(token_kind!(eq))?;
+        let (kind, end) = if self.peek_is(token_kind!(l_brace)) {
+            self.parse_record_decl()?
+        } else {
+            self.parse_variant_decl()?
+        };
+        Ok(TypeDecl {
+            name: name.name,
+            kind,
+            loc: name.loc.merge(&end),
+        })
+    }
+
+    // Returns the declaration and the location of its end.
+    fn parse_record_decl(&mut self) -> Result<(TypeDeclKind, Loc)> {
+        self.expect(token_kind!(l_brace))?;
+        let mut fields = Vec::new();
+        loop {
+            let mutable = self.peek_is(token_kind!(mutable));
+            if mutable {
+                self.read()?;
+            }
+            let label = self.parse_label()?;
+            self.expect(token_kind!(colon))?;
+            let typ_expr = self.parse_type_expr()?;
+            fields.push(FieldDecl {
+                mutable,
+                label,
+                typ_expr,
+                typ: None,
+            });
+            if !self.peek_is(token_kind!(semi)) {
+                break;
+            }
+            self.read()?;
+            if self.peek_is(token_kind!(r_brace)) {
+                break;
+            }
+        }
+        let rbrace = self.expect(token_kind!(r_brace))?;
+        Ok((TypeDeclKind::Record(fields), rbrace.loc))
+    }
+
+    // `|` may precede the first constructor.
+    fn parse_variant_decl(&mut self) -> Result<(TypeDeclKind, Loc)> {
+        if self.peek_is(token_kind!(bar)) {
+            self.read()?;
+        }
+        let mut constrs = vec![self.parse_constr_decl()?];
+        while self.peek_is(token_kind!(bar)) {
+            self.read()?;
+            constrs.push(self.parse_constr_decl()?);
+        }
+        let end = constrs[constrs.len() - 1].loc.clone();
+        Ok((TypeDeclKind::Variant(constrs), end))
+    }
+
+    fn parse_constr_decl(&mut self) -> Result<ConstrDecl> {
+        let (name, loc) = self.parse_constr_name()?;
+        let mut args = Vec::new();
+        if self.peek_is(token_kind!(of)) {
+            self.read()?;
+            args.push(self.parse_app_type()?);
+            while self.peek_is(token_kind!(star)) {
+                self.read()?;
+                args.push(self.parse_app_type()?);
+            }
+        }
+        let loc = match args.last() {
+            Some(last) => loc.merge(&last.loc),
+            None => loc,
+        };
+        Ok(ConstrDecl {
+            name,
+            args,
+            typs: None,
+            loc,
+        })
+    }
+
+    fn parse_constr_name(&mut self) -> Result<(String, Loc)> {
+        let tok = self.read()?;
+        let loc = tok.loc.clone();
+        expect_token_kind! {
+            tok => {
+                Ident(name) if is_module_name(&name) => (name, loc),
+            }
+        }
+    }
+
+    // `->` is right associative and binds looser than `*`.
+    fn parse_type_expr(&mut self) -> Result<TypeExpr> {
+        let param = self.parse_tuple_type()?;
+        if !self.peek_is(token_kind!(arrow)) {
+            return Ok(param);
+        }
+        self.read()?;
+        let ret = self.parse_type_expr()?;
+        let loc = param.loc.merge(&ret.loc);
+        Ok(TypeExpr::new(
+            TypeExprKind::Fun(Box::new(param), Box::new(ret)),
+            loc,
+        ))
+    }
+
+    fn parse_tuple_type(&mut self) -> Result<TypeExpr> {
+        let first = self.parse_app_type()?;
+        if !self.peek_is(token_kind!(star)) {
+            return Ok(first);
+        }
+        let mut types = vec![first];
+        while self.peek_is(token_kind!(star)) {
+            self.read()?;
+            types.push(self.parse_app_type()?);
+        }
+        let loc = types[0].loc.merge(&types[types.len() - 1].loc);
+        Ok(TypeExpr::new(TypeExprKind::Tuple(types), loc))
+    }
+
+    // Type constructors are applied postfix like `int list list`.
+    fn parse_app_type(&mut self) -> Result<TypeExpr> {
+        let mut typ = self.parse_atom_type()?;
+        while let Some(TokenKind::Ident(_)) = self.peek().map(|t| &t.kind) {
+            let name = self.parse_label()?;
+            let loc = typ.loc.merge(&name.loc);
+            typ = TypeExpr::new(TypeExprKind::Constr(vec![typ], name.name), loc);
+        }
+        Ok(typ)
+    }
+
+    fn parse_atom_type(&mut self) -> Result<TypeExpr> {
+        let tok = self.read()?;
+        let loc = tok.loc.clone();
+        expect_token_kind! {
+            tok => {
+                Ident(name) => TypeExpr::new(TypeExprKind::Constr(vec![], name), loc),
+                LParen => self.parse_paren_type(loc)?,
+            }
+        }
+    }
+
+    // `(t)` or arguments of a type constructor like `(t1, t2) c`.
+    fn parse_paren_type(&mut self, lparen: Loc) -> Result<TypeExpr> {
+        let mut types = vec![self.parse_type_expr()?];
+        while self.peek_is(token_kind!(comma)) {
+            self.read()?;
+            types.push(self.parse_type_expr()?);
+        }
+        let rparen = self.expect(token_kind!(r_paren))?;
+        if types.len() == 1 {
+            let mut typ = types.pop().unwrap();
+            typ.loc = lparen.merge(&rparen.loc);
+            return Ok(typ);
+        }
+        let name = self.parse_label()?;
+        Ok(TypeExpr::new(
+            TypeExprKind::Constr(types, name.name),
+            lparen.merge(&name.loc),
+        ))
+    }
+
+    // `begin e end` is same as `(e)`.
+    fn parse_begin(&mut self, begin: Loc) -> Result<Expr> {
+        self.parse_group(begin, token_kind!(end))
+    }
+
+    // `()` and `begin end` are the unit value.
+    fn parse_group(&mut self, open: Loc, close: TokenKindKey) -> Result<Expr> {
+        if self.peek_is(close) {
+            let close = self.read()?;
+            return Ok(Expr::new(ExprKind::UnitLiteral, open.merge(&close.loc)));
+        }
+        let mut expr = self.parse_seq_expr()?;
+        let close = self.expect(close)?;
+        expr.loc = open.merge(&close.loc);
+        Ok(expr)
+    }
 }
 
 #[cfg(test)]
@@ -130,7 +970,7 @@ mod tests {
         let s = code("42");
         test(&s, ast! { s => [ { int(42) } ] });
 
-        let s = code("1 2\n3 4");
+        let s = code("1;; 2\n;;3;;;; 4;;");
         test(
             &s,
             ast! { s => [
@@ -140,12 +980,659 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_apply() {
+        let s = code("f 1 (g x) y + -h 2 - 3");
+        test(
+            &s,
+            ast! { s => [
+                { {
+                    { apply {var(f)} {int(1)} { apply {var(g)} {var(x)} } {var(y)} }
+                    + { neg { apply {var(h)} {int(2)} } }
+                } - {int(3)} },
+            ] },
+        );
+
+        let s = code("(f 1) 2");
+        test(
+            &s,
+            ast! { s => [
+                { apply { apply {var(f)} {int(1)} } {int(2)} },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_top_level_let() {
+        let s =
+            code("let x = 1\nlet f a _ = a + x\n;; f 2 3\nlet y = let z = 1 in z;; let w = 2 in w");
+        test(
+            &s,
+            ast! { s => [
+                { let x = {int(1)} },
+                { let f (a _) = { {var(a)} + {var(x)} } },
+                { apply {var(f)} {int(2)} {int(3)} },
+                { let y = { let z = {int(1)} in {var(z)} } },
+                { let w = {int(2)} in {var(w)} },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_binop() {
+        let s = code("1 + 2 * 3 - 4");
+        test(
+            &s,
+            ast! { s => [
+                { { {int(1)} + { {int(2)} * {int(3)} } } - {int(4)} },
+            ] },
+        );
+
+        let s = code("1 - 2 - 3 / 4 / 5 mod 6");
+        test(
+            &s,
+            ast! { s => [
+                { { {int(1)} - {int(2)} } - { { { {int(3)} / {int(4)} } / {int(5)} } mod {int(6)} } },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_comparison() {
+        let s = code("1 + 2 < 3 = true && f x <> y || a >= b && c <= d && e > false");
+        test(
+            &s,
+            ast! { s => [
+                { {
+                    { { { {int(1)} + {int(2)} } < {int(3)} } = {bool(true)} }
+                    && { { apply {var(f)} {var(x)} } <> {var(y)} }
+                } || {
+                    { {var(a)} >= {var(b)} }
+                    && { { {var(c)} <= {var(d)} } && { {var(e)} > {bool(false)} } }
+                } },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_concat() {
+        let s = code(r#""a" ^ f "b" ^ "c" = "a" ^ "bc""#);
+        test(
+            &s,
+            ast! { s => [
+                { {
+                    {string("a")} ^ { { apply {var(f)} {string("b")} } ^ {string("c")} }
+                } = { {string("a")} ^ {string("bc")} } },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_if() {
+        let s = code("1 + if f true then 2 else if x then 3 else 4 * 5;; if a < b then a else b");
+        test(
+            &s,
+            ast! { s => [
+                { {int(1)} + {
+                    if { apply {var(f)} {bool(true)} } then {int(2)}
+                    else { if {var(x)} then {int(3)} else { {int(4)} * {int(5)} } }
+                } },
+                { if { {var(a)} < {var(b)} } then {var(a)} else {var(b)} },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_fun() {
+        let s = code("(fun x _ -> x + 1) 2 3;; let f = fun x -> fun y -> x y in f");
+        test(
+            &s,
+            ast! { s => [
+                { apply { fun (x _) -> { {var(x)} + {int(1)} } } {int(2)} {int(3)} },
+                { let f = { fun (x) -> { fun (y) -> { apply {var(x)} {var(y)} } } } in {var(f)} },
+            ] },
+        );
+
+        let s = code("fun -> 1");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 4,6; 1,5})]);
+    }
+
+    #[test]
+    fn test_error_if() {
+        let s = code("if true then 1");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 13,14; 1,14})]);
+
+        let s = code("if true else");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 8,12; 1,9})]);
+    }
+
+    #[test]
+    fn test_char() {
+        let s = code("Char.code 'a' + f Char.chr '\\n';; Char");
+        assert_eq!(
+            parse(tokenize(&s)).unwrap().to_string(),
+            "(expr (+ (apply (var Char.code) (char 'a')) (apply (var f) (var Char.chr) (char '\\n'))))\n\
+             (expr (constr Char))\n",
+        );
+
+        let s = code("Char.Code;; 1");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 5,9; 1,6})]);
+    }
+
+    #[test]
+    fn test_neg() {
+        let s = code("-1 - -2 * - (3)");
+        test(
+            &s,
+            ast! { s => [
+                { {int(-1)} - { {int(-2)} * { neg {int(3)} } } },
+            ] },
+        );
+
+        let s = code("- -9223372036854775808 --1");
+        test(
+            &s,
+            ast! { s => [
+                { { neg {int(i64::MIN)} } - {int(-1)} },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_let() {
+        let s = code("let x = 1 in let _ = 2 in x + let y = x in y * 3\n;;4");
+        test(
+            &s,
+            ast! { s => [
+                { let x = {int(1)} in {
+                    let _ = {int(2)} in {
+                        {var(x)} + { let y = {var(x)} in { {var(y)} * {int(3)} } }
+                    }
+                } },
+                { int(4) },
+            ] },
+        );
+
+        let s = code("let x = let y = 1 in y in x");
+        test(
+            &s,
+            ast! { s => [
+                { let x = { let y = {int(1)} in {var(y)} } in {var(x)} },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_let_rec() {
+        let s =
+            code("let rec f x = g x and g _ = f 1\nlet x = 1 and y = 2;; let rec h a = h a in h 1");
+        test(
+            &s,
+            ast! { s => [
+                { let rec f (x) = { apply {var(g)} {var(x)} } and g (_) = { apply {var(f)} {int(1)} } },
+                { let x = {int(1)} and y = {int(2)} },
+                { let rec h (a) = { apply {var(h)} {var(a)} } in { apply {var(h)} {int(1)} } },
+            ] },
+        );
+
+        let s = code("let x = 1 and y = 2 in x + y");
+        test(
+            &s,
+            ast! { s => [
+                { let x = {int(1)} and y = {int(2)} in { {var(x)} + {var(y)} } },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_error_let() {
+        let s = code("let 1 = 2");
+        test_error(
+            &s,
+            vec![error!(
+                "This pattern is only supported in match",
+                loc! {s => 4,5; 1,5}
+            )],
+        );
+
+        let s = code("let x = 1 and");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 10,13; 1,11})]);
+
+        let s = code("1 + let x = 1");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 12,13; 1,13})]);
+    }
+
+    #[test]
+    fn test_paren() {
+        let s = code("(1 + 2) * (3)");
+        test(
+            &s,
+            ast! { s => [
+                { { {int(1)} + {int(2)} } * {int(3)} },
+            ] },
+        );
+    }
+
+    #[test]
+    fn test_tuple() {
+        let s = code("1, 2 + 3, f x;; (1, (2, ()));; f (a, b) c;; if c then 1, 2 else 3, 4");
+        test(
+            &s,
+            ast! { s => [
+                { tuple {int(1)} { {int(2)} + {int(3)} } { apply {var(f)} {var(x)} } },
+                { tuple {int(1)} { tuple {int(2)} {unit} } },
+                { apply {var(f)} { tuple {var(a)} {var(b)} } {var(c)} },
+                { if {var(c)} then { tuple {int(1)} {int(2)} } else { tuple {int(3)} {int(4)} } },
+            ] },
+        );
+
+        let s = code(
+            "let x, (y, _) = p in x;; let (a, ()) = p;; let f (a, b) () = a;; fun (x, y) _ -> x, y",
+        );
+        test(
+            &s,
+            ast! { s => [
+                { let (x, (y, _)) = {var(p)} in {var(x)} },
+                { let (a, ()) = {var(p)} },
+                { let f ((a, b) ()) = {var(a)} },
+                { fun ((x, y) _) -> { tuple {var(x)} {var(y)} } },
+            ] },
+        );
+
+        let s = code("let (x, y) z");
+        test_error(
+            &s,
+            vec![error!("Unexpected token", loc! {s => 11,12; 1,12})],
+        );
+
+        let s = code("let (x, )");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 8,9; 1,9})]);
+    }
+
+    #[test]
+    fn test_list() {
+        let s =
+            code("[];; [1; 2, 3;];; f [x] [];; 1 :: 2 :: l @ m @ [3];; a ^ b :: c;; 1 + 2 :: []");
+        test(
+            &s,
+            ast! { s => [
+                { list },
+                { list {int(1)} { tuple {int(2)} {int(3)} } },
+                { apply {var(f)} { list {var(x)} } { list } },
+                { { {int(1)} :: { {int(2)} :: {var(l)} } } @ { {var(m)} @ { list {int(3)} } } },
+                { {var(a)} ^ { {var(b)} :: {var(c)} } },
+                { { {int(1)} + {int(2)} } :: { list } },
+            ] },
+        );
+
+        let s = code("[1; 2");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 4,5; 1,5})]);
+    }
+
+    #[test]
+    fn test_record() {
+        let s = code("{x = 1, 2; y = f p.x};; { p.q with x = 3; };; f r.x.y s");
+        test(
+            &s,
+            ast! { s => [
+                { record x = { tuple {int(1)} {int(2)} }; y = { apply {var(f)} { field {var(p)} x } } },
+                { with { field {var(p)} q } x = {int(3)} },
+                { apply {var(f)} { field { field {var(r)} x } y } {var(s)} },
+            ] },
+        );
+
+        let s = code(
+            "type t = { f : (int * (int -> a)) list; g : (int, int -> a) map; } and u = { x : t }",
+        );
+        let f = parse(tokenize(&s)).unwrap();
+        assert_eq!(
+            f.to_string(),
+            "(type (t (record (f (int * (int -> a)) list) (g (int, int -> a) map))) (u (record (x t))))\n",
+        );
+        let Stmt::Type(decls) = &f.stmts[0] else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(decls[0].loc, loc! {s => 5,66; 1,6});
+        let TypeDeclKind::Record(fields) = &decls[1].kind else {
+            panic!("unexpected {:?}", decls[1]);
+        };
+        assert_eq!(fields[0].label.loc, loc! {s => 77,78; 1,78});
+        assert_eq!(fields[0].typ_expr.loc, loc! {s => 81,82; 1,82});
+
+        let s = code("{ 1");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 2,3; 1,3})]);
+
+        let s = code("{ x = 1");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 6,7; 1,7})]);
+
+        let s = code(
+            "r := !r + 1, 2;; p.x <- q.y <- 3;; f !r.x !(g y);; if c then r := 1 else r := 2; 3",
+        );
+        test(
+            &s,
+            ast! { s => [
+                { {var(r)} := { tuple { { deref {var(r)} } + {int(1)} } {int(2)} } },
+                { setfield {var(p)} x { setfield {var(q)} y {int(3)} } },
+                { apply {var(f)} { field { deref {var(r)} } x } { deref { apply {var(g)} {var(y)} } } },
+                { { if {var(c)} then { {var(r)} := {int(1)} } else { {var(r)} := {int(2)} } } ; {int(3)} },
+            ] },
+        );
+
+        let s = code("type t = { mutable x : int; y : int ref }");
+        let f = parse(tokenize(&s)).unwrap();
+        assert_eq!(
+            f.to_string(),
+            "(type (t (record (mutable x int) (y int ref))))\n"
+        );
+
+        let s = code("x <- 1");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 2,4; 1,3})]);
+
+        let s = code("type t = { x : int; y }");
+        test_error(
+            &s,
+            vec![error!("Unexpected token", loc! {s => 22,23; 1,23})],
+        );
+    }
+
+    #[test]
+    fn test_array() {
+        let s =
+            code("[| 1; 2; |];; [||];; a.(i + 1).(0);; a.(0) <- f a.(1);; a.(0).x <- 1, [|[||]|]");
+        test(
+            &s,
+            ast! { s => [
+                { array {int(1)} {int(2)} },
+                { array },
+                { index { index {var(a)} { {var(i)} + {int(1)} } } {int(0)} },
+                { setindex {var(a)} {int(0)} { apply {var(f)} { index {var(a)} {int(1)} } } },
+                { setfield { index {var(a)} {int(0)} } x { tuple {int(1)} { array { array } } } },
+            ] },
+        );
+
+        let s = code("[| 1; 2");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 6,7; 1,7})]);
+
+        let s = code("a.()");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 3,4; 1,4})]);
+    }
+
+    #[test]
+    fn test_match() {
+        let s = code(
+            "match x with Some (1, 'a') -> 1 | x :: [_; ()] -> x | [] -> 0;; \
+             f (match l with | -1, _ -> 0; 1);; match e with A -> (match f with B -> 1 | C -> 2) | _ -> 3",
+        );
+        test(
+            &s,
+            ast! { s => [
+                { match {var(x)}
+                    | (constr Some ((int 1), (char b'a'))) -> {int(1)}
+                    | (:: x [_; ()]) -> {var(x)}
+                    | [] -> {int(0)}
+                },
+                { apply {var(f)} { match {var(l)} | ((int -1), _) -> { {int(0)} ; {int(1)} } } },
+                { match {var(e)}
+                    | (constr A) -> { match {var(f)} | (constr B) -> {int(1)} | (constr C) -> {int(2)} }
+                    | _ -> {int(3)}
+                },
+            ] },
+        );
+
+        let s = code(
+            "match e with true -> \"s\" | false :: _ -> \"t\" | Rect (a, _) -> a | Some Empty :: [] -> 0",
+        );
+        let f = parse(tokenize(&s)).unwrap();
+        assert_eq!(
+            f.to_string(),
+            "(expr (match (var e) ((bool true) (string \"s\")) ((:: (bool false) _) (string \"t\")) \
+             ((constr Rect (tuple a _)) (var a)) ((:: (constr Some (constr Empty)) (list)) (int 0))))\n",
+        );
+        let Stmt::Expr(Expr {
+            kind: ExprKind::Match(_, cases, _),
+            loc,
+            ..
+        }) = &f.stmts[0]
+        else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(*loc, loc! {s => 0,87; 1,1});
+        assert_eq!(cases[2].pattern.loc, loc! {s => 47,58; 1,48});
+
+        let s = code("match x with");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 8,12; 1,9})]);
+
+        let s = code("match x with 1 -");
+        test_error(
+            &s,
+            vec![error!("Unexpected token", loc! {s => 15,16; 1,16})],
+        );
+
+        let s = code("let f (Some x) = x");
+        test_error(
+            &s,
+            vec![error!(
+                "This pattern is only supported in match",
+                loc! {s => 6,14; 1,7}
+            )],
+        );
+
+        let s = code("fun [] -> 1");
+        test_error(
+            &s,
+            vec![error!(
+                "This pattern is only supported in match",
+                loc! {s => 4,6; 1,5}
+            )],
+        );
+    }
+
+    #[test]
+    fn test_match_guard() {
+        let s = code(
+            "match c with 'z'..'a' | '_' as c when c <> 'q' -> 1 | (1, _ | _, 2) as p -> 2 | _ -> 3;; \
+             let (a, b) as p = x",
+        );
+        let f = parse(tokenize(&s)).unwrap();
+        assert_eq!(
+            f.to_string(),
+            "(expr (match (var c) ((as (| (range 'a' 'z') (char '_')) c) (when (<> (var c) (char 'q'))) (int 1)) \
+             ((as (| (tuple (int 1) _) (tuple _ (int 2))) p) (int 2)) (_ (int 3))))\n\
+             (let ((as (tuple a b) p) (var x)))\n",
+        );
+        let Stmt::Expr(Expr {
+            kind: ExprKind::Match(_, cases, _),
+            ..
+        }) = &f.stmts[0]
+        else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(cases[0].pattern.loc, loc! {s => 13,32; 1,14});
+
+        let s = code("match x with 'a'..1 -> 1");
+        test_error(
+            &s,
+            vec![
+                error!("Unexpected token", loc! {s => 18,19; 1,19}),
+                error!("Unexpected token", loc! {s => 20,22; 1,21}),
+            ],
+        );
+
+        let s = code("match x with y as Some -> 1");
+        test_error(
+            &s,
+            vec![
+                error!("Expected a variable name", loc! {s => 18,22; 1,19}),
+                error!("Unexpected token", loc! {s => 23,25; 1,24}),
+            ],
+        );
+
+        let s = code("let (a | a) = x");
+        test_error(
+            &s,
+            vec![error!(
+                "This pattern is only supported in match",
+                loc! {s => 4,11; 1,5}
+            )],
+        );
+    }
+
+    #[test]
+    fn test_variant() {
+        let s = code("Empty;; Circle 1;; f (Rect (1, 2)) Empty;; Some x :: l;; Circle f x");
+        test(
+            &s,
+            ast! { s => [
+                { constr Empty },
+                { constr Circle {int(1)} },
+                { apply {var(f)} { constr Rect { tuple {int(1)} {int(2)} } } { constr Empty } },
+                { { constr Some {var(x)} } :: {var(l)} },
+                { apply { constr Circle {var(f)} } {var(x)} },
+            ] },
+        );
+
+        let s = code("type shape = Circle of int | Rect of int * int list | Empty;; type t = | A");
+        let f = parse(tokenize(&s)).unwrap();
+        assert_eq!(
+            f.to_string(),
+            "(type (shape (variant (Circle int) (Rect int int list) (Empty))))\n\
+             (type (t (variant (A))))\n",
+        );
+        let Stmt::Type(decls) = &f.stmts[0] else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(decls[0].loc, loc! {s => 5,59; 1,6});
+        let TypeDeclKind::Variant(constrs) = &decls[0].kind else {
+            panic!("unexpected {:?}", decls[0]);
+        };
+        assert_eq!(constrs[1].loc, loc! {s => 29,51; 1,30});
+
+        let s = code("type t = A of");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 11,13; 1,12})]);
+
+        let s = code("type t = a");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 9,10; 1,10})]);
+    }
+
+    #[test]
+    fn test_seq() {
+        let s = code(
+            "f (); g 1; h begin end;; let x = a; b in c; d;; if x then (a; b) else begin c end; e",
+        );
+        test(
+            &s,
+            ast! { s => [
+                { { apply {var(f)} {unit} } ; {
+                    { apply {var(g)} {int(1)} } ; { apply {var(h)} {unit} }
+                } },
+                { let x = { {var(a)} ; {var(b)} } in { {var(c)} ; {var(d)} } },
+                { { if {var(x)} then { {var(a)} ; {var(b)} } else {var(c)} } ; {var(e)} },
+            ] },
+        );
+
+        let s = code("fun x -> x; 1");
+        test(
+            &s,
+            ast! { s => [
+                { fun (x) -> { {var(x)} ; {int(1)} } },
+            ] },
+        );
+
+        let s = code("begin 1; 2");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 9,10; 1,10})]);
+
+        let s = code("(1; )");
+        test_error(&s, vec![error!("Unexpected token", loc! {s => 4,5; 1,5})]);
+    }
+
+    #[test]
+    fn test_loc() {
+        let s = code("1 + (2 * 3)");
+        let f = parse(tokenize(&s)).unwrap();
+        let Stmt::Expr(expr) = &f.stmts[0] else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(expr.loc, loc! {s => 0,11; 1,1});
+        match &expr.kind {
+            ExprKind::BinOp(_, lhs, rhs) => {
+                assert_eq!(lhs.loc, loc! {s => 0,1; 1,1});
+                assert_eq!(rhs.loc, loc! {s => 4,11; 1,5});
+            }
+            kind => panic!("unexpected {:?}", kind),
+        }
+
+        let s = code("let x = 1 in x");
+        let f = parse(tokenize(&s)).unwrap();
+        let Stmt::Expr(expr) = &f.stmts[0] else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(expr.loc, loc! {s => 0,14; 1,1});
+        match &expr.kind {
+            ExprKind::Let(_, body) => assert_eq!(body.loc, loc! {s => 13,14; 1,14}),
+            kind => panic!("unexpected {:?}", kind),
+        }
+
+        let s = code("f 1 (2)");
+        let f = parse(tokenize(&s)).unwrap();
+        let Stmt::Expr(expr) = &f.stmts[0] else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(expr.loc, loc! {s => 0,7; 1,1});
+
+        let s = code("( ); begin\nend");
+        let f = parse(tokenize(&s)).unwrap();
+        let Stmt::Expr(expr) = &f.stmts[0] else {
+            panic!("unexpected {:?}", f.stmts[0]);
+        };
+        assert_eq!(expr.loc, loc! {s => 0,14; 1,1});
+        match &expr.kind {
+            ExprKind::Seq(first, second) => {
+                assert_eq!(first.loc, loc! {s => 0,3; 1,1});
+                assert_eq!(second.loc, loc! {s => 5,14; 1,6});
+            }
+            kind => panic!("unexpected {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn test_error_paren() {
+        let s = code("(1 + 2");
+        test_error(&s, vec![error!("Unexpected EOF", loc! {s => 5,6; 1,6})]);
+
+        let s = code("1 + ?");
+        test_error(
+            &s,
+            vec![error!("Unexpected character", loc! {s => 4,5; 1,5})],
+        );
+    }
+
+    #[test]
+    fn test_error_int_overflow() {
+        let s = code("9223372036854775808");
+        test_error(
+            &s,
+            vec![error!(INT_LITERAL_OVERFLOW, loc! {s => 0,19; 1,1})],
+        );
+
+        let s = code("1 - 9223372036854775808");
+        test_error(
+            &s,
+            vec![error!(INT_LITERAL_OVERFLOW, loc! {s => 4,23; 1,5})],
+        );
+
+        let s = code("-9223372036854775809");
+        test_error(
+            &s,
+            vec![error!(INT_LITERAL_OVERFLOW, loc! {s => 1,20; 1,2})],
+        );
+    }
+
     #[test]
     fn test_error_simple() {
-        let s = code("x");
+        let s = code("in");
         test_error(
             &s,
-            vec![error!("unexpected token", loc! {s => {0, 1;1, 1}})],
+            vec![error!("Unexpected token", loc! {s => {0, 2;1, 1}})],
         );
     }
 }
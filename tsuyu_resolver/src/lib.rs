@@ -0,0 +1,518 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tsuyu_ast::{BindingGroup, Builtin, Expr, ExprKind, File, Pattern, PatternKind, Stmt};
+use tsuyu_error::{error, ComposedResult, Error, ErrorKind};
+
+/// Checks that every identifier refers to a binding in scope.
+pub fn resolve(file: &File) -> ComposedResult<()> {
+    let mut resolver = Resolver::default();
+    for builtin in Builtin::ALL {
+        resolver.scopes.push(builtin.name());
+    }
+    for stmt in &file.stmts {
+        resolver.stmt(stmt);
+    }
+    resolver
+        .errors
+        .is_empty()
+        .then_some(())
+        .ok_or(resolver.errors)
+}
+
+#[derive(Default)]
+struct Resolver<'a> {
+    scopes: Vec<&'a str>,
+    errors: Vec<Error>,
+}
+
+impl<'a> Resolver<'a> {
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match stmt {
+            Stmt::Expr(expr) => self.expr(expr),
+            Stmt::Let(group) => self.group(group),
+            // Type names and labels are resolved by the type checker.
+            Stmt::Type(_) => {}
+        }
+    }
+
+    // Leaves the names of the group in scope.
+    fn group(&mut self, group: &'a BindingGroup) {
+        let mut seen = Vec::new();
+        for binding in &group.bindings {
+            if let Some(name) = duplicate(&mut seen, &binding.pattern) {
+                self.errors.push(error!(
+                    format!("Variable {} is bound several times in this matching", name),
+                    binding.value.loc.clone()
+                ));
+            }
+            if group.rec && binding.name().is_none() {
+                self.errors.push(error!(
+                    "Only variables are allowed as left-hand side of `let rec`",
+                    binding.pattern.loc.clone()
+                ));
+                continue;
+            }
+            let is_function =
+                !binding.params.is_empty() || matches!(binding.value.kind, ExprKind::Fun(..));
+            if group.rec && !is_function {
+                self.errors.push(error!(
+                    "This kind of expression is not allowed as right-hand side of `let rec`",
+                    binding.value.loc.clone()
+                ));
+            }
+        }
+
+        if group.rec {
+            self.patterns(group.bindings.iter().map(|b| &b.pattern));
+        }
+        for binding in &group.bindings {
+            let len = self.scopes.len();
+            self.params(&binding.params);
+            self.expr(&binding.value);
+            self.scopes.truncate(len);
+        }
+        if !group.rec {
+            self.patterns(group.bindings.iter().map(|b| &b.pattern));
+        }
+    }
+
+    fn patterns(&mut self, patterns: impl Iterator<Item = &'a Pattern>) {
+        for pattern in patterns {
+            self.scopes.extend(pattern.names());
+        }
+    }
+
+    // All parameters of a function share one matching.
+    fn params(&mut self, params: &'a [Pattern]) {
+        let mut seen = Vec::new();
+        for param in params {
+            if let Some(name) = duplicate(&mut seen, param) {
+                self.errors.push(error!(
+                    format!("Variable {} is bound several times in this matching", name),
+                    param.loc.clone()
+                ));
+            }
+        }
+        self.patterns(params.iter());
+    }
+
+    fn var(&mut self, name: &str, expr: &Expr) {
+        if !self.scopes.contains(&name) {
+            self.errors.push(
+                Error::new(ErrorKind::UnboundValue(name.to_string())).with_loc(expr.loc.clone()),
+            );
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::UnitLiteral
+            | ExprKind::IntLiteral(_)
+            | ExprKind::BoolLiteral(_)
+            | ExprKind::StringLiteral(_)
+            | ExprKind::CharLiteral(_)
+            | ExprKind::Constr(_, None) => {}
+            ExprKind::Var(name) => self.var(name, expr),
+            ExprKind::Let(group, body) => {
+                let len = self.scopes.len();
+                self.group(group);
+                self.expr(body);
+                self.scopes.truncate(len);
+            }
+            ExprKind::Apply(func, args) => {
+                self.expr(func);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            ExprKind::Fun(params, body) => {
+                let len = self.scopes.len();
+                self.params(params);
+                self.expr(body);
+                self.scopes.truncate(len);
+            }
+            ExprKind::If(cond, then, els) => {
+                self.expr(cond);
+                self.expr(then);
+                self.expr(els);
+            }
+            ExprKind::Tuple(exprs) | ExprKind::List(exprs) | ExprKind::Array(exprs) => {
+                for expr in exprs {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::Record(fields) => {
+                for (_, expr) in fields {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::With(base, fields) => {
+                self.expr(base);
+                for (_, expr) in fields {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::UnOp(_, expr)
+            | ExprKind::Field(expr, _)
+            | ExprKind::Constr(_, Some(expr)) => self.expr(expr),
+            ExprKind::BinOp(_, lhs, rhs)
+            | ExprKind::Seq(lhs, rhs)
+            | ExprKind::SetField(lhs, _, rhs)
+            | ExprKind::Index(lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::SetIndex(array, index, value) => {
+                self.expr(array);
+                self.expr(index);
+                self.expr(value);
+            }
+            ExprKind::Match(expr, cases, _) => {
+                self.expr(expr);
+                for case in cases {
+                    let len = self.scopes.len();
+                    self.or_patterns(&case.pattern);
+                    self.params(std::slice::from_ref(&case.pattern));
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&case.body);
+                    self.scopes.truncate(len);
+                }
+            }
+        }
+    }
+
+    // Checks that both sides of every `|` in the pattern bind the same names.
+    fn or_patterns(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard
+            | PatternKind::Var(_)
+            | PatternKind::Unit
+            | PatternKind::IntLiteral(_)
+            | PatternKind::BoolLiteral(_)
+            | PatternKind::StringLiteral(_)
+            | PatternKind::CharLiteral(_)
+            | PatternKind::CharRange(..)
+            | PatternKind::Constr(_, None) => {}
+            PatternKind::Tuple(pats) | PatternKind::List(pats) => {
+                for pat in pats {
+                    self.or_patterns(pat);
+                }
+            }
+            PatternKind::Cons(head, tail) => {
+                self.or_patterns(head);
+                self.or_patterns(tail);
+            }
+            PatternKind::Constr(_, Some(pat)) | PatternKind::Alias(pat, _) => self.or_patterns(pat),
+            PatternKind::Or(left, right) => {
+                self.or_patterns(left);
+                self.or_patterns(right);
+                let (left_names, right_names) = (left.names(), right.names());
+                let missing = left_names
+                    .iter()
+                    .find(|name| !right_names.contains(name))
+                    .or_else(|| right_names.iter().find(|name| !left_names.contains(name)));
+                if let Some(name) = missing {
+                    self.errors.push(error!(
+                        format!(
+                            "Variable {} must occur on both sides of this | pattern",
+                            name
+                        ),
+                        pattern.loc.clone()
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// Adds the names of the pattern to `seen` and returns the first one which
+// was already there.
+fn duplicate<'a>(seen: &mut Vec<&'a str>, pattern: &'a Pattern) -> Option<&'a str> {
+    let mut dup = None;
+    for name in pattern.names() {
+        if seen.contains(&name) {
+            dup = dup.or(Some(name));
+        } else {
+            seen.push(name);
+        }
+    }
+    dup
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::rc::Rc;
+    use tsuyu_asserts::assert_eq;
+    use tsuyu_error::error;
+    use tsuyu_lexer::tokenize;
+    use tsuyu_parser::parse;
+    use tsuyu_source::{loc, Source};
+
+    fn resolve_code(s: &Rc<Source>) -> ComposedResult<()> {
+        resolve(&parse(tokenize(s)).unwrap())
+    }
+
+    fn unbound(name: &str) -> ErrorKind {
+        ErrorKind::UnboundValue(name.to_string())
+    }
+
+    #[test]
+    fn test_resolve() {
+        let s = Rc::new(Source::inline(
+            "let x = 1 in let y = x + 1 in let x = x * y in x - y",
+        ));
+        assert_eq!(resolve_code(&s), Ok(()));
+    }
+
+    #[test]
+    fn test_unbound() {
+        let s = Rc::new(Source::inline("let x = x in y + (let z = 1 in z) + z"));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("x")).with_loc(loc! {s => 8,9; 1,9}),
+                Error::new(unbound("y")).with_loc(loc! {s => 13,14; 1,14}),
+                Error::new(unbound("z")).with_loc(loc! {s => 36,37; 1,37}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_function() {
+        let s = Rc::new(Source::inline(
+            "let x = 1\nlet f x y = x + y\nlet g a = f a x;; g (f 1 2)",
+        ));
+        assert_eq!(resolve_code(&s), Ok(()));
+
+        // Not recursive without `rec`.
+        let s = Rc::new(Source::inline("let f x = f x"));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("f")).with_loc(loc! {s => 10,11; 1,11})
+            ]),
+        );
+
+        // Parameters are not visible after the definition.
+        let s = Rc::new(Source::inline("let f x = x;; x"));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("x")).with_loc(loc! {s => 14,15; 1,15})
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_let_rec() {
+        let s = Rc::new(Source::inline(
+            "let rec even n = odd n and odd n = even n;; even 1;; let x = 1 and y = 2 in x + y",
+        ));
+        assert_eq!(resolve_code(&s), Ok(()));
+
+        // Bindings of non-recursive group are not visible to each other.
+        let s = Rc::new(Source::inline(
+            "let x = 1;; let x = 2 and y = x;; let f a = g a and g a = a",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("g")).with_loc(loc! {s => 44,45; 1,45})
+            ]),
+        );
+
+        // A let rec name is not visible before the definition.
+        let s = Rc::new(Source::inline("let f x = g x\nlet rec g x = f x"));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("g")).with_loc(loc! {s => 10,11; 1,11})
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_let_error() {
+        let s = Rc::new(Source::inline(
+            "let rec x = 1;; let f x = x and f y = y;; let rec g a = let h b = b in h a",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                error!(
+                    "This kind of expression is not allowed as right-hand side of `let rec`",
+                    loc! {s => 12,13; 1,13}
+                ),
+                error!(
+                    "Variable f is bound several times in this matching",
+                    loc! {s => 38,39; 1,39}
+                ),
+            ]),
+        );
+
+        let s = Rc::new(Source::inline(
+            "let rec f = fun x -> f x;; let rec g x = let rec h y = h (g x) in h (fun y -> y + x)",
+        ));
+        assert_eq!(resolve_code(&s), Ok(()));
+    }
+
+    #[test]
+    fn test_apply() {
+        // Functions can be applied to any number of arguments.
+        let s = Rc::new(Source::inline(
+            "let f x y = x;; let v = f;; f 1;; v 1;; f 1 2 3;; let g x = let h a b = a in h 1;; g",
+        ));
+        assert_eq!(resolve_code(&s), Ok(()));
+    }
+
+    #[test]
+    fn test_builtin() {
+        let s = Rc::new(Source::inline(
+            "not true;; let not x = x + 1;; not 1;; if x then not else 1 2",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("x")).with_loc(loc! {s => 42,43; 1,43}),
+            ]),
+        );
+
+        let s = Rc::new(Source::inline(
+            "print_int (Char.code 'a'); print_newline (); print_endline (string_of_int 1); x",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("x")).with_loc(loc! {s => 78,79; 1,79}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let s = Rc::new(Source::inline("let _ = 1 in 2"));
+        assert_eq!(resolve_code(&s), Ok(()));
+    }
+
+    #[test]
+    fn test_tuple() {
+        let s = Rc::new(Source::inline(
+            "let (x, (y, _)) = (1, (2, 3));; let f (a, b) () = a + b + x + y;; fun (p, q) r -> p, q, r",
+        ));
+        assert_eq!(resolve_code(&s), Ok(()));
+
+        let s = Rc::new(Source::inline(
+            "let (x, x) = p;; let a, b = 1 and b = 2;; let f (u, v) u = v;; let rec (g, h) = 1, 2",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                error!(
+                    "Variable x is bound several times in this matching",
+                    loc! {s => 13,14; 1,14}
+                ),
+                Error::new(unbound("p")).with_loc(loc! {s => 13,14; 1,14}),
+                error!(
+                    "Variable b is bound several times in this matching",
+                    loc! {s => 38,39; 1,39}
+                ),
+                error!(
+                    "Variable u is bound several times in this matching",
+                    loc! {s => 55,56; 1,56}
+                ),
+                error!(
+                    "Only variables are allowed as left-hand side of `let rec`",
+                    loc! {s => 71,77; 1,72}
+                ),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let s = Rc::new(Source::inline(
+            "type t = { x : int };; let p = { x = 1 };; { x = p.x };; { q with x = y }",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("q")).with_loc(loc! {s => 59,60; 1,60}),
+                Error::new(unbound("y")).with_loc(loc! {s => 70,71; 1,71}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_array() {
+        let s = Rc::new(Source::inline(
+            "let a = Array.make 2 [||];; a.(0) <- [| Array.length a |];; b.(i) <- a.(j)",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("b")).with_loc(loc! {s => 60,61; 1,61}),
+                Error::new(unbound("i")).with_loc(loc! {s => 63,64; 1,64}),
+                Error::new(unbound("j")).with_loc(loc! {s => 72,73; 1,73}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_match() {
+        let s = Rc::new(Source::inline(
+            "let l = [1];; match l with x :: y :: _ -> x + y | [x] -> x | _ -> x;; \
+             match l with (x, x) -> 1",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("x")).with_loc(loc! {s => 66,67; 1,67}),
+                error!(
+                    "Variable x is bound several times in this matching",
+                    loc! {s => 83,89; 1,84}
+                ),
+            ]),
+        );
+
+        let s = Rc::new(Source::inline(
+            "let l = [1];; match l with [x] | [_; x] when x > 0 -> x | _ as y when y = [] -> y | _ -> [x];; \
+             match l with [x] | [y] -> 1 | [(x, _) | (_, y)] -> 2 | x as x -> 3",
+        ));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("x")).with_loc(loc! {s => 90,91; 1,91}),
+                error!(
+                    "Variable x must occur on both sides of this | pattern",
+                    loc! {s => 108,117; 1,109}
+                ),
+                error!(
+                    "Variable x must occur on both sides of this | pattern",
+                    loc! {s => 126,141; 1,127}
+                ),
+                error!(
+                    "Variable x is bound several times in this matching",
+                    loc! {s => 150,156; 1,151}
+                ),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_constr() {
+        let s = Rc::new(Source::inline("let x = 1;; Some (x, y);; Empty"));
+        assert_eq!(
+            resolve_code(&s),
+            Err(vec![
+                Error::new(unbound("y")).with_loc(loc! {s => 21,22; 1,22})
+            ]),
+        );
+    }
+}
@@ -0,0 +1,10 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// C source of the runtime library which is linked into every executable.
+pub const SOURCE: &str = include_str!("runtime.c");
+
+pub const FILE_NAME: &str = "tsuyu_runtime.c";
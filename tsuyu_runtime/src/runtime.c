@@ -0,0 +1,654 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// Same as OCaml.
+#define EXIT_UNCAUGHT_EXCEPTION 2
+
+// The unit value returned by functions called only for their effects.
+#define UNIT 0
+
+// `loc` is NULL if the location is unknown like in functions used as values.
+static void tsuyu_fatal(const char *loc, const char *exn) {
+    fflush(stdout);
+    if (loc != NULL) {
+        fprintf(stderr, "%s ", loc);
+    }
+    fprintf(stderr, "Fatal error: exception %s\n", exn);
+    exit(EXIT_UNCAUGHT_EXCEPTION);
+}
+
+void tsuyu_raise_division_by_zero(const char *loc) {
+    tsuyu_fatal(loc, "Division_by_zero");
+}
+
+void tsuyu_raise_index_out_of_bounds(const char *loc) {
+    tsuyu_fatal(loc, "Invalid_argument(\"index out of bounds\")");
+}
+
+void tsuyu_raise_match_failure(const char *loc) {
+    tsuyu_fatal(loc, "Match_failure");
+}
+
+// Blocks are never freed for now.
+int64_t *tsuyu_alloc(int64_t words) {
+    int64_t *block = malloc(words * sizeof(int64_t));
+    if (block == NULL) {
+        fflush(stdout);
+        fprintf(stderr, "Fatal error: out of memory\n");
+        exit(EXIT_UNCAUGHT_EXCEPTION);
+    }
+    return block;
+}
+
+// A string is a block of the length in bytes followed by the bytes and a null terminator.
+#define STRING_LEN 0
+#define STRING_BYTES(s) ((char *)&(s)[1])
+
+// Allocates a string of `len` bytes with the null terminator. The bytes are not initialized.
+static int64_t *tsuyu_string_alloc(int64_t len) {
+    int64_t *s = tsuyu_alloc(1 + (len + sizeof(int64_t)) / sizeof(int64_t));
+    s[STRING_LEN] = len;
+    STRING_BYTES(s)[len] = '\0';
+    return s;
+}
+
+int64_t *tsuyu_string_concat(int64_t *a, int64_t *b) {
+    int64_t len = a[STRING_LEN] + b[STRING_LEN];
+    int64_t *s = tsuyu_string_alloc(len);
+    memcpy(STRING_BYTES(s), STRING_BYTES(a), a[STRING_LEN]);
+    memcpy(STRING_BYTES(s) + a[STRING_LEN], STRING_BYTES(b), b[STRING_LEN]);
+    return s;
+}
+
+int64_t *tsuyu_string_of_int(int64_t n) {
+    char buf[24];
+    int len = snprintf(buf, sizeof(buf), "%lld", (long long)n);
+    int64_t *s = tsuyu_string_alloc(len);
+    memcpy(STRING_BYTES(s), buf, len);
+    return s;
+}
+
+// Compares bytes lexicographically like OCaml's `compare`. Returns -1, 0 or 1.
+int64_t tsuyu_string_compare(int64_t *a, int64_t *b) {
+    int64_t len = a[STRING_LEN] < b[STRING_LEN] ? a[STRING_LEN] : b[STRING_LEN];
+    int cmp = memcmp(STRING_BYTES(a), STRING_BYTES(b), len);
+    if (cmp == 0) {
+        cmp = (a[STRING_LEN] > b[STRING_LEN]) - (a[STRING_LEN] < b[STRING_LEN]);
+    }
+    return (cmp > 0) - (cmp < 0);
+}
+
+// Prints the string quoted and escaped like OCaml's `String.escaped`.
+static void dump_string(int64_t *s) {
+    unsigned char *bytes = (unsigned char *)STRING_BYTES(s);
+    putchar('"');
+    for (int64_t i = 0; i < s[STRING_LEN]; i++) {
+        unsigned char c = bytes[i];
+        switch (c) {
+        case '"':
+            fputs("\\\"", stdout);
+            break;
+        case '\\':
+            fputs("\\\\", stdout);
+            break;
+        case '\n':
+            fputs("\\n", stdout);
+            break;
+        case '\t':
+            fputs("\\t", stdout);
+            break;
+        case '\r':
+            fputs("\\r", stdout);
+            break;
+        case '\b':
+            fputs("\\b", stdout);
+            break;
+        default:
+            if (' ' <= c && c <= '~') {
+                putchar(c);
+            } else {
+                printf("\\%03d", c);
+            }
+        }
+    }
+    putchar('"');
+}
+
+// Prints the char quoted and escaped like OCaml's `Char.escaped`.
+static void dump_char(int64_t c) {
+    switch (c) {
+    case '\'':
+        fputs("'\\''", stdout);
+        break;
+    case '\\':
+        fputs("'\\\\'", stdout);
+        break;
+    case '\n':
+        fputs("'\\n'", stdout);
+        break;
+    case '\t':
+        fputs("'\\t'", stdout);
+        break;
+    case '\r':
+        fputs("'\\r'", stdout);
+        break;
+    case '\b':
+        fputs("'\\b'", stdout);
+        break;
+    default:
+        if (' ' <= c && c <= '~') {
+            printf("'%c'", (int)c);
+        } else {
+            printf("'\\%03d'", (int)c);
+        }
+    }
+}
+
+void tsuyu_dump_string(int64_t *s) {
+    dump_string(s);
+    putchar('\n');
+}
+
+void tsuyu_dump_char(int64_t c) {
+    dump_char(c);
+    putchar('\n');
+}
+
+// A list is NIL or a block of the head and the tail.
+#define NIL 0
+#define LIST_HEAD 0
+#define LIST_TAIL 1
+
+// An array is a block of the length followed by the elements.
+#define ARRAY_LENGTH 0
+#define ARRAY_ELEMS(a) (&(a)[1])
+
+// The shape of a value describes its type for dumping and comparing. It is one of `u` (unit),
+// `i` (int), `b` (bool), `s` (string), `c` (char), `p` (polymorphic), `f` (function),
+// `(...)` (tuple of the shapes of the elements), `l` followed by the shape of the elements (list),
+// `r` followed by the shape of the contents (ref), `a` followed by the shape of the elements
+// (array) and `t` followed by an index and `.` (type defined in `tsuyu_types`).
+//
+// Shapes of defined types are in the table generated by the compiler. A record is
+// `{name:shape;...}` with the fields in the order of the block. A variant is `<...>` with the
+// constructors separated by `|`. Each is its name followed by `:` and the shapes of the arguments
+// unless it is constant.
+extern const char *const tsuyu_types[];
+
+// Returns the rest of the shape after the first one.
+static const char *skip_shape(const char *shape) {
+    switch (*shape++) {
+    case 'l':
+    case 'r':
+    case 'a':
+        return skip_shape(shape);
+    case 't':
+        return strchr(shape, '.') + 1;
+    case '(':
+        while (*shape != ')') {
+            shape = skip_shape(shape);
+        }
+        return shape + 1;
+    default:
+        return shape;
+    }
+}
+
+// Returns the rest of the variant shape after the constructor and its `|`.
+static const char *skip_constr(const char *shape) {
+    shape += strcspn(shape, ":|>");
+    if (*shape == ':') {
+        shape++;
+        while (*shape != '|' && *shape != '>') {
+            shape = skip_shape(shape);
+        }
+    }
+    return *shape == '|' ? shape + 1 : shape;
+}
+
+static int is_constant_constr(const char *shape) {
+    return shape[strcspn(shape, ":|>")] != ':';
+}
+
+// Constant constructors are numbered from 0 in the order of the declaration. They are less than
+// the number of them, which no pointer to a block is.
+static int is_constant_value(int64_t v, const char *shape) {
+    uint64_t count = 0;
+    for (; *shape != '>'; shape = skip_constr(shape)) {
+        count += is_constant_constr(shape);
+    }
+    return (uint64_t)v < count;
+}
+
+// Finds the constructor of the value in the variant shape. A block of a non-constant constructor
+// starts with the tag numbered among the non-constant ones.
+static const char *find_constr(int64_t v, const char *shape) {
+    int constant = is_constant_value(v, shape);
+    int64_t tag = constant ? v : *(int64_t *)v;
+    for (;; shape = skip_constr(shape)) {
+        if (is_constant_constr(shape) == constant && tag-- == 0) {
+            return shape;
+        }
+    }
+}
+
+// Whether the value is parenthesized as the argument of a constructor.
+static int needs_parens(int64_t v, const char *shape) {
+    switch (*shape) {
+    case 'i':
+        return v < 0;
+    case 't': {
+        const char *def = tsuyu_types[strtol(shape + 1, NULL, 10)];
+        return *def == '<' && !is_constant_value(v, def + 1);
+    }
+    default:
+        return 0;
+    }
+}
+
+// Prints the value like OCaml's toplevel. Returns the rest of the shape after the value's.
+static const char *dump_value(int64_t v, const char *shape) {
+    switch (*shape++) {
+    case 'u':
+        fputs("()", stdout);
+        return shape;
+    case 'i':
+        printf("%lld", (long long)v);
+        return shape;
+    case 'b':
+        fputs(v ? "true" : "false", stdout);
+        return shape;
+    case 's':
+        dump_string((int64_t *)v);
+        return shape;
+    case 'c':
+        dump_char(v);
+        return shape;
+    case 'p':
+        fputs("<poly>", stdout);
+        return shape;
+    case 'f':
+        fputs("<fun>", stdout);
+        return shape;
+    case '(': {
+        int64_t *block = (int64_t *)v;
+        putchar('(');
+        for (int64_t i = 0; *shape != ')'; i++) {
+            if (i > 0) {
+                fputs(", ", stdout);
+            }
+            shape = dump_value(block[i], shape);
+        }
+        putchar(')');
+        return shape + 1;
+    }
+    case 'l':
+        putchar('[');
+        for (int64_t *cell = (int64_t *)v; cell != NIL; cell = (int64_t *)cell[LIST_TAIL]) {
+            if (cell != (int64_t *)v) {
+                fputs("; ", stdout);
+            }
+            dump_value(cell[LIST_HEAD], shape);
+        }
+        putchar(']');
+        return skip_shape(shape);
+    case 'a': {
+        int64_t *array = (int64_t *)v;
+        fputs("[|", stdout);
+        for (int64_t i = 0; i < array[ARRAY_LENGTH]; i++) {
+            if (i > 0) {
+                fputs("; ", stdout);
+            }
+            dump_value(ARRAY_ELEMS(array)[i], shape);
+        }
+        fputs("|]", stdout);
+        return skip_shape(shape);
+    }
+    case 'r':
+        fputs("{contents = ", stdout);
+        shape = dump_value(*(int64_t *)v, shape);
+        putchar('}');
+        return shape;
+    case 't': {
+        char *end;
+        long index = strtol(shape, &end, 10);
+        dump_value(v, tsuyu_types[index]);
+        return end + 1;
+    }
+    case '{': {
+        int64_t *block = (int64_t *)v;
+        putchar('{');
+        for (int64_t i = 0; *shape != '}'; i++) {
+            if (i > 0) {
+                fputs("; ", stdout);
+                shape++;
+            }
+            const char *colon = strchr(shape, ':');
+            printf("%.*s = ", (int)(colon - shape), shape);
+            shape = dump_value(block[i], colon + 1);
+        }
+        putchar('}');
+        return shape + 1;
+    }
+    case '<': {
+        const char *constr = find_constr(v, shape);
+        int len = (int)strcspn(constr, ":|>");
+        printf("%.*s", len, constr);
+        if (constr[len] == ':') {
+            int64_t *block = (int64_t *)v;
+            const char *args = constr + len + 1;
+            const char *rest = skip_shape(args);
+            // Several arguments are printed like a tuple.
+            int parens = (*rest != '|' && *rest != '>') || needs_parens(block[1], args);
+            fputs(parens ? " (" : " ", stdout);
+            for (int64_t i = 1; *args != '|' && *args != '>'; i++) {
+                if (i > 1) {
+                    fputs(", ", stdout);
+                }
+                args = dump_value(block[i], args);
+            }
+            if (parens) {
+                putchar(')');
+            }
+        }
+        while (*shape != '>') {
+            shape = skip_constr(shape);
+        }
+        return shape + 1;
+    }
+    default:
+        fprintf(stderr, "Fatal error: unknown shape %c\n", shape[-1]);
+        exit(EXIT_UNCAUGHT_EXCEPTION);
+    }
+}
+
+void tsuyu_dump(int64_t v, const char *shape) {
+    dump_value(v, shape);
+    putchar('\n');
+}
+
+static int64_t compare_words(int64_t a, int64_t b) {
+    return (a > b) - (a < b);
+}
+
+// Compares the values of the shape like OCaml's `compare`. Returns -1, 0 or 1. Functional values
+// raise `Invalid_argument` at `loc`.
+static int64_t compare_value(int64_t a, int64_t b, const char *shape, const char *loc) {
+    switch (*shape++) {
+    case 'u':
+        return 0;
+    case 'i':
+    case 'b':
+    case 'c':
+    case 'p':
+        return compare_words(a, b);
+    case 'f':
+        tsuyu_fatal(loc, "Invalid_argument(\"compare: functional value\")");
+        return 0;
+    case 's':
+        return tsuyu_string_compare((int64_t *)a, (int64_t *)b);
+    // Elements are compared in order until one differs.
+    case '(': {
+        int64_t *x = (int64_t *)a, *y = (int64_t *)b;
+        for (int64_t i = 0; *shape != ')'; i++, shape = skip_shape(shape)) {
+            int64_t cmp = compare_value(x[i], y[i], shape, loc);
+            if (cmp != 0) {
+                return cmp;
+            }
+        }
+        return 0;
+    }
+    // The empty list is less than any cell. Cells are compared by the heads and then the tails.
+    case 'l':
+        for (;; a = ((int64_t *)a)[LIST_TAIL], b = ((int64_t *)b)[LIST_TAIL]) {
+            if (a == NIL || b == NIL) {
+                return compare_words(a != NIL, b != NIL);
+            }
+            int64_t cmp =
+                compare_value(((int64_t *)a)[LIST_HEAD], ((int64_t *)b)[LIST_HEAD], shape, loc);
+            if (cmp != 0) {
+                return cmp;
+            }
+        }
+    // Shorter arrays are less, like OCaml.
+    case 'a': {
+        int64_t *x = (int64_t *)a, *y = (int64_t *)b;
+        if (x[ARRAY_LENGTH] != y[ARRAY_LENGTH]) {
+            return compare_words(x[ARRAY_LENGTH], y[ARRAY_LENGTH]);
+        }
+        for (int64_t i = 0; i < x[ARRAY_LENGTH]; i++) {
+            int64_t cmp = compare_value(ARRAY_ELEMS(x)[i], ARRAY_ELEMS(y)[i], shape, loc);
+            if (cmp != 0) {
+                return cmp;
+            }
+        }
+        return 0;
+    }
+    case 'r':
+        return compare_value(*(int64_t *)a, *(int64_t *)b, shape, loc);
+    case 't':
+        return compare_value(a, b, tsuyu_types[strtol(shape, NULL, 10)], loc);
+    // Fields are compared in the order of their declaration.
+    case '{': {
+        int64_t *x = (int64_t *)a, *y = (int64_t *)b;
+        for (int64_t i = 0; *shape != '}'; i++) {
+            shape = strchr(shape, ':') + 1;
+            int64_t cmp = compare_value(x[i], y[i], shape, loc);
+            if (cmp != 0) {
+                return cmp;
+            }
+            shape = skip_shape(shape);
+            shape += *shape == ';';
+        }
+        return 0;
+    }
+    // Constant constructors are less than the others and ordered by their declaration like
+    // OCaml. Others are ordered by their tags and then their arguments.
+    case '<': {
+        int constant_a = is_constant_value(a, shape), constant_b = is_constant_value(b, shape);
+        if (constant_a || constant_b) {
+            return constant_a && constant_b ? compare_words(a, b) : constant_b - constant_a;
+        }
+        int64_t *x = (int64_t *)a, *y = (int64_t *)b;
+        if (x[0] != y[0]) {
+            return compare_words(x[0], y[0]);
+        }
+        const char *args = find_constr(a, shape);
+        args += strcspn(args, ":|>") + 1;
+        for (int64_t i = 1; *args != '|' && *args != '>'; i++, args = skip_shape(args)) {
+            int64_t cmp = compare_value(x[i], y[i], args, loc);
+            if (cmp != 0) {
+                return cmp;
+            }
+        }
+        return 0;
+    }
+    default:
+        fprintf(stderr, "Fatal error: unknown shape %c\n", shape[-1]);
+        exit(EXIT_UNCAUGHT_EXCEPTION);
+    }
+}
+
+int64_t tsuyu_compare(int64_t a, int64_t b, const char *shape, const char *loc) {
+    return compare_value(a, b, shape, loc);
+}
+
+int64_t tsuyu_list_hd(int64_t *l) {
+    if (l == NIL) {
+        tsuyu_fatal(NULL, "Failure(\"hd\")");
+    }
+    return l[LIST_HEAD];
+}
+
+int64_t *tsuyu_list_tl(int64_t *l) {
+    if (l == NIL) {
+        tsuyu_fatal(NULL, "Failure(\"tl\")");
+    }
+    return (int64_t *)l[LIST_TAIL];
+}
+
+// Copies the cells of `a` in front of `b`.
+int64_t *tsuyu_list_append(int64_t *a, int64_t *b) {
+    int64_t *result = b;
+    int64_t **last = &result;
+    for (; a != NIL; a = (int64_t *)a[LIST_TAIL]) {
+        int64_t *cell = tsuyu_alloc(2);
+        cell[LIST_HEAD] = a[LIST_HEAD];
+        cell[LIST_TAIL] = (int64_t)b;
+        *last = cell;
+        last = (int64_t **)&cell[LIST_TAIL];
+    }
+    return result;
+}
+
+int64_t *tsuyu_array_make(int64_t len, int64_t init) {
+    if (len < 0) {
+        tsuyu_fatal(NULL, "Invalid_argument(\"Array.make\")");
+    }
+    int64_t *array = tsuyu_alloc(1 + len);
+    array[ARRAY_LENGTH] = len;
+    for (int64_t i = 0; i < len; i++) {
+        ARRAY_ELEMS(array)[i] = init;
+    }
+    return array;
+}
+
+int64_t tsuyu_char_chr(int64_t n) {
+    if (n < 0 || 255 < n) {
+        tsuyu_fatal(NULL, "Invalid_argument(\"Char.chr\")");
+    }
+    return n;
+}
+
+int64_t tsuyu_print_int(int64_t n) {
+    printf("%lld", (long long)n);
+    return UNIT;
+}
+
+int64_t tsuyu_print_char(int64_t c) {
+    putchar((int)c);
+    return UNIT;
+}
+
+// Strings may contain null bytes. So they are written by the length.
+int64_t tsuyu_print_string(int64_t *s) {
+    fwrite(STRING_BYTES(s), 1, s[STRING_LEN], stdout);
+    return UNIT;
+}
+
+int64_t tsuyu_print_endline(int64_t *s) {
+    tsuyu_print_string(s);
+    putchar('\n');
+    fflush(stdout);
+    return UNIT;
+}
+
+// Takes the unit value. Flushes like OCaml's `print_newline`.
+int64_t tsuyu_print_newline(int64_t unit) {
+    (void)unit;
+    putchar('\n');
+    fflush(stdout);
+    return UNIT;
+}
+
+// A closure is a block of the code address, the arity and captured values. The code takes the
+// arguments followed by the closure itself.
+//
+// A partial application is a block of the same shape whose code address and arity are 0 so that
+// generated code never calls it directly. It holds the closure, the number of arguments given so
+// far and the arguments.
+#define CLOSURE_CODE 0
+#define CLOSURE_ARITY 1
+#define PAP_CLOSURE 2
+#define PAP_ARGS_LEN 3
+#define PAP_ARGS 4
+
+// Calls the code of the closure with `arity` arguments and the closure in System V convention.
+int64_t tsuyu_call(int64_t *closure, int64_t *args, int64_t arity);
+__asm__(
+    ".intel_syntax noprefix\n"
+    ".text\n"
+    "tsuyu_call:\n"
+    "    push rbp\n"
+    "    mov rbp, rsp\n"
+    "    mov rax, rdi\n"
+    // Values are pushed so that popping 6 registers leaves the stack arguments aligned.
+    // Missing register arguments are filled with 0.
+    "    lea rcx, [rdx + 1]\n"
+    "    mov r8, 6\n"
+    "    cmp rcx, r8\n"
+    "    cmovg r8, rcx\n"
+    "    test r8, 1\n"
+    "    jz 2f\n"
+    "    sub rsp, 8\n"
+    "2:  cmp rcx, r8\n"
+    "    jge 3f\n"
+    "    push 0\n"
+    "    inc rcx\n"
+    "    jmp 2b\n"
+    "3:  push rdi\n"
+    "    mov rcx, rdx\n"
+    "4:  test rcx, rcx\n"
+    "    jz 5f\n"
+    "    push QWORD PTR [rsi + rcx * 8 - 8]\n"
+    "    dec rcx\n"
+    "    jmp 4b\n"
+    "5:  pop rdi\n"
+    "    pop rsi\n"
+    "    pop rdx\n"
+    "    pop rcx\n"
+    "    pop r8\n"
+    "    pop r9\n"
+    "    call QWORD PTR [rax]\n"
+    "    leave\n"
+    "    ret\n"
+    ".att_syntax prefix\n");
+
+// Applies the closure or the partial application to `len` arguments. Called from generated code
+// with the arguments on the stack when the number of them differs from the arity.
+int64_t tsuyu_apply(int64_t *closure, int64_t len, int64_t *args) {
+    for (;;) {
+        if (closure[CLOSURE_CODE] == 0) {
+            int64_t given = closure[PAP_ARGS_LEN];
+            int64_t *all = tsuyu_alloc(given + len);
+            for (int64_t i = 0; i < given; i++) {
+                all[i] = closure[PAP_ARGS + i];
+            }
+            for (int64_t i = 0; i < len; i++) {
+                all[given + i] = args[i];
+            }
+            closure = (int64_t *)closure[PAP_CLOSURE];
+            len += given;
+            args = all;
+        }
+
+        int64_t arity = closure[CLOSURE_ARITY];
+        if (len < arity) {
+            int64_t *pap = tsuyu_alloc(PAP_ARGS + len);
+            pap[CLOSURE_CODE] = 0;
+            pap[CLOSURE_ARITY] = 0;
+            pap[PAP_CLOSURE] = (int64_t)closure;
+            pap[PAP_ARGS_LEN] = len;
+            for (int64_t i = 0; i < len; i++) {
+                pap[PAP_ARGS + i] = args[i];
+            }
+            return (int64_t)pap;
+        }
+        int64_t result = tsuyu_call(closure, args, arity);
+        if (len == arity) {
+            return result;
+        }
+        // The result is a function applied to the rest of the arguments.
+        closure = (int64_t *)result;
+        len -= arity;
+        args += arity;
+    }
+}
@@ -105,6 +105,19 @@ impl Loc {
     pub fn source(&self) -> Option<&Rc<Source>> {
         self.source.as_ref()
     }
+
+    /// Returns the smallest location which covers both `self` and `other`.
+    pub fn merge(&self, other: &Loc) -> Loc {
+        let (head, tail) = if self.index <= other.index {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Loc {
+            len: cmp::max(head.index + head.len, tail.index + tail.len) - head.index,
+            ..head.clone()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -175,6 +188,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge() {
+        let s = Rc::new(Source::inline(""));
+        let lhs = loc! {s => 2,4; 1,3};
+        let rhs = loc! {s => 7,8; 2,1};
+        assert_eq!(lhs.merge(&rhs), loc! {s => 2,8; 1,3});
+        assert_eq!(rhs.merge(&lhs), loc! {s => 2,8; 1,3});
+        assert_eq!(lhs.merge(&loc! {s => 2,3; 1,3}), lhs);
+    }
+
     #[test]
     fn test_eq() {
         let s1 = Rc::new(Source::inline(""));
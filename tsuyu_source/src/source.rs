@@ -44,6 +44,12 @@ impl Source {
         }
         inner(path.into().into_boxed_path())
     }
+
+    pub fn read_from(name: impl Into<String>, mut reader: impl io::Read) -> io::Result<Source> {
+        let mut code = String::new();
+        reader.read_to_string(&mut code)?;
+        Ok(Self::dummy(name, code))
+    }
 }
 
 #[cfg(test)]
@@ -87,4 +93,16 @@ mod tests {
     fn test_read_file_not_found() {
         Source::read_file("xxxxxxxx").expect_err("");
     }
+
+    #[test]
+    fn test_read_from() {
+        let source = Source::read_from("<stdin>", "1 2\n3".as_bytes()).unwrap();
+        assert_eq!(source.path.to_str().unwrap(), "<stdin>");
+        assert_eq!(source.code, "1 2\n3");
+    }
+
+    #[test]
+    fn test_read_from_invalid_utf8() {
+        Source::read_from("<stdin>", [0xffu8, 0xfe].as_slice()).expect_err("");
+    }
 }
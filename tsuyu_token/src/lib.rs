@@ -4,7 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::iter::Peekable;
+use std::{fmt, iter::Peekable};
 
 use tsuyu_utils::derives::From;
 pub use tsuyu_utils::paste as _paste;
@@ -34,13 +34,10 @@ macro_rules! define_token_kind {
 
         impl TokenKind {
             pub fn key(&self) -> TokenKindKey {
-                _paste! {
-                    match self {
-                        $(
-                            Self::$variant $( ( $( [<__ $field:lower>]  ),+ ) )?
-                                => $crate::TokenKindKey::$variant,
-                        )+
-                    }
+                match self {
+                    $(
+                        Self::$variant { .. } => $crate::TokenKindKey::$variant,
+                    )+
                 }
             }
         }
@@ -49,10 +46,78 @@ macro_rules! define_token_kind {
 
 define_token_kind! {
     IntLiteral(i64),
+    /// Bytes of the string. They may be invalid UTF-8 like OCaml's strings.
+    #[from(ignore)]
+    StringLiteral(Vec<u8>),
+    /// Byte of the char.
+    #[from(ignore)]
+    CharLiteral(u8),
+    #[from(ignore)]
+    Ident(String),
     #[from(ignore)]
     Error(String),
+    True,
+    False,
+    Let,
+    Rec,
+    And,
+    In,
+    If,
+    Then,
+    Else,
+    Fun,
+    Match,
+    When,
+    As,
+    Begin,
+    End,
+    Type,
+    With,
+    Mutable,
+    Of,
+    Arrow,
+    Underscore,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    AndAnd,
+    BarBar,
+    Bar,
+    Semi,
+    SemiSemi,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Mod,
+    Caret,
+    At,
+    Colon,
+    ColonColon,
+    ColonEq,
+    LArrow,
+    Bang,
+    Dot,
+    DotDot,
+    Comma,
+    /// `'` which is not a part of a char literal like `'a` of type variables.
+    Quote,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBracketBar,
+    BarRBracket,
+    LBrace,
+    RBrace,
 }
 
+pub const INT_LITERAL_OVERFLOW: &str =
+    "Integer literal exceeds the range of representable integers of type int";
+
 #[cfg(test)]
 mod token_kind_tests {
     use crate::{TokenKind::*, *};
@@ -64,7 +129,10 @@ mod token_kind_tests {
 
     #[test]
     fn test_token_kind_key() {
-        assert_eq!(IntLiteral(42).key(), token_kind!(int_literal))
+        assert_eq!(IntLiteral(42).key(), token_kind!(int_literal));
+        assert_eq!(Plus.key(), token_kind!(plus));
+        assert_eq!(LParen.key(), token_kind!(l_paren));
+        assert_eq!(Ident("x".to_string()).key(), token_kind!(ident));
     }
 }
 
@@ -84,6 +152,12 @@ impl Token {
     }
 }
 
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} {:?}", self.loc.line, self.loc.column, self.kind)
+    }
+}
+
 #[macro_export]
 macro_rules! token {
     ($v:expr, $loc:expr) => {
@@ -127,6 +201,12 @@ mod token_tests {
         )
     }
 
+    #[test]
+    fn test_fmt() {
+        let loc = loc! { 4,6; 2,3 };
+        assert_eq!(format!("{}", token!(42, loc)), "2:3 IntLiteral(42)");
+    }
+
     #[test]
     fn test_token_kind() {
         let loc = loc! { 0,1; 1,1 };
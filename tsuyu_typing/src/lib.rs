@@ -0,0 +1,1765 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tsuyu_ast::{
+    BinOp, Binding, BindingGroup, Builtin, ConstrDecl, Expr, ExprKind, FieldDecl, File, Label,
+    Pattern, PatternKind, Stmt, Type, TypeDecl, TypeDeclKind, TypeExpr, TypeExprKind, UnOp,
+};
+use tsuyu_error::{error, ComposedResult, Error, ErrorKind, Result};
+use tsuyu_source::Loc;
+
+/// Infers the types of a resolved file and stores them in `Expr::typ` and `Binding::typ`.
+///
+/// Functions bound by `let` are generalized so that they can be used at different types.
+/// Type variables which are left undetermined remain as `Type::Var`.
+pub fn infer(file: &mut File) -> ComposedResult<()> {
+    let mut typer = Typer::default();
+    for builtin in Builtin::ALL {
+        typer
+            .env
+            .push((builtin.name().to_string(), Scheme::closed(builtin.typ())));
+    }
+    for stmt in &mut file.stmts {
+        if let Err(err) = typer.stmt(stmt) {
+            typer.errors.push(err);
+        }
+    }
+    typer.check_comparisons();
+    if !typer.errors.is_empty() {
+        return Err(typer.errors);
+    }
+    for stmt in &mut file.stmts {
+        typer.zonk_stmt(stmt);
+    }
+    Ok(())
+}
+
+/// Type quantified over `vars`.
+#[derive(Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    typ: Type,
+}
+
+impl Scheme {
+    fn mono(typ: Type) -> Self {
+        Self {
+            vars: Vec::new(),
+            typ,
+        }
+    }
+
+    // Quantifies all variables of the type.
+    fn closed(typ: Type) -> Self {
+        let mut vars = Vec::new();
+        type_vars(&typ, &mut vars);
+        Self { vars, typ }
+    }
+}
+
+enum Var {
+    /// Not determined yet. Variables deeper than the current level can be generalized.
+    Unbound(usize),
+    Link(Type),
+}
+
+/// Type declared by `type`.
+struct Decl {
+    name: String,
+    kind: DeclKind,
+}
+
+enum DeclKind {
+    Record(Vec<Field>),
+    /// Argument types of the constructors.
+    Variant(Vec<Vec<Type>>),
+}
+
+struct Field {
+    name: String,
+    typ: Type,
+    mutable: bool,
+}
+
+#[derive(Default)]
+struct Typer {
+    vars: Vec<Var>,
+    level: usize,
+    env: Vec<(String, Scheme)>,
+    /// Declared types indexed by the numbers of `Type::Named`.
+    decls: Vec<Decl>,
+    /// Type names in scope.
+    type_env: Vec<(String, usize)>,
+    /// Labels in scope and the types they belong to. A later label hides earlier ones.
+    labels: Vec<(String, usize)>,
+    /// Constructors in scope, the types they belong to and their indices. A later one hides
+    /// earlier ones.
+    constrs: Vec<(String, usize, usize)>,
+    /// Variables quantified by `generalize`.
+    generic: Vec<usize>,
+    /// Operand types of the polymorphic comparisons and their locations.
+    comparisons: Vec<(Type, Loc)>,
+    errors: Vec<Error>,
+}
+
+impl Typer {
+    fn fresh(&mut self) -> Type {
+        self.vars.push(Var::Unbound(self.level));
+        Type::Var(self.vars.len() - 1)
+    }
+
+    // Follows links at the top of the type.
+    fn repr(&self, typ: &Type) -> Type {
+        match typ {
+            Type::Var(v) => match &self.vars[*v] {
+                Var::Link(t) => self.repr(t),
+                Var::Unbound(_) => typ.clone(),
+            },
+            _ => typ.clone(),
+        }
+    }
+
+    // Substitutes all determined variables.
+    fn resolve(&self, typ: &Type) -> Type {
+        match self.repr(typ) {
+            Type::Fun(param, ret) => {
+                Type::Fun(Box::new(self.resolve(&param)), Box::new(self.resolve(&ret)))
+            }
+            Type::Tuple(elems) => Type::Tuple(elems.iter().map(|t| self.resolve(t)).collect()),
+            Type::List(elem) => Type::List(Box::new(self.resolve(&elem))),
+            Type::Ref(elem) => Type::Ref(Box::new(self.resolve(&elem))),
+            Type::Array(elem) => Type::Array(Box::new(self.resolve(&elem))),
+            typ => typ,
+        }
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> std::result::Result<(), ()> {
+        match (self.repr(a), self.repr(b)) {
+            (Type::Var(x), Type::Var(y)) if x == y => Ok(()),
+            (Type::Var(v), t) | (t, Type::Var(v)) => self.bind(v, t),
+            (Type::Fun(p1, r1), Type::Fun(p2, r2)) => {
+                self.unify(&p1, &p2)?;
+                self.unify(&r1, &r2)
+            }
+            (Type::Tuple(e1), Type::Tuple(e2)) if e1.len() == e2.len() => {
+                e1.iter().zip(&e2).try_for_each(|(a, b)| self.unify(a, b))
+            }
+            (Type::List(e1), Type::List(e2))
+            | (Type::Ref(e1), Type::Ref(e2))
+            | (Type::Array(e1), Type::Array(e2)) => self.unify(&e1, &e2),
+            // Base types.
+            (a, b) if a == b => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    fn bind(&mut self, v: usize, typ: Type) -> std::result::Result<(), ()> {
+        let Var::Unbound(level) = self.vars[v] else {
+            unreachable!("bind to linked variable");
+        };
+        if self.occurs(v, &typ) {
+            return Err(());
+        }
+        self.lower_levels(&typ, level);
+        self.vars[v] = Var::Link(typ);
+        Ok(())
+    }
+
+    fn occurs(&self, v: usize, typ: &Type) -> bool {
+        match self.repr(typ) {
+            Type::Var(u) => u == v,
+            Type::Fun(param, ret) => self.occurs(v, &param) || self.occurs(v, &ret),
+            Type::Tuple(elems) => elems.iter().any(|t| self.occurs(v, t)),
+            Type::List(elem) | Type::Ref(elem) | Type::Array(elem) => self.occurs(v, &elem),
+            Type::Unit | Type::Int | Type::Bool | Type::String | Type::Char | Type::Named(..) => {
+                false
+            }
+        }
+    }
+
+    fn lower_levels(&mut self, typ: &Type, level: usize) {
+        match self.repr(typ) {
+            Type::Var(v) => {
+                if let Var::Unbound(l) = &mut self.vars[v] {
+                    *l = (*l).min(level);
+                }
+            }
+            Type::Fun(param, ret) => {
+                self.lower_levels(&param, level);
+                self.lower_levels(&ret, level);
+            }
+            Type::Tuple(elems) => {
+                for elem in &elems {
+                    self.lower_levels(elem, level);
+                }
+            }
+            Type::List(elem) | Type::Ref(elem) | Type::Array(elem) => {
+                self.lower_levels(&elem, level)
+            }
+            Type::Unit | Type::Int | Type::Bool | Type::String | Type::Char | Type::Named(..) => {}
+        }
+    }
+
+    // Unifies the type of the expression at `loc` with the expected one.
+    fn expect(&mut self, loc: &Loc, actual: &Type, expected: &Type) -> Result<()> {
+        self.unify(actual, expected).map_err(|_| {
+            let (actual, expected) = self.mismatch(actual, expected);
+            Error::new(ErrorKind::TypeMismatch { actual, expected }).with_loc(loc.clone())
+        })
+    }
+
+    // Same as `expect` for the type of a pattern.
+    fn expect_pattern(&mut self, loc: &Loc, actual: &Type, expected: &Type) -> Result<()> {
+        self.unify(actual, expected).map_err(|_| {
+            let (actual, expected) = self.mismatch(actual, expected);
+            Error::new(ErrorKind::PatternTypeMismatch { actual, expected }).with_loc(loc.clone())
+        })
+    }
+
+    // Names the type variables of both types consistently.
+    fn mismatch(&self, actual: &Type, expected: &Type) -> (String, String) {
+        let mut names = Vec::new();
+        let actual = rename(&self.resolve(actual), &mut names);
+        let expected = rename(&self.resolve(expected), &mut names);
+        (actual.to_string(), expected.to_string())
+    }
+
+    fn generalize(&mut self, typ: &Type) -> Scheme {
+        let typ = self.resolve(typ);
+        let mut vars = Vec::new();
+        self.generic_vars(&typ, &mut vars);
+        self.generic.extend(&vars);
+        Scheme { vars, typ }
+    }
+
+    fn generic_vars(&self, typ: &Type, vars: &mut Vec<usize>) {
+        match typ {
+            Type::Var(v) => {
+                if matches!(self.vars[*v], Var::Unbound(l) if l > self.level) && !vars.contains(v) {
+                    vars.push(*v);
+                }
+            }
+            Type::Fun(param, ret) => {
+                self.generic_vars(param, vars);
+                self.generic_vars(ret, vars);
+            }
+            Type::Tuple(elems) => {
+                for elem in elems {
+                    self.generic_vars(elem, vars);
+                }
+            }
+            Type::List(elem) | Type::Ref(elem) | Type::Array(elem) => self.generic_vars(elem, vars),
+            Type::Unit | Type::Int | Type::Bool | Type::String | Type::Char | Type::Named(..) => {}
+        }
+    }
+
+    // Keeps the variables of a binding which is not generalized from being generalized later.
+    fn monomorphize(&mut self, typ: &Type) {
+        self.lower_levels(typ, self.level);
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let mapping: Vec<_> = scheme.vars.iter().map(|v| (*v, self.fresh())).collect();
+        substitute(&scheme.typ, &mapping)
+    }
+
+    fn lookup(&self, name: &str) -> &Scheme {
+        self.env
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, scheme)| scheme)
+            .expect("unresolved name")
+    }
+
+    // Finds the type which the label belongs to.
+    fn label(&self, label: &Label) -> Result<usize> {
+        self.labels
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == label.name)
+            .map(|(_, id)| *id)
+            .ok_or_else(|| {
+                error!(
+                    format!("Unbound record field {}", label.name),
+                    label.loc.clone()
+                )
+            })
+    }
+
+    fn named(&self, id: usize) -> Type {
+        Type::Named(self.decls[id].name.clone(), id)
+    }
+
+    // Labels belong only to record types.
+    fn record_fields(&self, id: usize) -> &[Field] {
+        match &self.decls[id].kind {
+            DeclKind::Record(fields) => fields,
+            DeclKind::Variant(_) => unreachable!("fields of a variant type"),
+        }
+    }
+
+    // Index and declaration of the field of the record type `id`.
+    fn field(&self, id: usize, label: &Label) -> Result<(usize, &Field)> {
+        let fields = self.record_fields(id);
+        match fields.iter().position(|f| f.name == label.name) {
+            Some(index) => Ok((index, &fields[index])),
+            None => {
+                let other = self.label(label)?;
+                Err(error!(
+                    format!(
+                        "The record field {} belongs to the type {} but is mixed here with fields of type {}",
+                        label.name, self.decls[other].name, self.decls[id].name
+                    ),
+                    label.loc.clone()
+                ))
+            }
+        }
+    }
+
+    // Finds the type and the index of the constructor.
+    fn constr(&self, name: &str, loc: &Loc) -> Result<(usize, usize)> {
+        self.constrs
+            .iter()
+            .rev()
+            .find(|(n, _, _)| n == name)
+            .map(|(_, id, index)| (*id, *index))
+            .ok_or_else(|| error!(format!("Unbound constructor {}", name), loc.clone()))
+    }
+}
+
+impl Typer {
+    fn stmt(&mut self, stmt: &mut Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Expr(expr) => self.expr(expr).map(|_| ()),
+            Stmt::Let(group) => self.group(group),
+            Stmt::Type(decls) => self.type_decls(decls),
+        }
+    }
+
+    // Type names of the group are in scope in all declarations of it.
+    fn type_decls(&mut self, decls: &mut [TypeDecl]) -> Result<()> {
+        let first = self.decls.len();
+        for (i, decl) in decls.iter().enumerate() {
+            if decls[..i].iter().any(|d| d.name == decl.name) {
+                return Err(error!(
+                    format!("Multiple definition of the type name {}.", decl.name),
+                    decl.loc.clone()
+                ));
+            }
+            self.decls.push(Decl {
+                name: decl.name.clone(),
+                kind: match decl.kind {
+                    TypeDeclKind::Record(_) => DeclKind::Record(Vec::new()),
+                    TypeDeclKind::Variant(_) => DeclKind::Variant(Vec::new()),
+                },
+            });
+            self.type_env.push((decl.name.clone(), first + i));
+        }
+        for (i, decl) in decls.iter_mut().enumerate() {
+            match &mut decl.kind {
+                TypeDeclKind::Record(fields) => self.record_decl(first + i, fields)?,
+                TypeDeclKind::Variant(constrs) => self.variant_decl(first + i, constrs)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn record_decl(&mut self, id: usize, fields: &mut [FieldDecl]) -> Result<()> {
+        for j in 0..fields.len() {
+            let label = &fields[j].label;
+            if fields[..j].iter().any(|f| f.label == *label) {
+                return Err(error!(
+                    format!("Two labels are named {}", label.name),
+                    label.loc.clone()
+                ));
+            }
+            let typ = self.type_expr(&fields[j].typ_expr)?;
+            let DeclKind::Record(decl) = &mut self.decls[id].kind else {
+                unreachable!();
+            };
+            decl.push(Field {
+                name: label.name.clone(),
+                typ: typ.clone(),
+                mutable: fields[j].mutable,
+            });
+            fields[j].typ = Some(typ);
+        }
+        for field in fields {
+            self.labels.push((field.label.name.clone(), id));
+        }
+        Ok(())
+    }
+
+    fn variant_decl(&mut self, id: usize, constrs: &mut [ConstrDecl]) -> Result<()> {
+        for j in 0..constrs.len() {
+            let constr = &constrs[j];
+            if constrs[..j].iter().any(|c| c.name == constr.name) {
+                return Err(error!(
+                    format!("Two constructors are named {}", constr.name),
+                    constr.loc.clone()
+                ));
+            }
+            let args = constr
+                .args
+                .iter()
+                .map(|arg| self.type_expr(arg))
+                .collect::<Result<Vec<_>>>()?;
+            let DeclKind::Variant(decl) = &mut self.decls[id].kind else {
+                unreachable!();
+            };
+            decl.push(args.clone());
+            constrs[j].typs = Some(args);
+        }
+        for (index, constr) in constrs.iter().enumerate() {
+            self.constrs.push((constr.name.clone(), id, index));
+        }
+        Ok(())
+    }
+
+    fn type_expr(&self, typ: &TypeExpr) -> Result<Type> {
+        match &typ.kind {
+            TypeExprKind::Constr(args, name) => {
+                // Declared types hide the predefined ones.
+                let named = self.type_env.iter().rev().find(|(n, _)| n == name);
+                let arity = match (named, name.as_str()) {
+                    (Some(_), _) | (None, "unit" | "int" | "bool" | "string" | "char") => 0,
+                    (None, "list" | "ref" | "array") => 1,
+                    _ => {
+                        return Err(error!(
+                            format!("Unbound type constructor {}", name),
+                            typ.loc.clone()
+                        ))
+                    }
+                };
+                if args.len() != arity {
+                    return Err(error!(
+                        format!(
+                            "The type constructor {} expects {} argument(s), but is here applied to {} argument(s)",
+                            name,
+                            arity,
+                            args.len()
+                        ),
+                        typ.loc.clone()
+                    ));
+                }
+                let mut args = args
+                    .iter()
+                    .map(|arg| self.type_expr(arg))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(match (named, name.as_str()) {
+                    (Some((name, id)), _) => Type::Named(name.clone(), *id),
+                    (None, "unit") => Type::Unit,
+                    (None, "int") => Type::Int,
+                    (None, "bool") => Type::Bool,
+                    (None, "string") => Type::String,
+                    (None, "char") => Type::Char,
+                    (None, "list") => Type::List(Box::new(args.remove(0))),
+                    (None, "ref") => Type::Ref(Box::new(args.remove(0))),
+                    (None, "array") => Type::Array(Box::new(args.remove(0))),
+                    _ => unreachable!(),
+                })
+            }
+            TypeExprKind::Tuple(types) => Ok(Type::Tuple(
+                types
+                    .iter()
+                    .map(|t| self.type_expr(t))
+                    .collect::<Result<_>>()?,
+            )),
+            TypeExprKind::Fun(param, ret) => Ok(Type::Fun(
+                Box::new(self.type_expr(param)?),
+                Box::new(self.type_expr(ret)?),
+            )),
+        }
+    }
+
+    // Leaves the names of the group in the environment even if it fails.
+    fn group(&mut self, group: &mut BindingGroup) -> Result<()> {
+        self.level += 1;
+        let mut vars = Vec::new();
+        let types: Vec<_> = group
+            .bindings
+            .iter()
+            .map(|binding| {
+                let mut binding_vars = Vec::new();
+                let typ = self.irrefutable(&binding.pattern, &mut binding_vars);
+                vars.push(binding_vars);
+                typ
+            })
+            .collect();
+
+        let len = self.env.len();
+        if group.rec {
+            for (name, typ) in vars.iter().flatten() {
+                self.env.push((name.clone(), Scheme::mono(typ.clone())));
+            }
+        }
+        let result = group
+            .bindings
+            .iter_mut()
+            .zip(&types)
+            .try_for_each(|(binding, typ)| self.binding(binding, typ));
+        self.env.truncate(len);
+        self.level -= 1;
+
+        for ((binding, typ), vars) in group.bindings.iter_mut().zip(types).zip(vars) {
+            // Results of applications are not generalized like OCaml's value restriction.
+            let generalizable = !binding.params.is_empty() || is_value(&binding.value);
+            for (name, typ) in vars {
+                let scheme = if generalizable {
+                    self.generalize(&typ)
+                } else {
+                    self.monomorphize(&typ);
+                    Scheme::mono(typ)
+                };
+                self.env.push((name, scheme));
+            }
+            binding.typ = Some(typ);
+        }
+        result
+    }
+
+    fn binding(&mut self, binding: &mut Binding, typ: &Type) -> Result<()> {
+        let len = self.env.len();
+        let params = self.params(&binding.params);
+        let result = self.expr(&mut binding.value);
+        self.env.truncate(len);
+        let typ_of_value = Type::fun(params, result?);
+        self.expect(&binding.value.loc, &typ_of_value, typ)
+    }
+
+    // Pushes the parameters to the environment and returns their types.
+    fn params(&mut self, params: &[Pattern]) -> Vec<Type> {
+        let mut vars = Vec::new();
+        let types = params
+            .iter()
+            .map(|param| self.irrefutable(param, &mut vars))
+            .collect();
+        for (name, typ) in vars {
+            self.env.push((name, Scheme::mono(typ)));
+        }
+        types
+    }
+
+    // Patterns of `let` and `fun` are checked to be irrefutable by the parser. They always have
+    // types.
+    fn irrefutable(&mut self, pattern: &Pattern, vars: &mut Vec<(String, Type)>) -> Type {
+        self.pattern(pattern, vars)
+            .expect("refutable pattern outside match")
+    }
+
+    // Returns the type of values matched by the pattern and adds the types of the names.
+    fn pattern(&mut self, pattern: &Pattern, vars: &mut Vec<(String, Type)>) -> Result<Type> {
+        Ok(match &pattern.kind {
+            PatternKind::Wildcard => self.fresh(),
+            PatternKind::Var(name) => {
+                let typ = self.fresh();
+                vars.push((name.clone(), typ.clone()));
+                typ
+            }
+            PatternKind::Unit => Type::Unit,
+            PatternKind::Tuple(pats) => Type::Tuple(
+                pats.iter()
+                    .map(|pat| self.pattern(pat, vars))
+                    .collect::<Result<_>>()?,
+            ),
+            PatternKind::IntLiteral(_) => Type::Int,
+            PatternKind::BoolLiteral(_) => Type::Bool,
+            PatternKind::StringLiteral(_) => Type::String,
+            PatternKind::CharLiteral(_) | PatternKind::CharRange(..) => Type::Char,
+            PatternKind::List(pats) => {
+                let elem = self.fresh();
+                for pat in pats {
+                    let typ = self.pattern(pat, vars)?;
+                    self.expect_pattern(&pat.loc, &typ, &elem)?;
+                }
+                Type::List(Box::new(elem))
+            }
+            PatternKind::Cons(head, tail) => {
+                let typ = Type::List(Box::new(self.pattern(head, vars)?));
+                let tail_typ = self.pattern(tail, vars)?;
+                self.expect_pattern(&tail.loc, &tail_typ, &typ)?;
+                typ
+            }
+            PatternKind::Constr(name, arg) => {
+                let (id, index) = self.constr(name, &pattern.loc)?;
+                let DeclKind::Variant(constrs) = &self.decls[id].kind else {
+                    unreachable!();
+                };
+                let params = constrs[index].clone();
+                // `_` matches all arguments.
+                let given = match arg.as_deref() {
+                    None => 0,
+                    Some(Pattern {
+                        kind: PatternKind::Tuple(pats),
+                        ..
+                    }) if params.len() > 1 => pats.len(),
+                    Some(Pattern {
+                        kind: PatternKind::Wildcard,
+                        ..
+                    }) => params.len(),
+                    Some(_) => 1,
+                };
+                if given != params.len() {
+                    return Err(error!(
+                        format!(
+                            "The constructor {} expects {} argument(s), but is applied here to {} argument(s)",
+                            name,
+                            params.len(),
+                            given
+                        ),
+                        pattern.loc.clone()
+                    ));
+                }
+                match arg.as_deref() {
+                    Some(Pattern {
+                        kind: PatternKind::Tuple(pats),
+                        ..
+                    }) if params.len() > 1 => {
+                        for (pat, param) in pats.iter().zip(&params) {
+                            let typ = self.pattern(pat, vars)?;
+                            self.expect_pattern(&pat.loc, &typ, param)?;
+                        }
+                    }
+                    Some(arg) if params.len() == 1 => {
+                        let typ = self.pattern(arg, vars)?;
+                        self.expect_pattern(&arg.loc, &typ, &params[0])?;
+                    }
+                    _ => {}
+                }
+                self.named(id)
+            }
+            PatternKind::Or(left, right) => {
+                let typ = self.pattern(left, vars)?;
+                let mut right_vars = Vec::new();
+                let right_typ = self.pattern(right, &mut right_vars)?;
+                self.expect_pattern(&right.loc, &right_typ, &typ)?;
+                // The resolver checked that both sides bind the same names.
+                for (name, right_typ) in right_vars {
+                    let (_, left_typ) = vars.iter().rev().find(|(n, _)| *n == name).unwrap();
+                    let left_typ = left_typ.clone();
+                    if self.unify(&right_typ, &left_typ).is_err() {
+                        let (left_typ, right_typ) = self.mismatch(&left_typ, &right_typ);
+                        return Err(error!(
+                            format!(
+                                "The variable {} on the left-hand side of this or-pattern has type {} \
+                                 but on the right-hand side it has type {}",
+                                name, left_typ, right_typ
+                            ),
+                            pattern.loc.clone()
+                        ));
+                    }
+                }
+                typ
+            }
+            PatternKind::Alias(pat, name) => {
+                let typ = self.pattern(pat, vars)?;
+                vars.push((name.clone(), typ.clone()));
+                typ
+            }
+        })
+    }
+
+    // The guard of a case is a bool with the names of the pattern in scope.
+    fn guard(&mut self, guard: Option<&mut Expr>) -> Result<()> {
+        let Some(guard) = guard else {
+            return Ok(());
+        };
+        let typ = self.expr(guard)?;
+        self.expect(&guard.loc, &typ, &Type::Bool)
+    }
+
+    // Checks the fields given to the record type `id` and returns their indices.
+    fn fields(&mut self, id: usize, fields: &mut [(Label, Expr)]) -> Result<Vec<usize>> {
+        let mut indices = Vec::new();
+        for (label, value) in fields {
+            let (index, field) = self.field(id, label)?;
+            let typ = field.typ.clone();
+            if indices.contains(&index) {
+                return Err(error!(
+                    format!("The record field {} is defined several times", label.name),
+                    label.loc.clone()
+                ));
+            }
+            indices.push(index);
+            let value_typ = self.expr(value)?;
+            self.expect(&value.loc, &value_typ, &typ)?;
+        }
+        Ok(indices)
+    }
+
+    fn expr(&mut self, expr: &mut Expr) -> Result<Type> {
+        let typ = match &mut expr.kind {
+            ExprKind::UnitLiteral => Type::Unit,
+            ExprKind::IntLiteral(_) => Type::Int,
+            ExprKind::BoolLiteral(_) => Type::Bool,
+            ExprKind::StringLiteral(_) => Type::String,
+            ExprKind::CharLiteral(_) => Type::Char,
+            ExprKind::Var(name) => {
+                let scheme = self.lookup(name).clone();
+                self.instantiate(&scheme)
+            }
+            ExprKind::Let(group, body) => {
+                let len = self.env.len();
+                let result = self.group(group).and_then(|_| self.expr(body));
+                self.env.truncate(len);
+                result?
+            }
+            ExprKind::Apply(func, args) => {
+                let mut typ = self.expr(func)?;
+                for (i, arg) in args.iter_mut().enumerate() {
+                    let (param, ret) = match self.repr(&typ) {
+                        Type::Fun(param, ret) => (*param, *ret),
+                        Type::Var(_) => {
+                            let (param, ret) = (self.fresh(), self.fresh());
+                            let fun = Type::fun(vec![param.clone()], ret.clone());
+                            self.expect(&func.loc, &typ, &fun)?;
+                            (param, ret)
+                        }
+                        _ if i == 0 => {
+                            return Err(error!(
+                                "This expression is not a function; it cannot be applied",
+                                func.loc.clone()
+                            ))
+                        }
+                        _ => {
+                            return Err(error!(
+                                "This function is applied to too many arguments",
+                                func.loc.clone()
+                            ))
+                        }
+                    };
+                    let arg_typ = self.expr(arg)?;
+                    self.expect(&arg.loc, &arg_typ, &param)?;
+                    typ = ret;
+                }
+                typ
+            }
+            ExprKind::Fun(params, body) => {
+                let len = self.env.len();
+                let param_types = self.params(params);
+                let result = self.expr(body);
+                self.env.truncate(len);
+                Type::fun(param_types, result?)
+            }
+            ExprKind::Tuple(exprs) => Type::Tuple(
+                exprs
+                    .iter_mut()
+                    .map(|expr| self.expr(expr))
+                    .collect::<Result<_>>()?,
+            ),
+            ExprKind::List(exprs) => {
+                let elem = self.fresh();
+                for expr in exprs {
+                    let typ = self.expr(expr)?;
+                    self.expect(&expr.loc, &typ, &elem)?;
+                }
+                Type::List(Box::new(elem))
+            }
+            ExprKind::Array(exprs) => {
+                let elem = self.fresh();
+                for expr in exprs {
+                    let typ = self.expr(expr)?;
+                    self.expect(&expr.loc, &typ, &elem)?;
+                }
+                Type::Array(Box::new(elem))
+            }
+            ExprKind::Index(array, index) => {
+                let elem = self.fresh();
+                let typ = self.expr(array)?;
+                self.expect(&array.loc, &typ, &Type::Array(Box::new(elem.clone())))?;
+                let typ = self.expr(index)?;
+                self.expect(&index.loc, &typ, &Type::Int)?;
+                elem
+            }
+            ExprKind::SetIndex(array, index, value) => {
+                let elem = self.fresh();
+                let typ = self.expr(array)?;
+                self.expect(&array.loc, &typ, &Type::Array(Box::new(elem.clone())))?;
+                let typ = self.expr(index)?;
+                self.expect(&index.loc, &typ, &Type::Int)?;
+                let typ = self.expr(value)?;
+                self.expect(&value.loc, &typ, &elem)?;
+                Type::Unit
+            }
+            ExprKind::If(cond, then, els) => {
+                let typ = self.expr(cond)?;
+                self.expect(&cond.loc, &typ, &Type::Bool)?;
+                let typ = self.expr(then)?;
+                let els_typ = self.expr(els)?;
+                self.expect(&els.loc, &els_typ, &typ)?;
+                typ
+            }
+            ExprKind::UnOp(UnOp::Neg, operand) => {
+                let typ = self.expr(operand)?;
+                self.expect(&operand.loc, &typ, &Type::Int)?;
+                Type::Int
+            }
+            ExprKind::UnOp(UnOp::Deref, operand) => {
+                let elem = self.fresh();
+                let typ = self.expr(operand)?;
+                self.expect(&operand.loc, &typ, &Type::Ref(Box::new(elem.clone())))?;
+                elem
+            }
+            ExprKind::BinOp(BinOp::Assign, lhs, rhs) => {
+                let elem = self.fresh();
+                let typ = self.expr(lhs)?;
+                self.expect(&lhs.loc, &typ, &Type::Ref(Box::new(elem.clone())))?;
+                let typ = self.expr(rhs)?;
+                self.expect(&rhs.loc, &typ, &elem)?;
+                Type::Unit
+            }
+            ExprKind::BinOp(BinOp::Cons, head, tail) => {
+                let elem = self.expr(head)?;
+                let list = Type::List(Box::new(elem));
+                let typ = self.expr(tail)?;
+                self.expect(&tail.loc, &typ, &list)?;
+                list
+            }
+            ExprKind::BinOp(op, lhs, rhs) => {
+                let (operand, result) = match op {
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod => {
+                        (Some(Type::Int), Type::Int)
+                    }
+                    BinOp::Concat => (Some(Type::String), Type::String),
+                    BinOp::Append => {
+                        let list = Type::List(Box::new(self.fresh()));
+                        (Some(list.clone()), list)
+                    }
+                    BinOp::Cons | BinOp::Assign => unreachable!(),
+                    BinOp::And | BinOp::Or => (Some(Type::Bool), Type::Bool),
+                    // Polymorphic comparison.
+                    _ => (None, Type::Bool),
+                };
+                // Comparisons with `[]` do not look at the elements of the other list.
+                let is_empty_list = |e: &Expr| matches!(&e.kind, ExprKind::List(l) if l.is_empty());
+                let checked = operand.is_none() && !is_empty_list(lhs) && !is_empty_list(rhs);
+                let lhs_typ = self.expr(lhs)?;
+                let operand = match operand {
+                    Some(typ) => {
+                        self.expect(&lhs.loc, &lhs_typ, &typ)?;
+                        typ
+                    }
+                    None => lhs_typ,
+                };
+                let rhs_typ = self.expr(rhs)?;
+                self.expect(&rhs.loc, &rhs_typ, &operand)?;
+                if checked {
+                    self.comparisons.push((operand, expr.loc.clone()));
+                }
+                result
+            }
+            // The first expression may have any type. OCaml only warns unless it is unit.
+            ExprKind::Seq(first, second) => {
+                self.expr(first)?;
+                self.expr(second)?
+            }
+            ExprKind::Record(fields) => {
+                let id = self.label(&fields[0].0)?;
+                let given = self.fields(id, fields)?;
+                let missing: Vec<_> = self
+                    .record_fields(id)
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !given.contains(i))
+                    .map(|(_, field)| field.name.as_str())
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(error!(
+                        format!("Some record fields are undefined: {}", missing.join(" ")),
+                        expr.loc.clone()
+                    ));
+                }
+                self.named(id)
+            }
+            ExprKind::With(base, fields) => {
+                let id = self.label(&fields[0].0)?;
+                let typ = self.named(id);
+                let base_typ = self.expr(base)?;
+                self.expect(&base.loc, &base_typ, &typ)?;
+                self.fields(id, fields)?;
+                typ
+            }
+            ExprKind::Field(record, label) => {
+                let id = self.label(label)?;
+                let typ = self.expr(record)?;
+                let named = self.named(id);
+                self.expect(&record.loc, &typ, &named)?;
+                self.field(id, label)?.1.typ.clone()
+            }
+            ExprKind::SetField(record, label, value) => {
+                let id = self.label(label)?;
+                let typ = self.expr(record)?;
+                let named = self.named(id);
+                self.expect(&record.loc, &typ, &named)?;
+                let field = self.field(id, label)?.1;
+                if !field.mutable {
+                    return Err(error!(
+                        format!("The record field {} is not mutable", label.name),
+                        expr.loc.clone()
+                    ));
+                }
+                let field_typ = field.typ.clone();
+                let typ = self.expr(value)?;
+                self.expect(&value.loc, &typ, &field_typ)?;
+                Type::Unit
+            }
+            ExprKind::Match(scrutinee, cases, _) => {
+                let typ = self.expr(scrutinee)?;
+                let result = self.fresh();
+                for case in cases {
+                    let mut vars = Vec::new();
+                    let pattern_typ = self.pattern(&case.pattern, &mut vars)?;
+                    self.expect_pattern(&case.pattern.loc, &pattern_typ, &typ)?;
+                    let len = self.env.len();
+                    for (name, typ) in vars {
+                        self.env.push((name, Scheme::mono(typ)));
+                    }
+                    let body = self
+                        .guard(case.guard.as_mut())
+                        .and_then(|_| self.expr(&mut case.body));
+                    self.env.truncate(len);
+                    self.expect(&case.body.loc, &body?, &result)?;
+                }
+                result
+            }
+            ExprKind::Constr(name, arg) => {
+                let (id, index) = self.constr(name, &expr.loc)?;
+                let DeclKind::Variant(constrs) = &self.decls[id].kind else {
+                    unreachable!();
+                };
+                let params = constrs[index].clone();
+                // Several arguments are given as a tuple written in place.
+                let given = match arg.as_deref() {
+                    None => 0,
+                    Some(Expr {
+                        kind: ExprKind::Tuple(exprs),
+                        ..
+                    }) if params.len() > 1 => exprs.len(),
+                    Some(_) => 1,
+                };
+                if given != params.len() {
+                    return Err(error!(
+                        format!(
+                            "The constructor {} expects {} argument(s), but is applied here to {} argument(s)",
+                            name,
+                            params.len(),
+                            given
+                        ),
+                        expr.loc.clone()
+                    ));
+                }
+                match arg.as_deref_mut() {
+                    None => {}
+                    Some(arg) if params.len() > 1 => {
+                        let ExprKind::Tuple(exprs) = &mut arg.kind else {
+                            unreachable!();
+                        };
+                        for (expr, param) in exprs.iter_mut().zip(&params) {
+                            let typ = self.expr(expr)?;
+                            self.expect(&expr.loc, &typ, param)?;
+                        }
+                        arg.typ = Some(Type::Tuple(params));
+                    }
+                    Some(arg) => {
+                        let typ = self.expr(arg)?;
+                        self.expect(&arg.loc, &typ, &params[0])?;
+                    }
+                }
+                self.named(id)
+            }
+        };
+        expr.typ = Some(typ.clone());
+        Ok(typ)
+    }
+}
+
+// Values are compared by the code generated for their type, which a function generalized over
+// the type does not know. OCaml compares them by their runtime representation instead.
+impl Typer {
+    fn check_comparisons(&mut self) {
+        for (typ, loc) in &self.comparisons {
+            let typ = self.resolve(typ);
+            let mut vars = Vec::new();
+            type_vars(&typ, &mut vars);
+            if vars.iter().any(|v| self.generic.contains(v)) {
+                let typ = rename(&typ, &mut Vec::new());
+                self.errors.push(error!(
+                    format!("Values of the polymorphic type {} cannot be compared", typ),
+                    loc.clone()
+                ));
+            }
+        }
+    }
+}
+
+// Writes the inferred types back.
+impl Typer {
+    fn zonk_stmt(&self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Expr(expr) => self.zonk_expr(expr),
+            Stmt::Let(group) => self.zonk_group(group),
+            // Types of fields have no variables.
+            Stmt::Type(_) => {}
+        }
+    }
+
+    fn zonk_group(&self, group: &mut BindingGroup) {
+        for binding in &mut group.bindings {
+            binding.typ = binding.typ.as_ref().map(|t| self.resolve(t));
+            self.zonk_expr(&mut binding.value);
+        }
+    }
+
+    fn zonk_expr(&self, expr: &mut Expr) {
+        expr.typ = expr.typ.as_ref().map(|t| self.resolve(t));
+        match &mut expr.kind {
+            ExprKind::UnitLiteral
+            | ExprKind::IntLiteral(_)
+            | ExprKind::BoolLiteral(_)
+            | ExprKind::StringLiteral(_)
+            | ExprKind::CharLiteral(_)
+            | ExprKind::Var(_)
+            | ExprKind::Constr(_, None) => {}
+            ExprKind::Let(group, body) => {
+                self.zonk_group(group);
+                self.zonk_expr(body);
+            }
+            ExprKind::Apply(func, args) => {
+                self.zonk_expr(func);
+                for arg in args {
+                    self.zonk_expr(arg);
+                }
+            }
+            ExprKind::Fun(_, body) => self.zonk_expr(body),
+            ExprKind::Tuple(exprs) | ExprKind::List(exprs) | ExprKind::Array(exprs) => {
+                for expr in exprs {
+                    self.zonk_expr(expr);
+                }
+            }
+            ExprKind::If(cond, then, els) => {
+                self.zonk_expr(cond);
+                self.zonk_expr(then);
+                self.zonk_expr(els);
+            }
+            ExprKind::Record(fields) => {
+                for (_, expr) in fields {
+                    self.zonk_expr(expr);
+                }
+            }
+            ExprKind::With(base, fields) => {
+                self.zonk_expr(base);
+                for (_, expr) in fields {
+                    self.zonk_expr(expr);
+                }
+            }
+            ExprKind::UnOp(_, operand)
+            | ExprKind::Field(operand, _)
+            | ExprKind::Constr(_, Some(operand)) => self.zonk_expr(operand),
+            ExprKind::BinOp(_, lhs, rhs)
+            | ExprKind::Seq(lhs, rhs)
+            | ExprKind::SetField(lhs, _, rhs)
+            | ExprKind::Index(lhs, rhs) => {
+                self.zonk_expr(lhs);
+                self.zonk_expr(rhs);
+            }
+            ExprKind::SetIndex(array, index, value) => {
+                self.zonk_expr(array);
+                self.zonk_expr(index);
+                self.zonk_expr(value);
+            }
+            ExprKind::Match(scrutinee, cases, _) => {
+                self.zonk_expr(scrutinee);
+                for case in cases {
+                    if let Some(guard) = &mut case.guard {
+                        self.zonk_expr(guard);
+                    }
+                    self.zonk_expr(&mut case.body);
+                }
+            }
+        }
+    }
+}
+
+// Syntactic values which can be generalized.
+fn is_value(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::UnitLiteral
+        | ExprKind::IntLiteral(_)
+        | ExprKind::BoolLiteral(_)
+        | ExprKind::StringLiteral(_)
+        | ExprKind::CharLiteral(_)
+        | ExprKind::Var(_)
+        | ExprKind::Constr(_, None)
+        | ExprKind::Fun(..) => true,
+        ExprKind::Tuple(exprs) | ExprKind::List(exprs) => exprs.iter().all(is_value),
+        // Arrays are mutable unless empty.
+        ExprKind::Array(exprs) => exprs.is_empty(),
+        ExprKind::Record(fields) => fields.iter().all(|(_, expr)| is_value(expr)),
+        // Constructor application.
+        ExprKind::Constr(_, Some(arg)) => is_value(arg),
+        ExprKind::BinOp(BinOp::Cons, head, tail) => is_value(head) && is_value(tail),
+        _ => false,
+    }
+}
+
+fn type_vars(typ: &Type, vars: &mut Vec<usize>) {
+    match typ {
+        Type::Var(v) => {
+            if !vars.contains(v) {
+                vars.push(*v);
+            }
+        }
+        Type::Fun(param, ret) => {
+            type_vars(param, vars);
+            type_vars(ret, vars);
+        }
+        Type::Tuple(elems) => {
+            for elem in elems {
+                type_vars(elem, vars);
+            }
+        }
+        Type::List(elem) | Type::Ref(elem) | Type::Array(elem) => type_vars(elem, vars),
+        Type::Unit | Type::Int | Type::Bool | Type::String | Type::Char | Type::Named(..) => {}
+    }
+}
+
+fn substitute(typ: &Type, mapping: &[(usize, Type)]) -> Type {
+    match typ {
+        Type::Var(v) => mapping
+            .iter()
+            .find(|(u, _)| u == v)
+            .map_or_else(|| typ.clone(), |(_, t)| t.clone()),
+        Type::Fun(param, ret) => Type::Fun(
+            Box::new(substitute(param, mapping)),
+            Box::new(substitute(ret, mapping)),
+        ),
+        Type::Tuple(elems) => Type::Tuple(elems.iter().map(|t| substitute(t, mapping)).collect()),
+        Type::List(elem) => Type::List(Box::new(substitute(elem, mapping))),
+        Type::Ref(elem) => Type::Ref(Box::new(substitute(elem, mapping))),
+        Type::Array(elem) => Type::Array(Box::new(substitute(elem, mapping))),
+        Type::Unit | Type::Int | Type::Bool | Type::String | Type::Char | Type::Named(..) => {
+            typ.clone()
+        }
+    }
+}
+
+// Numbers variables in order of appearance so that messages show `'a`, `'b`, ...
+fn rename(typ: &Type, names: &mut Vec<usize>) -> Type {
+    match typ {
+        Type::Var(v) => Type::Var(names.iter().position(|u| u == v).unwrap_or_else(|| {
+            names.push(*v);
+            names.len() - 1
+        })),
+        Type::Fun(param, ret) => {
+            Type::Fun(Box::new(rename(param, names)), Box::new(rename(ret, names)))
+        }
+        Type::Tuple(elems) => Type::Tuple(elems.iter().map(|t| rename(t, names)).collect()),
+        Type::List(elem) => Type::List(Box::new(rename(elem, names))),
+        Type::Ref(elem) => Type::Ref(Box::new(rename(elem, names))),
+        Type::Array(elem) => Type::Array(Box::new(rename(elem, names))),
+        Type::Unit | Type::Int | Type::Bool | Type::String | Type::Char | Type::Named(..) => {
+            typ.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::rc::Rc;
+    use tsuyu_asserts::assert_eq;
+    use tsuyu_lexer::tokenize;
+    use tsuyu_parser::parse;
+    use tsuyu_resolver::resolve;
+    use tsuyu_source::{loc, Source};
+
+    fn infer_code(s: &Rc<Source>) -> ComposedResult<File> {
+        let mut file = parse(tokenize(s)).unwrap();
+        resolve(&file).unwrap();
+        infer(&mut file).map(|_| file)
+    }
+
+    // Types of top-level bindings and expressions.
+    fn types(s: &Rc<Source>) -> Vec<String> {
+        let file = infer_code(s).unwrap();
+        let mut types = Vec::new();
+        for stmt in &file.stmts {
+            let typ = match stmt {
+                Stmt::Expr(expr) => expr.typ.as_ref(),
+                Stmt::Let(group) => group.bindings.last().unwrap().typ.as_ref(),
+                Stmt::Type(_) => continue,
+            };
+            types.push(rename(typ.unwrap(), &mut Vec::new()).to_string());
+        }
+        types
+    }
+
+    fn mismatch(actual: &str, expected: &str) -> ErrorKind {
+        ErrorKind::TypeMismatch {
+            actual: actual.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    fn pattern_mismatch(actual: &str, expected: &str) -> ErrorKind {
+        ErrorKind::PatternTypeMismatch {
+            actual: actual.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_infer() {
+        let s = Rc::new(Source::inline(
+            "let x = 1 < 2 && not false;; let f a b = if a then b else b + 1;; f x 1;; let n = -1;; \
+             let s = \"a\" ^ \"b\";; fun a b -> a ^ b = \"\";; Char.chr (Char.code 'a' + 1);; \
+             let u = ();; fun x -> print_int x; print_newline ();; print_string \"a\"; true;; \
+             begin end = ();; string_of_bool",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "bool",
+                "bool -> int -> int",
+                "int",
+                "int",
+                "string",
+                "string -> string -> bool",
+                "char",
+                "unit",
+                "int -> unit",
+                "bool",
+                "bool",
+                "bool -> string",
+            ]
+            .map(String::from),
+        );
+    }
+
+    #[test]
+    fn test_polymorphic() {
+        let s = Rc::new(Source::inline(
+            "let id x = x;; id true;; id 1;; let twice f x = f (f x);; twice not false;; \
+             let k a _ = a",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "'a -> 'a",
+                "bool",
+                "int",
+                "('a -> 'a) -> 'a -> 'a",
+                "bool",
+                "'a -> 'b -> 'a"
+            ]
+            .map(String::from),
+        );
+    }
+
+    #[test]
+    fn test_polymorphic_compare() {
+        // Comparisons in generalized functions do not know the type of the values.
+        let s = Rc::new(Source::inline(
+            "let eq a b = a = b;; let mem x l = l <> [] && List.hd l = x;; \
+             let f x = let g y = (y, 1) < (x, 1) in g;; \
+             let is_empty l = l = [];; let eq_int a b = a = b + 0;; [] = []",
+        ));
+        let polymorphic =
+            |typ: &str| format!("Values of the polymorphic type {} cannot be compared", typ);
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                error!(polymorphic("'a"), loc! {s => 13,18; 1,14}),
+                error!(polymorphic("'a"), loc! {s => 46,59; 1,47}),
+                error!(polymorphic("'a * int"), loc! {s => 82,97; 1,83}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_let_rec() {
+        let s = Rc::new(Source::inline(
+            "let rec even n = if n = 0 then true else odd (n - 1) \
+             and odd n = if n = 0 then false else even (n - 1);; \
+             let rec loop x = loop x;; loop 1",
+        ));
+        assert_eq!(
+            types(&s),
+            ["int -> bool", "'a -> 'b", "'a"].map(String::from),
+        );
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let s = Rc::new(Source::inline(
+            "if 1 then 2 else 3;; 1 + true;; if true then 1 else false;; not 1;; 1 = true",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("int", "bool")).with_loc(loc! {s => 3,4; 1,4}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 25,29; 1,26}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 52,57; 1,53}),
+                Error::new(mismatch("int", "bool")).with_loc(loc! {s => 64,65; 1,65}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 72,76; 1,73}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_mismatch_binding() {
+        // Parameters are monomorphic in the body.
+        let s = Rc::new(Source::inline(
+            "let f x = x + 1;; f true;; let g y = y && y = 1",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 20,24; 1,21}),
+                Error::new(mismatch("int", "bool")).with_loc(loc! {s => 46,47; 1,47}),
+            ]),
+        );
+
+        // Recursive uses are monomorphic.
+        let s = Rc::new(Source::inline("let rec f x = if f true then x else f 1"));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("int", "bool")).with_loc(loc! {s => 38,39; 1,39})
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_value_restriction() {
+        // `y` is not a function so its type is not generalized.
+        let s = Rc::new(Source::inline(
+            "let rec loop x = loop x;; let y = loop 1;; let f _ = y;; f 1 + 1;; f 1 && true",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("int", "bool")).with_loc(loc! {s => 67,70; 1,68})
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_fun() {
+        let s = Rc::new(Source::inline(
+            "let twice f x = f (f x);; let compose f g = fun x -> f (g x);; \
+             let add n = fun x -> x + n;; twice (add 1);; fun x -> not x",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "('a -> 'a) -> 'a -> 'a",
+                "('a -> 'b) -> ('c -> 'a) -> 'c -> 'b",
+                "int -> int -> int",
+                "int -> int",
+                "bool -> bool",
+            ]
+            .map(String::from),
+        );
+    }
+
+    #[test]
+    fn test_apply_error() {
+        let s = Rc::new(Source::inline(
+            "1 2;; (fun x -> x + 1) 1 2;; let f g = g 1 && g true",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                error!(
+                    "This expression is not a function; it cannot be applied",
+                    loc! {s => 0,1; 1,1}
+                ),
+                error!(
+                    "This function is applied to too many arguments",
+                    loc! {s => 6,22; 1,7}
+                ),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 48,52; 1,49}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_tuple() {
+        let s = Rc::new(Source::inline(
+            "let p = 1, true;; let (x, (y, _)) = (\"a\", ('b', ()));; y;; let swap (a, b) = b, a;; \
+             swap p;; fst;; snd (fst (1, 'c'), 2);; let f () = ();; fun (a, b) (c, d) -> a + d",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "int * bool",
+                "string * (char * unit)",
+                "char",
+                "'a * 'b -> 'b * 'a",
+                "bool * int",
+                "'a * 'b -> 'a",
+                "int",
+                "unit -> unit",
+                "int * 'a -> 'b * int -> int",
+            ]
+            .map(String::from),
+        );
+
+        // Components of a value are generalized.
+        let s = Rc::new(Source::inline(
+            "let (id, n) = (fun x -> x), 1;; id true, id n;; let (a, b) = (fst, 1);; a (1, 2)",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "('a -> 'a) * int",
+                "bool * int",
+                "('a * 'b -> 'a) * int",
+                "int"
+            ]
+            .map(String::from),
+        );
+
+        let s = Rc::new(Source::inline(
+            "let (a, b) = 1;; fst (1, 2, 3);; let f (x, y) = x + y in f (1, true)",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("int", "'a * 'b")).with_loc(loc! {s => 13,14; 1,14}),
+                Error::new(mismatch("int * int * int", "'a * 'b"))
+                    .with_loc(loc! {s => 21,30; 1,22}),
+                Error::new(mismatch("int * bool", "int * int")).with_loc(loc! {s => 59,68; 1,60}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let s = Rc::new(Source::inline(
+            "[];; [1; 2];; let l = 'a' :: ['b'];; [(1, \"a\")] @ [];; [[]; [true]];; \
+             let rec length l = if l = [] then 0 else 1 + length (List.tl l);; \
+             let nil = [];; 1 :: nil, \"a\" :: nil;; List.hd;; fun x -> [x, 1] @ [x, x]",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "'a list",
+                "int list",
+                "char list",
+                "(int * string) list",
+                "bool list list",
+                "'a list -> int",
+                "'a list",
+                "int list * string list",
+                "'a list -> 'a",
+                "int -> (int * int) list",
+            ]
+            .map(String::from),
+        );
+
+        let s = Rc::new(Source::inline(
+            "[1; true];; 1 :: 2;; [1] @ [\"a\"];; List.hd 1",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 4,8; 1,5}),
+                Error::new(mismatch("int", "int list")).with_loc(loc! {s => 17,18; 1,18}),
+                Error::new(mismatch("string list", "int list")).with_loc(loc! {s => 27,32; 1,28}),
+                Error::new(mismatch("int", "'a list")).with_loc(loc! {s => 43,44; 1,44}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let s = Rc::new(Source::inline(
+            "type point = { x : int; y : int };; let p = { y = 2; x = 1 };; p.x;; \
+             fun q -> { q with y = q.x };; type t = { p : point; l : u list } and u = { t : t; o : option2 } \
+             and option2 = { f : int -> int * bool };; fun r -> r.l;; let x = 1 in { x = x; y = x }",
+        ));
+        assert_eq!(
+            types(&s),
+            ["point", "int", "point -> point", "t -> u list", "point",].map(String::from),
+        );
+        let file = infer_code(&s).unwrap();
+        let Stmt::Type(decls) = &file.stmts[4] else {
+            panic!("unexpected {:?}", file.stmts[4]);
+        };
+        let TypeDeclKind::Record(fields) = &decls[2].kind else {
+            panic!("unexpected {:?}", decls[2]);
+        };
+        assert_eq!(
+            fields[0].typ,
+            Some(Type::fun(
+                vec![Type::Int],
+                Type::Tuple(vec![Type::Int, Type::Bool])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_record_error() {
+        let s = Rc::new(Source::inline(
+            "type p = { x : int; y : int } and q = { z : bool };; { x = 1 };; { x = 1; x = 2; y = 3 };; \
+             { x = 1; z = true };; { w = 1 };; (1, 2).x;; { x = true; y = 1 };; \
+             type a = { f : t };; type b = { g : int list list; h : int int };; \
+             type c = { i : int; i : int };; type d = { j : int } and d = { k : int }",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                error!(
+                    "Some record fields are undefined: y",
+                    loc! {s => 53,62; 1,54}
+                ),
+                error!(
+                    "The record field x is defined several times",
+                    loc! {s => 74,75; 1,75}
+                ),
+                error!(
+                    "The record field z belongs to the type q but is mixed here with fields of type p",
+                    loc! {s => 100,101; 1,101}
+                ),
+                error!("Unbound record field w", loc! {s => 115,116; 1,116}),
+                Error::new(mismatch("int * int", "p")).with_loc(loc! {s => 125,131; 1,126}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 142,146; 1,143}),
+                error!("Unbound type constructor t", loc! {s => 173,174; 1,174}),
+                error!(
+                    "The type constructor int expects 0 argument(s), but is here applied to 1 argument(s)",
+                    loc! {s => 213,220; 1,214}
+                ),
+                error!("Two labels are named i", loc! {s => 245,246; 1,246}),
+                error!(
+                    "Multiple definition of the type name d.",
+                    loc! {s => 282,297; 1,283}
+                ),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_ref() {
+        let s = Rc::new(Source::inline(
+            "let r = ref 1;; !r;; r := !r + 1;; incr r;; fun x -> x := [];; ref;; \
+             type c = { mutable n : int; s : string };; let c = { n = 0; s = \"a\" };; c.n <- c.n + 1;; \
+             fun x -> !(List.hd !x) + 1",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "int ref",
+                "int",
+                "unit",
+                "unit",
+                "'a list ref -> unit",
+                "'a -> 'a ref",
+                "c",
+                "unit",
+                "int ref list ref -> int",
+            ]
+            .map(String::from),
+        );
+
+        let s = Rc::new(Source::inline(
+            "type c = { mutable n : int; s : string };; let c = { n = 0; s = \"a\" };; \
+             c.s <- \"b\";; c.n <- true;; !1;; 1 := 2;; decr (ref true)",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                error!("The record field s is not mutable", loc! {s => 72,82; 1,73}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 92,96; 1,93}),
+                Error::new(mismatch("int", "'a ref")).with_loc(loc! {s => 100,101; 1,101}),
+                Error::new(mismatch("int", "'a ref")).with_loc(loc! {s => 104,105; 1,105}),
+                Error::new(mismatch("bool ref", "int ref")).with_loc(loc! {s => 118,128; 1,119}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_array() {
+        let s = Rc::new(Source::inline(
+            "let a = [| 1; 2 |];; a.(0);; a.(1) <- a.(0) + 1;; [||];; let e = [||];; \
+             Array.make 3 'c';; Array.make;; fun a -> Array.length a;; fun a i -> a.(i).(i) = \"\";; \
+             let b = Array.make 2 [] in b",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "int array",
+                "int",
+                "unit",
+                "'a array",
+                "'a array",
+                "char array",
+                "int -> 'a -> 'a array",
+                "'a array -> int",
+                "string array array -> int -> bool",
+                "'a list array",
+            ]
+            .map(String::from),
+        );
+
+        let s = Rc::new(Source::inline(
+            "[| 1; 'a' |];; [1].(0);; [|1|].(true);; [|1|].(0) <- \"\";; Array.length [1]",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("char", "int")).with_loc(loc! {s => 6,9; 1,7}),
+                Error::new(mismatch("int list", "'a array")).with_loc(loc! {s => 15,18; 1,16}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 32,36; 1,33}),
+                Error::new(mismatch("string", "int")).with_loc(loc! {s => 53,55; 1,54}),
+                Error::new(mismatch("int list", "'a array")).with_loc(loc! {s => 71,74; 1,72}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_match() {
+        let s = Rc::new(Source::inline(
+            "type shape = Circle of int | Rect of int * int | Empty;; \
+             fun s -> match s with Circle r -> r | Rect (w, h) -> w * h | Empty -> 0;; \
+             fun l -> match l with [] -> Empty | x :: _ -> x;; \
+             let rec len l = match l with [] -> 0 | _ :: t -> 1 + len t;; \
+             fun p -> match p with (0, c) -> c | (_, 'a') -> 'b' | _ -> 'c';; \
+             match \"s\" with \"t\" -> true | _ -> false;; \
+             fun x -> match x with Rect _ -> [x] | _ -> [];; \
+             fun x -> match x with y -> y",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "shape -> int",
+                "shape list -> shape",
+                "'a list -> int",
+                "int * char -> char",
+                "bool",
+                "shape -> shape list",
+                "'a -> 'a",
+            ]
+            .map(String::from),
+        );
+
+        let s = Rc::new(Source::inline(
+            "type shape = Circle of int | Rect of int * int | Empty;; \
+             match 1 with true -> 1;; match [1] with x :: ['a'] -> x;; \
+             match Empty with Circle _ -> 1 | Rect (1, _) -> 'a';; \
+             fun x -> match x with Rect 1 -> 1 | Empty _ -> 2;; \
+             fun x -> match x with Square -> 1;; match (1, 2) with (x, y, z) -> 1",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(pattern_mismatch("bool", "int")).with_loc(loc! {s => 70,74; 1,71}),
+                Error::new(pattern_mismatch("char list", "int list")).with_loc(loc! {s => 97,107; 1,98}),
+                Error::new(mismatch("char", "int")).with_loc(loc! {s => 163,166; 1,164}),
+                error!(
+                    "The constructor Rect expects 2 argument(s), but is applied here to 1 argument(s)",
+                    loc! {s => 191,197; 1,192}
+                ),
+                error!("Unbound constructor Square", loc! {s => 242,248; 1,243}),
+                Error::new(pattern_mismatch("'a * 'b * 'c", "int * int"))
+                    .with_loc(loc! {s => 274,283; 1,275}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_match_guard() {
+        let s = Rc::new(Source::inline(
+            "fun c -> match c with ('a'..'z' | '_') as d when d <> 'q' -> d | _ -> c;; \
+             fun p -> match p with (x, 0) | (0, x) -> x | (_, _) as q -> fst q;; \
+             fun l -> match l with [x] | [_; x] when x -> 1 | _ -> 0",
+        ));
+        assert_eq!(
+            types(&s),
+            ["char -> char", "int * int -> int", "bool list -> int"].map(String::from),
+        );
+
+        let s = Rc::new(Source::inline(
+            "match 1 with x when x -> 1;; match [1] with [x] | x -> 1;; \
+             match 1 with ('a'..'b') -> 1",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("int", "bool")).with_loc(loc! {s => 20,21; 1,21}),
+                error!(
+                    "The variable x on the left-hand side of this or-pattern has type 'a \
+                     but on the right-hand side it has type 'a list",
+                    loc! {s => 44,51; 1,45}
+                ),
+                Error::new(pattern_mismatch("char", "int")).with_loc(loc! {s => 72,82; 1,73}),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_variant() {
+        let s = Rc::new(Source::inline(
+            "type shape = Circle of int | Rect of int * int | Empty;; Circle 1;; Rect (1, 2);; \
+             let l = [Empty];; fun x -> Circle x;; type t = Leaf | Node of t * int * t;; \
+             Node (Leaf, 1, Node (Leaf, 2, Leaf));; type u = Empty;; Empty;; \
+             type v = Pair of (int * int);; Pair (1, 2)",
+        ));
+        assert_eq!(
+            types(&s),
+            [
+                "shape",
+                "shape",
+                "shape list",
+                "int -> shape",
+                "t",
+                "u",
+                "v",
+            ]
+            .map(String::from),
+        );
+        let file = infer_code(&s).unwrap();
+        let Stmt::Type(decls) = &file.stmts[0] else {
+            panic!("unexpected {:?}", file.stmts[0]);
+        };
+        let TypeDeclKind::Variant(constrs) = &decls[0].kind else {
+            panic!("unexpected {:?}", decls[0]);
+        };
+        assert_eq!(constrs[1].typs, Some(vec![Type::Int, Type::Int]));
+
+        let s = Rc::new(Source::inline(
+            "type shape = Circle of int | Rect of int * int | Empty;; Circle 'a';; Rect (1, true);; \
+             Rect 1;; Rect (1, 2, 3);; Empty 1;; Circle;; Square 1;; type t = A | A",
+        ));
+        assert_eq!(
+            infer_code(&s),
+            Err(vec![
+                Error::new(mismatch("char", "int")).with_loc(loc! {s => 64,67; 1,65}),
+                Error::new(mismatch("bool", "int")).with_loc(loc! {s => 79,83; 1,80}),
+                error!(
+                    "The constructor Rect expects 2 argument(s), but is applied here to 1 argument(s)",
+                    loc! {s => 87,93; 1,88}
+                ),
+                error!(
+                    "The constructor Rect expects 2 argument(s), but is applied here to 3 argument(s)",
+                    loc! {s => 96,110; 1,97}
+                ),
+                error!(
+                    "The constructor Empty expects 0 argument(s), but is applied here to 1 argument(s)",
+                    loc! {s => 113,120; 1,114}
+                ),
+                error!(
+                    "The constructor Circle expects 1 argument(s), but is applied here to 0 argument(s)",
+                    loc! {s => 123,129; 1,124}
+                ),
+                error!("Unbound constructor Square", loc! {s => 132,140; 1,133}),
+                error!("Two constructors are named A", loc! {s => 156,157; 1,157}),
+            ]),
+        );
+    }
+}
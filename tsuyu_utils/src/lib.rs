@@ -11,6 +11,8 @@ mod option;
 pub use option::*;
 mod iter;
 pub use iter::*;
+mod string;
+pub use string::*;
 
 #[macro_export]
 macro_rules! define_with_params_and_init {
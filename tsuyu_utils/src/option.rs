@@ -50,7 +50,6 @@ macro_rules! must {
 
 #[cfg(test)]
 mod must_tests {
-    use crate::*;
     use tsuyu_asserts::assert_eq;
 
     fn ok<T>(v: T) -> Result<T, ()> {
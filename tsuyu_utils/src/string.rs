@@ -0,0 +1,57 @@
+// Copyright (c) 2022 zakuro <z@kuro.red>. All rights reserved.
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// Escapes the bytes like OCaml's `String.escaped`.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'"' => s.push_str("\\\""),
+            b'\\' => s.push_str("\\\\"),
+            b'\n' => s.push_str("\\n"),
+            b'\t' => s.push_str("\\t"),
+            b'\r' => s.push_str("\\r"),
+            b'\x08' => s.push_str("\\b"),
+            b' '..=b'~' => s.push(b as char),
+            _ => s.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    s
+}
+
+/// Escapes the byte like OCaml's `Char.escaped`.
+pub fn escape_char(c: u8) -> String {
+    match c {
+        b'\'' => "\\'".to_string(),
+        b'"' => "\"".to_string(),
+        _ => escape_bytes(&[c]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use tsuyu_asserts::assert_eq;
+
+    #[test]
+    fn test_escape_bytes() {
+        assert_eq!(escape_bytes(b"abc 'x'"), "abc 'x'");
+        assert_eq!(escape_bytes(b"\"\\\n\t\r\x08"), "\\\"\\\\\\n\\t\\r\\b");
+        assert_eq!(
+            escape_bytes("\0\x7fあ".as_bytes()),
+            "\\000\\127\\227\\129\\130"
+        );
+    }
+
+    #[test]
+    fn test_escape_char() {
+        assert_eq!(escape_char(b'a'), "a");
+        assert_eq!(escape_char(b'\''), "\\'");
+        assert_eq!(escape_char(b'"'), "\"");
+        assert_eq!(escape_char(b'\n'), "\\n");
+        assert_eq!(escape_char(255), "\\255");
+    }
+}
@@ -1,73 +1,674 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+use tsuyu_utils::{escape_bytes, escape_char};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Type {
+    /// 0 in a word.
+    Unit,
     Int,
+    Bool,
+    /// Pointer to a block of the length in bytes followed by the bytes and a null terminator.
+    String,
+    /// Byte in a word.
+    Char,
+    /// Type of a polymorphic value. It is represented in a word like other types.
+    Poly,
+    /// Pointer to a closure block. See `Expr::Apply`.
+    Fun,
+    /// Pointer to a block of the elements.
+    Tuple(Vec<Type>),
+    /// 0 for the empty list. Otherwise a pointer to a block of the head and the tail.
+    List(Box<Type>),
+    /// Pointer to a block of the contents. It is updated in place.
+    Ref(Box<Type>),
+    /// Pointer to a block of the length followed by the elements. See `Expr::Load`.
+    Array(Box<Type>),
+    /// Type defined in `IR::types` at the index.
+    Defined(usize),
+}
+
+/// Type declared in the source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TypeDef {
+    pub name: String,
+    pub kind: TypeDefKind,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TypeDefKind {
+    /// Pointer to a block of the fields in the order of the declaration.
+    Record(Vec<(String, Type)>),
+    /// Constructors and their argument types. Constant constructors are immediates numbered
+    /// in the order of the declaration. Others are pointers to blocks of the tag followed by
+    /// the arguments, where tags are numbered among the non-constant constructors.
+    Variant(Vec<(String, Vec<Type>)>),
+}
+
+impl TypeDef {
+    /// Index of the word holding the field in the block.
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        match &self.kind {
+            TypeDefKind::Record(fields) => fields.iter().position(|(n, _)| n == name),
+            TypeDefKind::Variant(_) => None,
+        }
+    }
+
+    /// Number of the constructor among the constant or the non-constant ones.
+    pub fn constr_tag(&self, name: &str) -> Option<usize> {
+        match &self.kind {
+            TypeDefKind::Record(_) => None,
+            TypeDefKind::Variant(constrs) => {
+                let (_, args) = constrs.iter().find(|(n, _)| n == name)?;
+                Some(
+                    constrs
+                        .iter()
+                        .take_while(|(n, _)| n != name)
+                        .filter(|(_, a)| a.is_empty() == args.is_empty())
+                        .count(),
+                )
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Value {
+    Unit,
+    /// The empty list.
+    Nil,
     Int(i64),
+    Bool(bool),
+    /// Constant string. It must not be modified.
+    String(Vec<u8>),
+    Char(u8),
 }
 
 impl Value {
     pub fn typ(&self) -> Type {
         match self {
+            Value::Unit => Type::Unit,
+            Value::Nil => Type::List(Box::new(Type::Poly)),
             Value::Int(_) => Type::Int,
+            Value::Bool(_) => Type::Bool,
+            Value::String(_) => Type::String,
+            Value::Char(_) => Type::Char,
         }
     }
 }
 
+/// Source position reported by runtime errors.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    /// Raises `Division_by_zero` at the location if the divisor is zero.
+    Div(Location),
+    /// Raises `Division_by_zero` at the location if the divisor is zero.
+    Mod(Location),
+    /// Comparisons of words which result in a bool.
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Expr {
     Immediate(Value),
+    /// Reads the local slot.
+    Local(usize),
+    /// Stores the value to the local slot and evaluates the body.
+    Let(usize, Box<Expr>, Box<Expr>),
+    /// Reads the global variable.
+    Global(String),
+    /// Calls the function with the arguments. The type is the return type.
+    Call(String, Vec<Expr>, Type),
+    /// Address of the function.
+    FuncAddr(String),
+    /// Applies the closure to the arguments.
+    /// A closure is a block of the code address, the number of parameters and captured values.
+    /// The code is called with the arguments followed by the closure itself if the number of
+    /// arguments matches. Otherwise the runtime makes a partial application or applies the result
+    /// to the rest of the arguments.
+    Apply(Box<Expr>, Vec<Expr>),
+    /// Allocates a block of words initialized with the values.
+    Alloc(Vec<Expr>),
+    /// Reads the word of the block at the index.
+    Field(Box<Expr>, usize),
+    /// Writes the word of the block at the index and evaluates to 0. Blocks of refs and records
+    /// with mutable fields are updated in place.
+    SetField(Box<Expr>, usize, Box<Expr>),
+    /// Reads the element of the array at the index. The index is checked against the length
+    /// unless the codegen omits bounds checks. `Invalid_argument` is raised at the location if it
+    /// is out of bounds.
+    Load(Box<Expr>, Box<Expr>, Location),
+    /// Writes the element of the array at the index and evaluates to 0. The index is checked like
+    /// `Load`.
+    Store(Box<Expr>, Box<Expr>, Box<Expr>, Location),
+    /// Evaluates the first expression for its effect and then the second.
+    Seq(Box<Expr>, Box<Expr>),
+    /// Evaluates only one of the branches by the condition.
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    /// Evaluates the branch at the index found in the table at the value. The value must be in
+    /// the bounds of the table.
+    Switch(Box<Expr>, Vec<usize>, Vec<Expr>),
+    /// Raises `Match_failure` at the location.
+    MatchFailure(Location),
+    UnOp(UnOp, Box<Expr>),
+    BinOp(BinOp, Box<Expr>, Box<Expr>),
+    /// Compares the values of the type by their contents like OCaml's `compare`. Results in -1,
+    /// 0 or 1. Raises an error at the location if they contain functions.
+    Compare(Type, Box<Expr>, Box<Expr>, Location),
 }
 
 impl Expr {
-    pub fn typ(&self) -> Type {
-        match self {
-            Expr::Immediate(v) => v.typ(),
-        }
+    pub fn let_(local: usize, value: Expr, body: Expr) -> Self {
+        Expr::Let(local, Box::new(value), Box::new(body))
+    }
+
+    pub fn if_(cond: Expr, then: Expr, els: Expr) -> Self {
+        Expr::If(Box::new(cond), Box::new(then), Box::new(els))
+    }
+
+    pub fn switch(value: Expr, table: Vec<usize>, branches: Vec<Expr>) -> Self {
+        Expr::Switch(Box::new(value), table, branches)
+    }
+
+    pub fn field(block: Expr, index: usize) -> Self {
+        Expr::Field(Box::new(block), index)
+    }
+
+    pub fn set_field(block: Expr, index: usize, value: Expr) -> Self {
+        Expr::SetField(Box::new(block), index, Box::new(value))
+    }
+
+    pub fn load(array: Expr, index: Expr, loc: Location) -> Self {
+        Expr::Load(Box::new(array), Box::new(index), loc)
+    }
+
+    pub fn store(array: Expr, index: Expr, value: Expr, loc: Location) -> Self {
+        Expr::Store(Box::new(array), Box::new(index), Box::new(value), loc)
+    }
+
+    pub fn seq(first: Expr, second: Expr) -> Self {
+        Expr::Seq(Box::new(first), Box::new(second))
+    }
+
+    pub fn unop(op: UnOp, expr: Expr) -> Self {
+        Expr::UnOp(op, Box::new(expr))
+    }
+
+    pub fn binop(op: BinOp, lhs: Expr, rhs: Expr) -> Self {
+        Expr::BinOp(op, Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn compare(typ: Type, lhs: Expr, rhs: Expr, loc: Location) -> Self {
+        Expr::Compare(typ, Box::new(lhs), Box::new(rhs), loc)
     }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Stmt {
-    Dump(Expr),
+    /// Prints the value of the type.
+    Dump(Type, Expr),
+    /// Evaluates the expression and discards the value.
+    Eval(Expr),
+    SetGlobal(String, Expr),
     Return(Expr),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Function {
+    /// Parameters are stored to the first local slots on entry.
+    pub params: Vec<Type>,
+    pub ret: Type,
+    /// Number of local slots used in the body including parameters.
+    pub locals: usize,
     pub body: Vec<Stmt>,
 }
 
 impl Function {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(params: Vec<Type>, ret: Type) -> Self {
+        Self {
+            locals: params.len(),
+            params,
+            ret,
+            body: Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
-pub struct IR<'a> {
-    pub functions: HashMap<&'a str, Function>,
+pub struct IR {
+    pub types: Vec<TypeDef>,
+    pub globals: BTreeSet<String>,
+    pub functions: BTreeMap<String, Function>,
 }
 
-impl<'a> IR<'a> {
+impl IR {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn create_function(&mut self, name: &'a str) -> Option<&mut Function> {
-        self.functions.insert(name, Function::new());
-        self.functions.get_mut(name)
+    pub fn create_function(
+        &mut self,
+        name: impl Into<String>,
+        params: Vec<Type>,
+        ret: Type,
+    ) -> Option<&mut Function> {
+        let name = name.into();
+        self.functions
+            .insert(name.clone(), Function::new(params, ret));
+        self.functions.get_mut(&name)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Unit => write!(f, "unit"),
+            Type::Int => write!(f, "int"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Char => write!(f, "char"),
+            Type::Poly => write!(f, "poly"),
+            Type::Fun => write!(f, "fun"),
+            Type::Tuple(types) => {
+                let types: Vec<_> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", types.join(" * "))
+            }
+            Type::List(elem) => write!(f, "{} list", elem),
+            Type::Ref(elem) => write!(f, "{} ref", elem),
+            Type::Array(elem) => write!(f, "{} array", elem),
+            Type::Defined(i) => write!(f, "#{}", i),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Nil => write!(f, "[]"),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "\"{}\"", escape_bytes(s)),
+            Value::Char(c) => write!(f, "'{}'", escape_char(*c)),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div(_) => "/",
+            BinOp::Mod(_) => "mod",
+            BinOp::Eq => "=",
+            BinOp::Ne => "<>",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+        })
+    }
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "not ",
+        })
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Immediate(v) => write!(f, "{}", v),
+            Expr::Local(i) => write!(f, "${}", i),
+            Expr::Let(i, value, body) => write!(f, "(let ${} = {} in {})", i, value, body),
+            Expr::Global(name) => write!(f, "@{}", name),
+            Expr::Call(name, args, _) => {
+                write!(f, "(call {}", name)?;
+                for arg in args {
+                    write!(f, " {}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expr::FuncAddr(name) => write!(f, "&{}", name),
+            Expr::Apply(closure, args) => {
+                write!(f, "(apply {}", closure)?;
+                for arg in args {
+                    write!(f, " {}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Alloc(values) => {
+                write!(f, "(alloc")?;
+                for value in values {
+                    write!(f, " {}", value)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Field(block, i) => write!(f, "{}[{}]", block, i),
+            Expr::SetField(block, i, value) => write!(f, "({}[{}] <- {})", block, i, value),
+            Expr::Load(array, index, _) => write!(f, "{}.({})", array, index),
+            Expr::Store(array, index, value, _) => {
+                write!(f, "({}.({}) <- {})", array, index, value)
+            }
+            Expr::Seq(first, second) => write!(f, "({}; {})", first, second),
+            Expr::If(cond, then, els) => write!(f, "(if {} then {} else {})", cond, then, els),
+            Expr::Switch(value, table, branches) => {
+                let table: Vec<_> = table.iter().map(|i| i.to_string()).collect();
+                write!(f, "(switch {} [{}]", value, table.join(" "))?;
+                for branch in branches {
+                    write!(f, " {}", branch)?;
+                }
+                write!(f, ")")
+            }
+            Expr::MatchFailure(_) => write!(f, "match_failure"),
+            Expr::UnOp(op, expr) => write!(f, "({}{})", op, expr),
+            Expr::BinOp(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            Expr::Compare(typ, lhs, rhs, _) => write!(f, "(compare {} {} {})", typ, lhs, rhs),
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Dump(typ, expr) => write!(f, "dump {} {}", typ, expr),
+            Stmt::Eval(expr) => write!(f, "eval {}", expr),
+            Stmt::SetGlobal(name, expr) => write!(f, "set @{} = {}", name, expr),
+            Stmt::Return(expr) => write!(f, "return {}", expr),
+        }
     }
 }
 
-#[cfg(tests)]
+impl fmt::Display for TypeDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TypeDefKind::Record(fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, typ)| format!("{}: {}", name, typ))
+                    .collect();
+                write!(f, "{} {{{}}}", self.name, fields.join("; "))
+            }
+            TypeDefKind::Variant(constrs) => {
+                let constrs: Vec<_> = constrs
+                    .iter()
+                    .map(|(name, args)| {
+                        if args.is_empty() {
+                            return name.clone();
+                        }
+                        let args: Vec<_> = args.iter().map(|t| t.to_string()).collect();
+                        format!("{} of {}", name, args.join(" * "))
+                    })
+                    .collect();
+                write!(f, "{} ({})", self.name, constrs.join(" | "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for IR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, def) in self.types.iter().enumerate() {
+            writeln!(f, "type #{} = {}", i, def)?;
+        }
+        if !self.types.is_empty() {
+            writeln!(f)?;
+        }
+        for name in &self.globals {
+            writeln!(f, "global @{}", name)?;
+        }
+        if !self.globals.is_empty() {
+            writeln!(f)?;
+        }
+        for (i, (name, func)) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let params: Vec<_> = func.params.iter().map(|t| t.to_string()).collect();
+            writeln!(
+                f,
+                "function {}({}): {} {{",
+                name,
+                params.join(", "),
+                func.ret
+            )?;
+            for stmt in &func.body {
+                writeln!(f, "    {}", stmt)?;
+            }
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use crate::*;
-    use tsuyu_asserts::*;
+    use tsuyu_asserts::{assert_eq, *};
 
     fn_test_data_traits!(IR);
+
+    #[test]
+    fn test_fmt() {
+        let mut ir = IR::new();
+        ir.types.push(TypeDef {
+            name: "t".to_string(),
+            kind: TypeDefKind::Record(vec![
+                ("n".to_string(), Type::Int),
+                ("p".to_string(), Type::Defined(0)),
+            ]),
+        });
+        assert_eq!(ir.types[0].field_index("q"), None);
+        let p = ir.types[0].field_index("p").unwrap();
+        ir.types.push(TypeDef {
+            name: "shape".to_string(),
+            kind: TypeDefKind::Variant(vec![
+                ("A".to_string(), vec![]),
+                ("B".to_string(), vec![Type::Int]),
+                ("C".to_string(), vec![]),
+                ("D".to_string(), vec![Type::Int, Type::Defined(1)]),
+            ]),
+        });
+        let tags: Vec<_> = ["A", "B", "C", "D", "E"]
+            .iter()
+            .map(|name| ir.types[1].constr_tag(name))
+            .collect();
+        assert_eq!(tags, [Some(0), Some(0), Some(1), Some(1), None]);
+        assert_eq!(ir.types[0].constr_tag("n"), None);
+        let main = ir.create_function("main", vec![], Type::Int).unwrap();
+        main.body.push(Stmt::Dump(
+            Type::Int,
+            Expr::binop(
+                BinOp::Div(Location::default()),
+                Expr::binop(
+                    BinOp::Add,
+                    Expr::Immediate(Value::Int(40)),
+                    Expr::Immediate(Value::Int(2)),
+                ),
+                Expr::Immediate(Value::Int(1)),
+            ),
+        ));
+        main.body.push(Stmt::Return(Expr::Immediate(Value::Int(0))));
+        main.body.push(Stmt::Dump(
+            Type::Int,
+            Expr::let_(
+                0,
+                Expr::Immediate(Value::Int(1)),
+                Expr::binop(BinOp::Mul, Expr::Local(0), Expr::Local(0)),
+            ),
+        ));
+        main.body.push(Stmt::Dump(
+            Type::Bool,
+            Expr::if_(
+                Expr::binop(BinOp::Le, Expr::Local(0), Expr::Immediate(Value::Int(1))),
+                Expr::unop(UnOp::Not, Expr::Immediate(Value::Bool(true))),
+                Expr::binop(BinOp::Ne, Expr::Local(0), Expr::Local(1)),
+            ),
+        ));
+        main.body.push(Stmt::Dump(
+            Type::String,
+            Expr::Immediate(Value::String(b"a\n\xff".to_vec())),
+        ));
+        main.body
+            .push(Stmt::Dump(Type::Char, Expr::Immediate(Value::Char(b'\''))));
+        main.body
+            .push(Stmt::Dump(Type::Unit, Expr::Immediate(Value::Unit)));
+        main.body.push(Stmt::Dump(
+            Type::List(Box::new(Type::Tuple(vec![Type::Int, Type::String]))),
+            Expr::Alloc(vec![
+                Expr::Alloc(vec![
+                    Expr::Immediate(Value::Int(1)),
+                    Expr::Immediate(Value::String(b"a".to_vec())),
+                ]),
+                Expr::Immediate(Value::Nil),
+            ]),
+        ));
+        main.body
+            .push(Stmt::Dump(Type::Defined(0), Expr::field(Expr::Local(0), p)));
+        main.body.push(Stmt::Dump(
+            Type::Ref(Box::new(Type::Int)),
+            Expr::Alloc(vec![Expr::Immediate(Value::Int(1))]),
+        ));
+        main.body.push(Stmt::Dump(
+            Type::Unit,
+            Expr::store(
+                Expr::Local(0),
+                Expr::Immediate(Value::Int(0)),
+                Expr::load(Expr::Local(1), Expr::Local(2), Location::default()),
+                Location::default(),
+            ),
+        ));
+        main.body.push(Stmt::SetGlobal(
+            "x".to_string(),
+            Expr::Call(
+                "f".to_string(),
+                vec![
+                    Expr::Immediate(Value::Int(1)),
+                    Expr::Global("x".to_string()),
+                ],
+                Type::Int,
+            ),
+        ));
+        main.body.push(Stmt::Eval(Expr::Global("x".to_string())));
+        main.body.push(Stmt::Dump(
+            Type::Fun,
+            Expr::let_(
+                0,
+                Expr::Alloc(vec![
+                    Expr::FuncAddr("f".to_string()),
+                    Expr::Immediate(Value::Int(2)),
+                    Expr::Immediate(Value::Int(0)),
+                ]),
+                Expr::seq(
+                    Expr::set_field(Expr::Local(0), 2, Expr::Local(0)),
+                    Expr::Apply(
+                        Box::new(Expr::field(Expr::Local(0), 2)),
+                        vec![Expr::Immediate(Value::Int(1))],
+                    ),
+                ),
+            ),
+        ));
+        main.body.push(Stmt::Dump(
+            Type::Bool,
+            Expr::binop(
+                BinOp::Eq,
+                Expr::compare(
+                    Type::Fun,
+                    Expr::Local(0),
+                    Expr::Local(1),
+                    Location::default(),
+                ),
+                Expr::Immediate(Value::Int(0)),
+            ),
+        ));
+        main.body.push(Stmt::Dump(
+            Type::Int,
+            Expr::switch(
+                Expr::Local(0),
+                vec![1, 0, 1],
+                vec![
+                    Expr::Immediate(Value::Int(1)),
+                    Expr::MatchFailure(Location::default()),
+                ],
+            ),
+        ));
+        main.body.push(Stmt::Return(Expr::Immediate(Value::Int(0))));
+        ir.globals.insert("x".to_string());
+        let f = ir
+            .create_function("f", vec![Type::Int, Type::Poly], Type::Int)
+            .unwrap();
+        f.body.push(Stmt::Return(Expr::binop(
+            BinOp::Add,
+            Expr::Local(0),
+            Expr::Local(1),
+        )));
+        assert_eq!(
+            ir.to_string(),
+            concat!(
+                "type #0 = t {n: int; p: #0}\n",
+                "type #1 = shape (A | B of int | C | D of int * #1)\n",
+                "\n",
+                "global @x\n",
+                "\n",
+                "function f(int, poly): int {\n",
+                "    return ($0 + $1)\n",
+                "}\n",
+                "\n",
+                "function main(): int {\n",
+                "    dump int ((40 + 2) / 1)\n",
+                "    return 0\n",
+                "    dump int (let $0 = 1 in ($0 * $0))\n",
+                "    dump bool (if ($0 <= 1) then (not true) else ($0 <> $1))\n",
+                "    dump string \"a\\n\\255\"\n",
+                "    dump char '\\''\n",
+                "    dump unit ()\n",
+                "    dump (int * string) list (alloc (alloc 1 \"a\") [])\n",
+                "    dump #0 $0[1]\n",
+                "    dump int ref (alloc 1)\n",
+                "    dump unit ($0.(0) <- $1.($2))\n",
+                "    set @x = (call f 1 @x)\n",
+                "    eval @x\n",
+                "    dump fun (let $0 = (alloc &f 2 0) in (($0[2] <- $0); (apply $0[2] 1)))\n",
+                "    dump bool ((compare fun $0 $1) = 0)\n",
+                "    dump int (switch $0 [1 0 1] 1 match_failure)\n",
+                "    return 0\n",
+                "}\n",
+            ),
+        );
+    }
 }
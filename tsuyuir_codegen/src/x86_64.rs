@@ -4,26 +4,124 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use tsuyuir::{Expr, Function, Stmt, Type, Value, IR};
-use std::{collections::HashMap, fmt::Write};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+use tsuyuir::{BinOp, Expr, Function, Location, Stmt, Type, TypeDef, TypeDefKind, UnOp, Value, IR};
 
-pub fn compile(ir: &IR<'_>) -> String {
-    let mut gen = Gen::default();
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Omit bounds checks of array accesses like `ocamlopt -unsafe`.
+    pub no_bounds_check: bool,
+}
+
+pub fn compile(ir: &IR, options: &Options) -> String {
+    let mut gen = Gen {
+        no_bounds_check: options.no_bounds_check,
+        ..Gen::default()
+    };
     gen.gen(ir)
 }
 
+// Integer argument registers of System V AMD64 ABI.
+const ARG_REGS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+fn local(i: usize) -> String {
+    format!("QWORD PTR [rbp - {}]", (i + 1) * 8)
+}
+
+// Describes the type for `tsuyu_dump` and `tsuyu_compare` of the runtime.
+fn shape(typ: &Type, out: &mut String) {
+    match typ {
+        Type::Unit => out.push('u'),
+        Type::Int => out.push('i'),
+        Type::Bool => out.push('b'),
+        Type::String => out.push('s'),
+        Type::Char => out.push('c'),
+        Type::Poly => out.push('p'),
+        Type::Fun => out.push('f'),
+        Type::Tuple(types) => {
+            out.push('(');
+            for typ in types {
+                shape(typ, out);
+            }
+            out.push(')');
+        }
+        Type::List(elem) => {
+            out.push('l');
+            shape(elem, out);
+        }
+        Type::Ref(elem) => {
+            out.push('r');
+            shape(elem, out);
+        }
+        Type::Array(elem) => {
+            out.push('a');
+            shape(elem, out);
+        }
+        // Refers to the entry of `tsuyu_types` so that recursive types have finite shapes.
+        Type::Defined(i) => {
+            write!(out, "t{}.", i).unwrap();
+        }
+    }
+}
+
+// Describes the type definition for the `tsuyu_types` table of the runtime.
+fn type_shape(def: &TypeDef) -> String {
+    match &def.kind {
+        TypeDefKind::Record(fields) => {
+            let mut out = String::from("{");
+            for (i, (name, typ)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(';');
+                }
+                write!(out, "{}:", name).unwrap();
+                shape(typ, &mut out);
+            }
+            out.push('}');
+            out
+        }
+        TypeDefKind::Variant(constrs) => {
+            let mut out = String::from("<");
+            for (i, (name, args)) in constrs.iter().enumerate() {
+                if i > 0 {
+                    out.push('|');
+                }
+                out.push_str(name);
+                if !args.is_empty() {
+                    out.push(':');
+                    for typ in args {
+                        shape(typ, &mut out);
+                    }
+                }
+            }
+            out.push('>');
+            out
+        }
+    }
+}
+
 #[derive(Default)]
 struct Gen {
     out_head: String,
     out_lc: String,
     out: String,
     lc_str_count: usize,
+    label_count: usize,
     indent_size: usize,
+    // Number of 8 bytes values pushed on the stack. Used to align rsp at call.
+    depth: usize,
+    // Number of parameters of the current function passed on the stack.
+    stack_params: usize,
+    no_bounds_check: bool,
 }
 
 impl Gen {
-    fn gen(&mut self, ir: &IR<'_>) -> String {
+    fn gen(&mut self, ir: &IR) -> String {
         self.out_head = ".intel_syntax noprefix\n".to_string();
+        self.types(&ir.types);
+        self.globals(&ir.globals);
         self.functions(&ir.functions);
 
         let outs = [&self.out_head, &self.out_lc, &self.out];
@@ -46,6 +144,27 @@ impl Gen {
         self.out.push('\n');
     }
 
+    fn label(&mut self, label: &str) {
+        self.unindent();
+        self.writeln(&format!("{}:", label));
+        self.indent();
+    }
+
+    fn new_label(&mut self) -> String {
+        self.label_count += 1;
+        format!(".L.{}", self.label_count)
+    }
+
+    fn push(&mut self, reg: &str) {
+        self.writeln(&format!("push {}", reg));
+        self.depth += 1;
+    }
+
+    fn pop(&mut self, reg: &str) {
+        self.writeln(&format!("pop {}", reg));
+        self.depth -= 1;
+    }
+
     fn indent(&mut self) {
         self.indent_size += 1;
     }
@@ -54,24 +173,84 @@ impl Gen {
         self.indent_size -= 1;
     }
 
+    // Writes the address of a null-terminated string for C functions.
     fn string_constant(&mut self, value: &str) {
+        let label = self.c_string(value);
+        self.write(&format!("[rip + {}]", &label));
+    }
+
+    // Writes a null-terminated string and returns its label.
+    fn c_string(&mut self, value: &str) -> String {
+        let label = format!(".LC.str.{}", self.lc_str_count);
+        self.lc_str_count += 1;
+        writeln!(&mut self.out_lc, ".section .rodata\n{}:", label).unwrap();
+        self.bytes_data(value.as_bytes());
+        self.out_lc.push_str("\t.byte 0\n");
+        label
+    }
+
+    // Writes the label of a string value. See `Type::String`.
+    fn string_value(&mut self, value: &[u8]) -> String {
         let label = format!(".LC.str.{}", self.lc_str_count);
         self.lc_str_count += 1;
-        write!(
+        writeln!(
             &mut self.out_lc,
-            concat!(
-                ".section .rodata\n",
-                "{label}:\n",
-                "\t.string \"{value}\"\n",
-            ),
-            label = label,
-            value = value.replace('\n', "\\n").replace('\"', "\\\""),
+            ".section .rodata\n.p2align 3\n{}:\n\t.quad {}",
+            label,
+            value.len()
         )
         .unwrap();
-        self.write(&format!("[rip + {}]", &label));
+        self.bytes_data(value);
+        self.out_lc.push_str("\t.byte 0\n");
+        label
+    }
+
+    // Printable characters are written in `.ascii` and others in `.byte` to keep bytes exact.
+    fn bytes_data(&mut self, bytes: &[u8]) {
+        let is_plain = |b: &u8| matches!(b, b' '..=b'~') && !matches!(b, b'"' | b'\\');
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let plain = rest.iter().take_while(|b| is_plain(b)).count();
+            let (data, next) = if plain > 0 {
+                let (data, next) = rest.split_at(plain);
+                let data = std::str::from_utf8(data).unwrap();
+                (format!("\t.ascii \"{}\"\n", data), next)
+            } else {
+                let special = rest.iter().take_while(|b| !is_plain(b)).count();
+                let (data, next) = rest.split_at(special);
+                let data: Vec<_> = data.iter().map(|b| b.to_string()).collect();
+                (format!("\t.byte {}\n", data.join(", ")), next)
+            };
+            self.out_lc.push_str(&data);
+            rest = next;
+        }
+    }
+
+    // The runtime refers to the table even if no types are defined.
+    fn types(&mut self, types: &[TypeDef]) {
+        self.writeln(".section .data.rel.ro");
+        self.writeln(".globl tsuyu_types");
+        self.writeln(".p2align 3");
+        self.writeln("tsuyu_types:");
+        for def in types {
+            let label = self.c_string(&type_shape(def));
+            self.writeln(&format!(".quad {}", label));
+        }
     }
 
-    fn functions<'a>(&mut self, funcs: &'a HashMap<&str, Function>) {
+    fn globals(&mut self, globals: &BTreeSet<String>) {
+        if globals.is_empty() {
+            return;
+        }
+        self.writeln(".section .bss");
+        for name in globals {
+            self.writeln(".p2align 3");
+            self.writeln(&format!("{}:", name));
+            self.writeln(".zero 8");
+        }
+    }
+
+    fn functions(&mut self, funcs: &BTreeMap<String, Function>) {
         self.writeln(".section .text");
         for name in funcs.keys() {
             self.writeln(&format!(".globl {}", name));
@@ -79,9 +258,29 @@ impl Gen {
         for (name, func) in funcs {
             self.writeln(&format!("{}:", name));
             self.indent();
+            self.stack_params = func.params.len().saturating_sub(ARG_REGS.len());
 
             self.writeln("push rbp");
             self.writeln("mov rbp, rsp");
+            if func.locals > 0 {
+                // Keeps rsp 16 bytes aligned.
+                self.writeln(&format!(
+                    "sub rsp, {}",
+                    (func.locals * 8).next_multiple_of(16)
+                ));
+            }
+
+            // Arguments after the sixth are passed on the stack above the return address.
+            for i in 0..func.params.len() {
+                match ARG_REGS.get(i) {
+                    Some(reg) => self.writeln(&format!("mov {}, {}", local(i), reg)),
+                    None => {
+                        let offset = 16 + (i - ARG_REGS.len()) * 8;
+                        self.writeln(&format!("mov rax, QWORD PTR [rbp + {}]", offset));
+                        self.writeln(&format!("mov {}, rax", local(i)));
+                    }
+                }
+            }
 
             for stmt in &func.body {
                 self.stmt(stmt);
@@ -93,32 +292,467 @@ impl Gen {
 
     fn stmt(&mut self, stmt: &Stmt) {
         match stmt {
-            Stmt::Return(expr) => {
+            Stmt::Return(expr) => self.tail(expr),
+            Stmt::Dump(typ @ (Type::String | Type::Char), expr) => {
                 self.expr(expr);
-                self.writeln("leave");
-                self.writeln("ret")
+                self.writeln("mov rdi, rax");
+                self.call_aligned(match typ {
+                    Type::String => "tsuyu_dump_string",
+                    _ => "tsuyu_dump_char",
+                });
             }
-            Stmt::Dump(expr) => {
+            // Values in blocks are printed by the runtime following the shape of the type.
+            Stmt::Dump(
+                typ @ (Type::Tuple(_)
+                | Type::List(_)
+                | Type::Ref(_)
+                | Type::Array(_)
+                | Type::Defined(_)),
+                expr,
+            ) => {
                 self.expr(expr);
-                self.writeln("mov rsi, rax");
-                self.write("lea rdi, ");
-                self.string_constant(&format!(
-                    "{}\n",
-                    match expr.typ() {
-                        Type::Int => "%lld",
+                self.writeln("mov rdi, rax");
+                let mut desc = String::new();
+                shape(typ, &mut desc);
+                self.write("lea rsi, ");
+                self.string_constant(&desc);
+                self.writeln("");
+                self.call_aligned("tsuyu_dump");
+            }
+            Stmt::Dump(typ, expr) => {
+                self.expr(expr);
+                let format = match typ {
+                    Type::Int => {
+                        self.writeln("mov rsi, rax");
+                        "%lld\n"
                     }
-                ));
+                    Type::Bool => {
+                        self.write("lea rsi, ");
+                        self.string_constant("true");
+                        self.writeln("");
+                        self.write("lea rdx, ");
+                        self.string_constant("false");
+                        self.writeln("");
+                        self.writeln("test rax, rax");
+                        self.writeln("cmove rsi, rdx");
+                        "%s\n"
+                    }
+                    Type::Unit => "()\n",
+                    // Values of polymorphic types cannot be inspected like OCaml's toplevel.
+                    Type::Poly => "<poly>\n",
+                    Type::Fun => "<fun>\n",
+                    Type::String
+                    | Type::Char
+                    | Type::Tuple(_)
+                    | Type::List(_)
+                    | Type::Ref(_)
+                    | Type::Array(_)
+                    | Type::Defined(_) => unreachable!(),
+                };
+                self.write("lea rdi, ");
+                self.string_constant(format);
                 self.writeln("");
                 self.writeln("call printf@PLT")
             }
+            Stmt::Eval(expr) => self.expr(expr),
+            Stmt::SetGlobal(name, expr) => {
+                self.expr(expr);
+                self.writeln(&format!("mov QWORD PTR [rip + {}], rax", name));
+            }
+        }
+    }
+
+    // Never returns. So rsp can be aligned without restoring it.
+    fn raise(&mut self, func: &str, loc: &Location) {
+        self.write("lea rdi, ");
+        self.string_constant(&loc.to_string());
+        self.writeln("");
+        self.writeln("and rsp, -16");
+        self.writeln(&format!("call {}", func));
+    }
+
+    // Raises `Invalid_argument` unless the index in rdi is in the bounds of the array in rax.
+    fn bounds_check(&mut self, loc: &Location) {
+        if self.no_bounds_check {
+            return;
+        }
+        let in_bounds = self.new_label();
+        // Negative indices are greater than any length as unsigned.
+        self.writeln("cmp rdi, QWORD PTR [rax]");
+        self.writeln(&format!("jb {}", in_bounds));
+        self.raise("tsuyu_raise_index_out_of_bounds", loc);
+        self.label(&in_bounds);
+    }
+
+    // Jumps to the label of the branch found in the table at the value and returns the labels
+    // of the branches. The table holds offsets of the labels from itself so that it needs no
+    // relocations.
+    fn switch(&mut self, value: &Expr, table: &[usize], branches: usize) -> Vec<String> {
+        let table_label = self.new_label();
+        let labels: Vec<_> = (0..branches).map(|_| self.new_label()).collect();
+        writeln!(
+            &mut self.out_lc,
+            ".section .rodata\n.p2align 2\n{}:",
+            table_label
+        )
+        .unwrap();
+        for &i in table {
+            writeln!(&mut self.out_lc, "\t.long {} - {}", labels[i], table_label).unwrap();
         }
+        self.expr(value);
+        self.writeln(&format!("lea rdi, [rip + {}]", table_label));
+        self.writeln("movsxd rax, DWORD PTR [rdi + rax * 4]");
+        self.writeln("add rax, rdi");
+        self.writeln("jmp rax");
+        labels
     }
 
     fn expr(&mut self, expr: &Expr) {
         match expr {
             Expr::Immediate(value) => match value {
+                Value::Unit | Value::Nil => self.writeln("xor eax, eax"),
                 Value::Int(i) => self.writeln(&format!("mov rax, {}", i)),
+                Value::Bool(b) => self.writeln(&format!("mov rax, {}", *b as i64)),
+                Value::Char(c) => self.writeln(&format!("mov rax, {}", c)),
+                Value::String(s) => {
+                    let label = self.string_value(s);
+                    self.writeln(&format!("lea rax, [rip + {}]", label));
+                }
             },
+            Expr::Local(i) => self.writeln(&format!("mov rax, {}", local(*i))),
+            Expr::Let(i, value, body) => {
+                self.expr(value);
+                self.writeln(&format!("mov {}, rax", local(*i)));
+                self.expr(body);
+            }
+            Expr::Global(name) => self.writeln(&format!("mov rax, QWORD PTR [rip + {}]", name)),
+            Expr::Call(name, args, _) => self.call(name, args),
+            Expr::FuncAddr(name) => self.writeln(&format!("lea rax, [rip + {}]", name)),
+            Expr::Apply(closure, args) => self.apply(closure, args),
+            Expr::Alloc(values) => {
+                for value in values.iter().rev() {
+                    self.expr(value);
+                    self.push("rax");
+                }
+                self.writeln(&format!("mov rdi, {}", values.len()));
+                self.call_aligned("tsuyu_alloc");
+                for i in 0..values.len() {
+                    self.pop("rdi");
+                    self.writeln(&format!("mov QWORD PTR [rax + {}], rdi", i * 8));
+                }
+            }
+            Expr::Field(block, i) => {
+                self.expr(block);
+                self.writeln(&format!("mov rax, QWORD PTR [rax + {}]", i * 8));
+            }
+            Expr::SetField(block, i, value) => {
+                self.expr(value);
+                self.push("rax");
+                self.expr(block);
+                self.pop("rdi");
+                self.writeln(&format!("mov QWORD PTR [rax + {}], rdi", i * 8));
+                self.writeln("xor eax, eax");
+            }
+            // The elements follow the length.
+            Expr::Load(array, index, loc) => {
+                self.expr(index);
+                self.push("rax");
+                self.expr(array);
+                self.pop("rdi");
+                self.bounds_check(loc);
+                self.writeln("mov rax, QWORD PTR [rax + rdi * 8 + 8]");
+            }
+            Expr::Store(array, index, value, loc) => {
+                self.expr(value);
+                self.push("rax");
+                self.expr(index);
+                self.push("rax");
+                self.expr(array);
+                self.pop("rdi");
+                self.pop("rsi");
+                self.bounds_check(loc);
+                self.writeln("mov QWORD PTR [rax + rdi * 8 + 8], rsi");
+                self.writeln("xor eax, eax");
+            }
+            Expr::Seq(first, second) => {
+                self.expr(first);
+                self.expr(second);
+            }
+            Expr::If(cond, then, els) => {
+                let else_label = self.new_label();
+                let end = self.new_label();
+                self.expr(cond);
+                self.writeln("test rax, rax");
+                self.writeln(&format!("je {}", else_label));
+                self.expr(then);
+                self.writeln(&format!("jmp {}", end));
+                self.label(&else_label);
+                self.expr(els);
+                self.label(&end);
+            }
+            Expr::Switch(value, table, branches) => {
+                let end = self.new_label();
+                let labels = self.switch(value, table, branches.len());
+                for (label, branch) in labels.iter().zip(branches) {
+                    self.label(label);
+                    self.expr(branch);
+                    self.writeln(&format!("jmp {}", end));
+                }
+                self.label(&end);
+            }
+            Expr::MatchFailure(loc) => self.raise("tsuyu_raise_match_failure", loc),
+            Expr::UnOp(op, expr) => {
+                self.expr(expr);
+                match op {
+                    UnOp::Neg => self.writeln("neg rax"),
+                    UnOp::Not => self.writeln("xor rax, 1"),
+                }
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                self.expr(rhs);
+                self.push("rax");
+                self.expr(lhs);
+                self.pop("rdi");
+                self.binop(op);
+            }
+            // The runtime follows the shape of the type.
+            Expr::Compare(typ, lhs, rhs, loc) => {
+                self.expr(rhs);
+                self.push("rax");
+                self.expr(lhs);
+                self.pop("rsi");
+                self.writeln("mov rdi, rax");
+                let mut desc = String::new();
+                shape(typ, &mut desc);
+                self.write("lea rdx, ");
+                self.string_constant(&desc);
+                self.writeln("");
+                self.write("lea rcx, ");
+                self.string_constant(&loc.to_string());
+                self.writeln("");
+                self.call_aligned("tsuyu_compare");
+            }
+        }
+    }
+
+    // Evaluates the expression in tail position and returns from the function. Calls in tail
+    // position jump to the callee reusing the frame of the caller.
+    fn tail(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Let(i, value, body) => {
+                self.expr(value);
+                self.writeln(&format!("mov {}, rax", local(*i)));
+                self.tail(body);
+            }
+            Expr::Seq(first, second) => {
+                self.expr(first);
+                self.tail(second);
+            }
+            Expr::If(cond, then, els) => {
+                let else_label = self.new_label();
+                self.expr(cond);
+                self.writeln("test rax, rax");
+                self.writeln(&format!("je {}", else_label));
+                self.tail(then);
+                self.label(&else_label);
+                self.tail(els);
+            }
+            Expr::Switch(value, table, branches) => {
+                let labels = self.switch(value, table, branches.len());
+                for (label, branch) in labels.iter().zip(branches) {
+                    self.label(label);
+                    self.tail(branch);
+                }
+            }
+            Expr::Call(name, args, _) if self.can_tail_call(args.len()) => {
+                let args: Vec<_> = args.iter().collect();
+                self.push_tail_args(&args);
+                self.pop_tail_args(args.len());
+                self.writeln("leave");
+                self.writeln(&format!("jmp {}", name));
+            }
+            Expr::Apply(closure, args) if self.can_tail_call(args.len() + 1) => {
+                let mut all: Vec<_> = args.iter().collect();
+                all.push(closure);
+                self.push_tail_args(&all);
+
+                let generic = self.new_label();
+                self.check_arity(args.len(), &generic);
+                self.pop_tail_args(all.len());
+                self.writeln("leave");
+                self.writeln("jmp QWORD PTR [rax]");
+
+                self.depth += all.len();
+                self.label(&generic);
+                self.generic_apply(args.len());
+                self.depth -= all.len();
+                self.writeln("leave");
+                self.writeln("ret");
+            }
+            _ => {
+                self.expr(expr);
+                self.writeln("leave");
+                self.writeln("ret");
+            }
+        }
+    }
+
+    // The arguments on the stack are written over the parameters of the caller. So the callee
+    // cannot take more of them than the caller.
+    fn can_tail_call(&self, args: usize) -> bool {
+        args.saturating_sub(ARG_REGS.len()) <= self.stack_params
+    }
+
+    // Pushes the arguments evaluated from right to left without alignment.
+    fn push_tail_args(&mut self, args: &[&Expr]) {
+        for arg in args.iter().rev() {
+            self.expr(arg);
+            self.push("rax");
+        }
+    }
+
+    // Pops the arguments into the registers and the stack parameters of the caller.
+    fn pop_tail_args(&mut self, args: usize) {
+        self.pop_arg_regs(args);
+        for i in 0..args.saturating_sub(ARG_REGS.len()) {
+            self.pop("r11");
+            self.writeln(&format!("mov QWORD PTR [rbp + {}], r11", 16 + i * 8));
+        }
+    }
+
+    // Calls a function whose arguments are already in registers.
+    fn call_aligned(&mut self, name: &str) {
+        let padding = self.depth % 2 == 1;
+        if padding {
+            self.writeln("sub rsp, 8");
+        }
+        self.writeln(&format!("call {}", name));
+        if padding {
+            self.writeln("add rsp, 8");
+        }
+    }
+
+    // Pushes the arguments evaluated from right to left like ocamlopt, keeping rsp aligned at
+    // the call. Returns the number of words to be removed after the call.
+    fn push_args(&mut self, args: &[&Expr]) -> usize {
+        let stack_args = args.len().saturating_sub(ARG_REGS.len());
+        let padding = (self.depth + stack_args) % 2;
+        if padding > 0 {
+            self.writeln("sub rsp, 8");
+            self.depth += 1;
+        }
+        for arg in args.iter().rev() {
+            self.expr(arg);
+            self.push("rax");
+        }
+        stack_args + padding
+    }
+
+    fn pop_arg_regs(&mut self, args: usize) {
+        for reg in ARG_REGS.iter().take(args) {
+            self.pop(reg);
+        }
+    }
+
+    fn remove_args(&mut self, words: usize) {
+        if words > 0 {
+            self.writeln(&format!("add rsp, {}", words * 8));
+            self.depth -= words;
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) {
+        let words = self.push_args(&args.iter().collect::<Vec<_>>());
+        self.pop_arg_regs(args.len());
+        self.writeln(&format!("call {}", name));
+        self.remove_args(words);
+    }
+
+    // The closure is passed as the last argument if the number of arguments equals the arity.
+    // Otherwise the arguments on the stack are passed to the runtime as an array.
+    fn apply(&mut self, closure: &Expr, args: &[Expr]) {
+        let mut all: Vec<_> = args.iter().collect();
+        all.push(closure);
+        let words = self.push_args(&all);
+        let regs = all.len().min(ARG_REGS.len());
+
+        let generic = self.new_label();
+        let end = self.new_label();
+        self.check_arity(args.len(), &generic);
+        self.pop_arg_regs(regs);
+        self.writeln("call QWORD PTR [rax]");
+        self.writeln(&format!("jmp {}", end));
+
+        self.depth += regs;
+        self.label(&generic);
+        self.generic_apply(args.len());
+        self.writeln(&format!("add rsp, {}", regs * 8));
+        self.depth -= regs;
+        self.label(&end);
+        self.remove_args(words);
+    }
+
+    // Loads the closure pushed after the arguments into rax and jumps to the label unless it
+    // takes the number of arguments.
+    fn check_arity(&mut self, args: usize, label: &str) {
+        self.writeln(&format!("mov rax, QWORD PTR [rsp + {}]", args * 8));
+        self.writeln(&format!("cmp QWORD PTR [rax + 8], {}", args));
+        self.writeln(&format!("jne {}", label));
+    }
+
+    // Applies the closure in rax to the arguments on the top of the stack.
+    fn generic_apply(&mut self, args: usize) {
+        self.writeln("mov rdi, rax");
+        self.writeln(&format!("mov rsi, {}", args));
+        self.writeln("mov rdx, rsp");
+        self.call_aligned("tsuyu_apply");
+    }
+
+    // lhs is in rax and rhs is in rdi.
+    fn binop(&mut self, op: &BinOp) {
+        match op {
+            BinOp::Add => self.writeln("add rax, rdi"),
+            BinOp::Sub => self.writeln("sub rax, rdi"),
+            BinOp::Mul => self.writeln("imul rax, rdi"),
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                let set = match op {
+                    BinOp::Eq => "sete",
+                    BinOp::Ne => "setne",
+                    BinOp::Lt => "setl",
+                    BinOp::Le => "setle",
+                    BinOp::Gt => "setg",
+                    BinOp::Ge => "setge",
+                    _ => unreachable!(),
+                };
+                self.writeln("cmp rax, rdi");
+                self.writeln(&format!("{} al", set));
+                self.writeln("movzx eax, al");
+            }
+            BinOp::Div(loc) | BinOp::Mod(loc) => {
+                let is_div = matches!(op, BinOp::Div(_));
+                let non_zero = self.new_label();
+                let not_minus_one = self.new_label();
+                let end = self.new_label();
+
+                self.writeln("test rdi, rdi");
+                self.writeln(&format!("jne {}", non_zero));
+                self.raise("tsuyu_raise_division_by_zero", loc);
+
+                // idiv traps on min_int / -1. OCaml returns min_int for it.
+                self.label(&non_zero);
+                self.writeln("cmp rdi, -1");
+                self.writeln(&format!("jne {}", not_minus_one));
+                self.writeln(if is_div { "neg rax" } else { "xor eax, eax" });
+                self.writeln(&format!("jmp {}", end));
+
+                self.label(&not_minus_one);
+                self.writeln("cqo");
+                self.writeln("idiv rdi");
+                if !is_div {
+                    self.writeln("mov rax, rdx");
+                }
+                self.label(&end);
+            }
         }
     }
 }